@@ -1,4 +1,3 @@
-use core::panic;
 use std::{
     cell::RefCell,
     collections::HashMap,
@@ -11,13 +10,20 @@ use token::{Token, TokenLiteral};
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Statement {
     LetStatement(LetStatement),
+    DestructureStatement(DestructureStatement),
     ReturnStatement(ReturnStatement),
     ExpressStatement(Expression),
     MutateStatement(MutateStatement),
+    IndexAssignStatement(IndexAssignStatement),
     BlockStatement(BlockStatement),
     LoopStatement(BlockStatement),
+    WhileStatement(WhileStatement),
+    ForStatement(ForStatement),
+    ModStatement(ModStatement),
+    ImportStatement(ImportStatement),
     PostfixStatement(PostfixStatement),
     BreakStatement,
+    ContinueStatement,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,6 +34,7 @@ pub enum Expression {
     InfixExpression((Token, Box<Expression>, Box<Expression>)),
     IdentExpression(Token),
     IntExpression(Token),
+    FloatExpression(Token),
     StringExpression(Token),
     BoolExpression(Token),
     // Token, condition, consequence, alternative
@@ -48,6 +55,8 @@ pub enum Expression {
     ArrayExpression(Vec<Expression>),
     // left, index
     IndexExpression(Box<Expression>, Box<Expression>),
+    // module, member ie math.pi
+    MemberExpression(Box<Expression>, Token),
     HashLiteral(Map),
     UnknownExpression(Token),
 }
@@ -63,15 +72,19 @@ impl Hash for Expression {
             Expression::StringExpression(t) => t.hash(state),
             Expression::BoolExpression(t) => t.hash(state),
             Expression::IntExpression(t) => t.hash(state),
+            Expression::FloatExpression(t) => t.hash(state),
             Expression::InfixExpression(t) => t.0.hash(state),
-            _ => panic!("not allowed, {}", self.to_string()),
+            // keys that can't be hashed at runtime are rejected by the compiler,
+            // this only has to keep equal expressions hashing the same
+            _ => self.to_string().hash(state),
         };
     }
 }
 
 impl PartialEq for Map {
-    fn eq(&self, _: &Self) -> bool {
-        todo!()
+    // pairs are compared by key, so the order they were written in doesn't matter
+    fn eq(&self, other: &Self) -> bool {
+        self.pairs == other.pairs
     }
 }
 
@@ -93,6 +106,14 @@ pub struct MutateStatement {
     pub value: Expression,
 }
 
+// left[index] = value;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexAssignStatement {
+    pub left: Expression,
+    pub index: Expression,
+    pub value: Expression,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LetStatement {
     pub token: Token,
@@ -100,6 +121,48 @@ pub struct LetStatement {
     pub value: Expression,
 }
 
+// let [a, b] = value;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestructureStatement {
+    pub token: Token,
+    pub names: Vec<Token>,
+    pub value: Expression,
+}
+
+// while (condition) { body } or while (let name = condition) { body }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhileStatement {
+    pub token: Token,
+    pub name: Option<Token>,
+    pub condition: Expression,
+    pub body: BlockStatement,
+}
+
+// for (let name = value; condition; update) { body }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForStatement {
+    pub token: Token,
+    pub init: LetStatement,
+    pub condition: Expression,
+    pub update: Box<Statement>,
+    pub body: BlockStatement,
+}
+
+// mod name { let a = 1; let b = 2; }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModStatement {
+    pub token: Token,
+    pub name: Token,
+    pub members: Vec<LetStatement>,
+}
+
+// import "path.monkey";
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportStatement {
+    pub token: Token,
+    pub path: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReturnStatement {
     pub token: Token,
@@ -149,15 +212,69 @@ impl Display for Statement {
                     s.value
                 ));
             }
+            Self::DestructureStatement(s) => {
+                buffer.push_str(&format!(
+                    "{} [{}] = {};",
+                    s.token.token_literal(),
+                    s.names
+                        .iter()
+                        .map(|n| n.token_literal())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    s.value
+                ));
+            }
             Self::MutateStatement(s) => {
                 buffer.push_str(&format!("{} = {};", s.name.token_literal(), s.value));
             }
+            Self::IndexAssignStatement(s) => {
+                buffer.push_str(&format!("{}[{}] = {};", s.left, s.index, s.value));
+            }
             Self::ReturnStatement(s) => buffer.push_str(&s.to_string()),
             Self::ExpressStatement(s) => buffer.push_str(&s.to_string()),
             Self::PostfixStatement(s) => buffer.push_str(&s.to_string()),
             Self::BlockStatement(s) => buffer.push_str(&s.to_string()),
             Self::LoopStatement(s) => buffer.push_str(&s.to_string()),
+            Self::WhileStatement(s) => {
+                let condition = match &s.name {
+                    Some(name) => format!("let {} = {}", name.token_literal(), s.condition),
+                    None => s.condition.to_string(),
+                };
+                buffer.push_str(&format!(
+                    "{} ({}) {}",
+                    s.token.token_literal(),
+                    condition,
+                    s.body
+                ));
+            }
+            Self::ForStatement(s) => {
+                buffer.push_str(&format!(
+                    "{} (let {} = {}; {}; {}) {}",
+                    s.token.token_literal(),
+                    s.init.name.token_literal(),
+                    s.init.value,
+                    s.condition,
+                    s.update.to_string().trim_end_matches(';'),
+                    s.body
+                ));
+            }
+            Self::ModStatement(s) => {
+                buffer.push_str(&format!(
+                    "{} {} {{ {} }}",
+                    s.token.token_literal(),
+                    s.name.token_literal(),
+                    s.members
+                        .iter()
+                        .map(|m| format!("let {} = {};", m.name.token_literal(), m.value))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ));
+            }
+            Self::ImportStatement(s) => {
+                buffer.push_str(&format!("{} \"{}\";", s.token.token_literal(), s.path));
+            }
             Self::BreakStatement => buffer.push_str("break"),
+            Self::ContinueStatement => buffer.push_str("continue"),
         };
 
         write!(f, "{buffer}")
@@ -168,7 +285,9 @@ impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut buffer = String::new();
         match self {
-            Self::IntExpression(t) => buffer.push_str(&t.token_literal()),
+            Self::IntExpression(t) | Self::FloatExpression(t) => {
+                buffer.push_str(&t.token_literal())
+            }
             Self::InfixExpression(t) => {
                 buffer.push_str(&format!("({} {} {})", t.1, t.0.token_literal(), t.2))
             }
@@ -222,6 +341,9 @@ impl Display for Expression {
             Self::IndexExpression(left, index) => {
                 buffer.push_str(&format!("({}[{}])", left, index))
             }
+            Self::MemberExpression(left, member) => {
+                buffer.push_str(&format!("{}.{}", left, member.token_literal()))
+            }
             Self::HashLiteral(map) => {
                 buffer.push_str(&format!(
                     "{{{}}}",
@@ -278,7 +400,7 @@ mod test {
             .statements
             .push(Statement::LetStatement(LetStatement {
                 token: Token::Let,
-                name: Token::Ident("test".to_string()),
+                name: Token::Ident("test".into()),
                 value: Expression::default(),
             }));
 
@@ -293,8 +415,8 @@ mod test {
             .statements
             .push(Statement::LetStatement(LetStatement {
                 token: Token::Let,
-                name: Token::Ident("myVar".to_string()),
-                value: Expression::IdentExpression(Token::Ident("anotherVar".to_string())),
+                name: Token::Ident("myVar".into()),
+                value: Expression::IdentExpression(Token::Ident("anotherVar".into())),
             }));
 
         assert_eq!(program.to_string(), "let myVar = anotherVar;");
@@ -309,7 +431,7 @@ mod test {
                 Token::Bang,
                 Box::new(Expression::PrefixExpression((
                     Token::Minus,
-                    Box::new(Expression::IntExpression(Token::Ident("a".to_string()))),
+                    Box::new(Expression::IntExpression(Token::Ident("a".into()))),
                 ))),
             ))));
 
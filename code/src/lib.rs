@@ -95,7 +95,9 @@ pub fn read_operands(op: &Op, ins: &[u8]) -> (Vec<u32>, usize) {
     (operands, offset)
 }
 
-pub fn instruction_to_string(ins: &Instructions) -> String {
+/// A readable listing of `ins`, one instruction per line with its byte
+/// offset and operands, e.g. `0000 OpConstant 1`.
+pub fn instruction_to_string(ins: &[u8]) -> String {
     let mut out = String::new();
 
     let mut i = 0;
@@ -224,7 +226,7 @@ mod test {
             bytes_read: usize,
         }
 
-        let tests = vec![
+        let tests = [
             Test {
                 op: Op::Constant,
                 operands: vec![65535],
@@ -248,7 +250,7 @@ mod test {
             }
         }
 
-        let tests = vec![Test {
+        let tests = [Test {
             op: Op::GetLocal,
             operands: vec![255],
             bytes_read: 1,
@@ -50,6 +50,13 @@ pub enum Op {
 
     Closure,
     CurrentClosure,
+
+    Destructure,
+
+    Mod,
+    SetIndex,
+
+    CallGlobal,
 }
 
 impl From<Op> for u8 {
@@ -100,6 +107,10 @@ impl Display for Op {
             Self::CurrentClosure => write!(f, "OpCurrentClosure"),
             Self::Or => write!(f, "OpOr"),
             Self::And => write!(f, "OpAnd"),
+            Self::Destructure => write!(f, "OpDestructure"),
+            Self::Mod => write!(f, "OpMod"),
+            Self::SetIndex => write!(f, "OpSetIndex"),
+            Self::CallGlobal => write!(f, "OpCallGlobal"),
         }
     }
 }
@@ -112,6 +123,7 @@ impl Op {
             | Self::Sub
             | Self::Mul
             | Self::Div
+            | Self::Mod
             | Self::True
             | Self::False
             | Self::Equal
@@ -120,6 +132,7 @@ impl Op {
             | Self::Minus
             | Self::Bang
             | Self::Index
+            | Self::SetIndex
             | Self::ReturnValue
             | Self::Return
             | Self::CurrentClosure
@@ -127,9 +140,12 @@ impl Op {
             | Self::And
             | Self::Null => vec![],
 
-            Self::GetLocal | Self::SetLocal | Self::Call | Self::GetBuiltin | Self::GetFree => {
-                vec![1]
-            }
+            Self::GetLocal
+            | Self::SetLocal
+            | Self::Call
+            | Self::GetBuiltin
+            | Self::GetFree
+            | Self::Destructure => vec![1],
 
             Self::Constant
             | Self::Jump
@@ -139,7 +155,7 @@ impl Op {
             | Self::Array
             | Self::Hash => vec![2],
 
-            Self::Closure => vec![2, 1],
+            Self::Closure | Self::CallGlobal => vec![2, 1],
         }
     }
 }
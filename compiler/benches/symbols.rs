@@ -0,0 +1,62 @@
+//! Times lexing, parsing and compiling a program that's mostly identifiers,
+//! where the cost is in handling names. Run with `cargo bench -p compiler`.
+use compiler::{Compiler, symbol_table::SymbolTable};
+use lexer::Lexer;
+use parser::Parser;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn run(input: &str) -> Duration {
+    let start = Instant::now();
+
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    start.elapsed()
+}
+
+// identifiers can't hold digits, so spell the number out in letters
+fn name(prefix: &str, mut i: usize) -> String {
+    let mut name = prefix.to_string();
+    loop {
+        name.push((b'a' + (i % 26) as u8) as char);
+        i /= 26;
+        if i == 0 {
+            return name;
+        }
+    }
+}
+
+fn main() {
+    // a few hundred globals, each read over and over inside closures
+    let mut input = String::new();
+    for i in 0..300 {
+        input.push_str(&format!("let {} = {i};\n", name("variable_", i)));
+    }
+    for i in 1..300 {
+        let (function, prev, var) = (
+            name("function_", i),
+            name("variable_", i - 1),
+            name("variable_", i),
+        );
+        input.push_str(&format!(
+            "let {function} = fn(argument_one, argument_two) {{ \
+                let local_value = argument_one + {prev}; \
+                fn(inner) {{ inner + local_value + argument_two + {var} }} \
+            }};\n"
+        ));
+    }
+
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        total += run(&input);
+    }
+
+    println!("symbol heavy program: {:?} per run", total / ITERATIONS);
+}
@@ -1,9 +1,14 @@
 pub mod symbol_table;
-use ast::{self, BlockStatement, Expression, LetStatement, ReturnStatement, Statement};
+use ast::{
+    self, BlockStatement, DestructureStatement, Expression, ForStatement, LetStatement,
+    ModStatement, ReturnStatement, Statement, WhileStatement,
+};
 use code::{self, Op, make};
-use object::{self, ObjectType};
+use object::{self, HashPair, ObjectType};
+use std::collections::HashMap;
 use symbol_table::{
-    BUILTIN_SCOPE, FREE_SCOPE, FUNCTION_SCOPE, GLOBAL_SCOPE, LOCAL_SCOPE, Symbol, SymbolTable,
+    BUILTIN_SCOPE, FREE_SCOPE, FUNCTION_SCOPE, GLOBAL_SCOPE, LOCAL_SCOPE, ScopeInfo, Symbol,
+    SymbolTable,
 };
 use thiserror::Error;
 use token::{Token, TokenLiteral};
@@ -13,7 +18,10 @@ pub struct Compiler<'a> {
     pub symbol_table: SymbolTable,
     scopes: Vec<CompilationScope>,
     scope_index: usize,
-    break_position: Vec<usize>,
+    // problems worth reporting that don't stop compilation
+    pub warnings: Vec<String>,
+    // build arrays and hashes made only of literals at compile time
+    fold_literals: bool,
 }
 
 pub struct Environment {
@@ -43,6 +51,16 @@ struct CompilationScope {
     last_instruction: EmittedInstruction,
     // instruction before last_instruction
     previous_instruction: EmittedInstruction,
+    // the loops being compiled in this function, innermost last
+    loops: Vec<LoopJumps>,
+}
+
+/// Jumps out of, and back round, a loop that can only be patched once the
+/// whole loop has been compiled.
+#[derive(Clone, Debug, Default)]
+struct LoopJumps {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -63,6 +81,22 @@ pub enum CompilerError {
     InvalidToken(Token),
     #[error("can't mutate a non variable")]
     InvalidMutation,
+    #[error("cannot assign to undeclared variable: {0}")]
+    UndeclaredAssignment(String),
+    #[error("duplicate parameter name: {0}")]
+    DuplicateParameter(String),
+    #[error("break outside of a loop")]
+    BreakOutsideLoop,
+    #[error("continue outside of a loop")]
+    ContinueOutsideLoop,
+    #[error("undefined member: {0}")]
+    UndefinedMember(String),
+    #[error("unresolved import: {0}")]
+    UnresolvedImport(String),
+    #[error("inconsistent symbol table: {0}")]
+    InconsistentSymbolTable(String),
+    #[error("unusable as a hash key: {0}")]
+    UnusableHashKey(String),
 }
 
 pub trait Compile {
@@ -72,9 +106,9 @@ pub trait Compile {
 impl Compile for Expression {
     fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
         match self {
-            Self::IntExpression(t) => {
-                let integer = t.to_owned().into();
-                let i = compiler.add_constant(integer);
+            Self::IntExpression(t) | Self::FloatExpression(t) => {
+                let number = t.to_owned().into();
+                let i = compiler.add_constant(number);
                 let _ = compiler.emit(&code::Op::Constant, vec![i]);
             }
             Self::BoolExpression(t) => match t {
@@ -102,6 +136,7 @@ impl Compile for Expression {
                     Token::Plus => compiler.emit(&Op::Add, vec![]),
                     Token::Minus => compiler.emit(&Op::Sub, vec![]),
                     Token::Slash => compiler.emit(&Op::Div, vec![]),
+                    Token::Percent => compiler.emit(&Op::Mod, vec![]),
                     Token::Asterisk => compiler.emit(&Op::Mul, vec![]),
                     Token::Gt => compiler.emit(&Op::GreaterThan, vec![]),
                     Token::Eq => compiler.emit(&Op::Equal, vec![]),
@@ -179,6 +214,7 @@ impl Compile for Expression {
                 let i = compiler.add_constant(string.to_owned().into());
                 compiler.emit(&Op::Constant, vec![i]);
             }
+            Self::ArrayExpression(_) | Self::HashLiteral(_) if compiler.fold_literal(self) => {}
             Self::ArrayExpression(expressions) => {
                 for expression in expressions {
                     expression.compile(compiler)?;
@@ -187,6 +223,16 @@ impl Compile for Expression {
                 compiler.emit(&Op::Array, vec![expressions.len()]);
             }
             Self::HashLiteral(hash) => {
+                // these can never be hashed, whatever they evaluate to
+                if let Some(key) = hash.pairs.keys().find(|key| {
+                    matches!(
+                        key,
+                        Self::FunctionLiteral(..) | Self::ArrayExpression(_) | Self::HashLiteral(_)
+                    )
+                }) {
+                    return Err(CompilerError::UnusableHashKey(key.to_string()));
+                }
+
                 // Sort values in test so the tests can be deterministic
                 #[cfg(test)]
                 {
@@ -194,7 +240,7 @@ impl Compile for Expression {
                     for kv in &hash.pairs {
                         keys.push(kv);
                     }
-                    keys.sort_by(|a, b| a.0.to_string().cmp(&b.0.to_string()));
+                    keys.sort_by_key(|a| a.0.to_string());
 
                     for kv in keys {
                         kv.0.compile(compiler)?;
@@ -219,6 +265,19 @@ impl Compile for Expression {
                 compiler.emit(&Op::Index, vec![]);
             }
             Self::FunctionLiteral(_, params, block, name) => {
+                // checked before entering the function's scope, so an error
+                // doesn't leave the compiler inside it
+                let mut names = Vec::with_capacity(params.len());
+                for (i, param) in params.iter().enumerate() {
+                    if params[..i].contains(param) {
+                        return Err(CompilerError::DuplicateParameter(param.token_literal()));
+                    }
+                    let Token::Ident(name) = param else {
+                        return Err(CompilerError::InvalidToken(param.clone()));
+                    };
+                    names.push(name);
+                }
+
                 compiler.enter_scope();
 
                 if name.borrow().is_some() {
@@ -227,11 +286,14 @@ impl Compile for Expression {
                         .define_function_name(name.borrow().clone().unwrap());
                 }
 
-                for param in params {
-                    compiler.symbol_table.define(param.token_literal());
+                for name in names {
+                    compiler.symbol_table.define(name.clone());
                 }
 
-                block.compile(compiler)?;
+                if let Err(err) = block.compile(compiler) {
+                    compiler.leave_scope();
+                    return Err(err);
+                }
 
                 if compiler.last_instruction_is(&Op::Pop) {
                     compiler.replace_last_pop_with_return();
@@ -260,7 +322,36 @@ impl Compile for Expression {
                 for arg in args {
                     arg.compile(compiler)?;
                 }
-                compiler.emit(&Op::Call, vec![args.len()]);
+
+                // a call through a global the arguments can't reassign gets an
+                // instruction the vm caches its target for
+                let global = match function.as_ref() {
+                    Expression::IdentExpression(Token::Ident(name))
+                        if !args.iter().any(may_run_code) =>
+                    {
+                        compiler
+                            .symbol_table
+                            .resolve(name)
+                            .filter(|symbol| symbol.scope == GLOBAL_SCOPE)
+                    }
+                    _ => None,
+                };
+                match global {
+                    Some(symbol) => compiler.emit(&Op::CallGlobal, vec![symbol.index, args.len()]),
+                    None => compiler.emit(&Op::Call, vec![args.len()]),
+                };
+            }
+            Self::MemberExpression(module, member) => {
+                let qualified = format!("{module}.{}", member.token_literal());
+                let symbol = match module.as_ref() {
+                    Expression::IdentExpression(_) => compiler.symbol_table.resolve(&qualified),
+                    _ => None,
+                };
+
+                match symbol {
+                    Some(symbol) => compiler.load_symbol(&symbol),
+                    None => return Err(CompilerError::UndefinedMember(qualified)),
+                }
             }
             _ => panic!("no done yet {self:?}"),
         }
@@ -273,6 +364,9 @@ impl Compile for Statement {
     fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
         match self {
             Self::LetStatement(let_statement) => let_statement.compile(compiler),
+            Self::DestructureStatement(destructure_statement) => {
+                destructure_statement.compile(compiler)
+            }
             Self::ExpressStatement(expression) => {
                 expression.compile(compiler)?;
                 compiler.emit(&code::Op::Pop, vec![]);
@@ -282,51 +376,62 @@ impl Compile for Statement {
             Self::BlockStatement(block_statement) => block_statement.compile(compiler),
             Self::ReturnStatement(return_statement) => return_statement.compile(compiler),
             Self::LoopStatement(block_statement) => {
+                if !may_exit(block_statement) {
+                    compiler
+                        .warnings
+                        .push("loop never exits, it has no `break` or `return`".to_string());
+                }
+
                 let loop_start = compiler.current_instructions().len();
+                compiler.enter_loop();
                 block_statement.compile(compiler)?;
                 compiler.emit(&Op::Jump, vec![loop_start]);
-
-                let loop_end = compiler.current_instructions().len();
-                let break_position = compiler
-                    .break_position
-                    .pop()
-                    .ok_or(CompilerError::UndefinedVariable)?;
-                compiler.change_operand(break_position, loop_end);
+                compiler.leave_loop(loop_start);
 
                 Ok(())
             }
+            Self::WhileStatement(while_statement) => while_statement.compile(compiler),
+            Self::ForStatement(for_statement) => for_statement.compile(compiler),
+            Self::ModStatement(mod_statement) => mod_statement.compile(compiler),
+            // imports are swapped for the imported definitions before compiling
+            Self::ImportStatement(import) => {
+                Err(CompilerError::UnresolvedImport(import.path.clone()))
+            }
             Self::BreakStatement => {
+                if compiler.current_loop().is_none() {
+                    return Err(CompilerError::BreakOutsideLoop);
+                }
+
                 // set bogus for now and commit its position for later
                 let break_position = compiler.emit(&Op::Jump, vec![99]);
-                compiler.break_position.push(break_position);
+                if let Some(jumps) = compiler.current_loop() {
+                    jumps.breaks.push(break_position);
+                }
 
                 Ok(())
             }
-            Self::MutateStatement(mutate_statement) => {
-                let name = if let Token::Ident(name) = &mutate_statement.name {
-                    name
-                } else {
-                    return Err(CompilerError::InvalidToken(mutate_statement.name.clone()));
-                };
-
-                let symbol = compiler
-                    .symbol_table
-                    .resolve(name)
-                    .ok_or(CompilerError::UndefinedVariable)?;
-
-                mutate_statement.value.compile(compiler)?;
+            Self::ContinueStatement => {
+                if compiler.current_loop().is_none() {
+                    return Err(CompilerError::ContinueOutsideLoop);
+                }
 
-                match symbol.scope {
-                    GLOBAL_SCOPE => compiler.emit(&Op::SetGlobal, vec![symbol.index]),
-                    LOCAL_SCOPE => compiler.emit(&Op::SetLocal, vec![symbol.index]),
-                    FREE_SCOPE | FUNCTION_SCOPE | BUILTIN_SCOPE => {
-                        return Err(CompilerError::InvalidMutation);
-                    }
-                    _ => return Err(CompilerError::UndefinedVariable),
-                };
+                let continue_position = compiler.emit(&Op::Jump, vec![99]);
+                if let Some(jumps) = compiler.current_loop() {
+                    jumps.continues.push(continue_position);
+                }
 
                 Ok(())
             }
+            Self::MutateStatement(mutate_statement) => {
+                let symbol = compiler.assignable_symbol(&mutate_statement.name)?;
+                mutate_statement.value.compile(compiler)?;
+                compiler.store_symbol(&symbol)
+            }
+            Self::IndexAssignStatement(statement) => {
+                compile_index_assignment(compiler, &statement.left, &statement.index, &|compiler| {
+                    statement.value.compile(compiler)
+                })
+            }
             Self::PostfixStatement(postfix_statement) => {
                 let name = if let Token::Ident(name) = &postfix_statement.name {
                     name
@@ -337,7 +442,7 @@ impl Compile for Statement {
                 let symbol = compiler
                     .symbol_table
                     .resolve(name)
-                    .ok_or(CompilerError::UndefinedVariable)?;
+                    .ok_or_else(|| CompilerError::UndeclaredAssignment(name.to_string()))?;
 
                 // Load the variable
                 match symbol.scope {
@@ -350,7 +455,7 @@ impl Compile for Statement {
                 };
 
                 // Load in a 1
-                let i = compiler.add_constant(ObjectType::IntegerObj(1.0));
+                let i = compiler.add_constant(ObjectType::IntegerObj(1));
                 compiler.emit(&Op::Constant, vec![i]);
 
                 // Determine whether to add or subtract
@@ -397,7 +502,7 @@ impl Compile for LetStatement {
             return Err(CompilerError::InvalidToken(self.name.clone()));
         };
 
-        let symbol = compiler.symbol_table.define(name.to_string());
+        let symbol = compiler.symbol_table.define(name.clone());
 
         self.value.compile(compiler)?;
 
@@ -411,32 +516,336 @@ impl Compile for LetStatement {
     }
 }
 
+impl Compile for WhileStatement {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
+        if self.condition == Expression::BoolExpression(Token::True) && !may_exit(&self.body) {
+            compiler
+                .warnings
+                .push("while (true) never exits, it has no `break` or `return`".to_string());
+        }
+
+        // the condition is checked at the top of every pass, a bound name is
+        // rebound to it each time
+        let loop_start = compiler.current_instructions().len();
+        match &self.name {
+            Some(Token::Ident(name)) => {
+                // defined before the value, like a let, so it's in scope below
+                let symbol = compiler.symbol_table.define(name.clone());
+                self.condition.compile(compiler)?;
+                if symbol.scope == GLOBAL_SCOPE {
+                    compiler.emit(&Op::SetGlobal, vec![symbol.index]);
+                } else {
+                    compiler.emit(&Op::SetLocal, vec![symbol.index]);
+                }
+                compiler.load_symbol(&symbol);
+            }
+            Some(name) => return Err(CompilerError::InvalidToken(name.clone())),
+            None => self.condition.compile(compiler)?,
+        }
+        let exit_position = compiler.emit(&Op::JumpNotTruthy, vec![9999]);
+
+        compiler.enter_loop();
+        self.body.compile(compiler)?;
+        compiler.emit(&Op::Jump, vec![loop_start]);
+
+        let loop_end = compiler.leave_loop(loop_start);
+        compiler.change_operand(exit_position, loop_end);
+
+        Ok(())
+    }
+}
+
+impl Compile for ForStatement {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
+        let Token::Ident(name) = &self.init.name else {
+            return Err(CompilerError::InvalidToken(self.init.name.clone()));
+        };
+
+        // the loop variable gets its own slot for the length of the loop, so
+        // it can't clobber a variable of the same name, say an outer loop's
+        self.init.value.compile(compiler)?;
+        let (symbol, previous) = compiler.symbol_table.shadow(name.clone());
+        compiler.store_symbol(&symbol)?;
+
+        let loop_start = compiler.current_instructions().len();
+        self.condition.compile(compiler)?;
+        let exit_position = compiler.emit(&Op::JumpNotTruthy, vec![9999]);
+
+        compiler.enter_loop();
+        self.body.compile(compiler)?;
+        // continue still runs the update before checking the condition again
+        let update_start = compiler.current_instructions().len();
+        self.update.compile(compiler)?;
+        compiler.emit(&Op::Jump, vec![loop_start]);
+
+        let loop_end = compiler.leave_loop(update_start);
+        compiler.change_operand(exit_position, loop_end);
+
+        compiler.symbol_table.restore(name, previous);
+
+        Ok(())
+    }
+}
+
+impl Compile for ModStatement {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
+        let Token::Ident(module) = &self.name else {
+            return Err(CompilerError::InvalidToken(self.name.clone()));
+        };
+
+        // members are globals or locals like any other, reachable by their
+        // plain names inside the mod and as module.member after it
+        let mut shadowed = Vec::new();
+        for member in self.members.iter() {
+            let Token::Ident(name) = &member.name else {
+                return Err(CompilerError::InvalidToken(member.name.clone()));
+            };
+
+            let (symbol, previous) = compiler.symbol_table.shadow(name.clone());
+            shadowed.push((name, previous));
+
+            member.value.compile(compiler)?;
+            compiler.store_symbol(&symbol)?;
+            compiler.symbol_table.define_member(module, &symbol);
+        }
+
+        for (name, previous) in shadowed.into_iter().rev() {
+            compiler.symbol_table.restore(name, previous);
+        }
+
+        Ok(())
+    }
+}
+
+impl Compile for DestructureStatement {
+    fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
+        self.value.compile(compiler)?;
+
+        // leaves the elements on the stack with the first one on top
+        compiler.emit(&Op::Destructure, vec![self.names.len()]);
+
+        for name in self.names.iter() {
+            let name = if let Token::Ident(name) = name {
+                name
+            } else {
+                return Err(CompilerError::InvalidToken(name.clone()));
+            };
+
+            let symbol = compiler.symbol_table.define(name.clone());
+
+            if symbol.scope == GLOBAL_SCOPE {
+                compiler.emit(&Op::SetGlobal, vec![symbol.index]);
+            } else {
+                compiler.emit(&Op::SetLocal, vec![symbol.index]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Compile for BlockStatement {
     fn compile(&self, compiler: &mut Compiler) -> Result<(), CompilerError> {
-        for statement in self.statements.iter() {
+        for (i, statement) in self.statements.iter().enumerate() {
             statement.compile(compiler)?;
+
+            let exit = match statement {
+                Statement::BreakStatement => "break",
+                Statement::ContinueStatement => "continue",
+                Statement::ReturnStatement(_) => "return",
+                _ => continue,
+            };
+            // nothing after the jump out can run, so don't bother emitting it
+            if i + 1 < self.statements.len() {
+                compiler
+                    .warnings
+                    .push(format!("unreachable code after `{exit}`"));
+            }
+            break;
         }
 
         Ok(())
     }
 }
 
+/// Compiles `left[index] = value` as building the updated collection and
+/// storing it back where `left` came from. A nested target such as
+/// `a[0][1] = v` becomes `a[0] = <a[0] with [1] set to v>`, all the way out to
+/// the variable at the bottom.
+fn compile_index_assignment(
+    compiler: &mut Compiler,
+    left: &Expression,
+    index: &Expression,
+    value: &dyn Fn(&mut Compiler) -> Result<(), CompilerError>,
+) -> Result<(), CompilerError> {
+    let updated = |compiler: &mut Compiler| {
+        left.compile(compiler)?;
+        index.compile(compiler)?;
+        value(compiler)?;
+        compiler.emit(&Op::SetIndex, vec![]);
+        Ok(())
+    };
+
+    match left {
+        Expression::IdentExpression(name) => {
+            let symbol = compiler.assignable_symbol(name)?;
+            updated(compiler)?;
+            compiler.store_symbol(&symbol)
+        }
+        Expression::IndexExpression(outer, outer_index) => {
+            compile_index_assignment(compiler, outer, outer_index, &updated)
+        }
+        _ => Err(CompilerError::InvalidMutation),
+    }
+}
+
+/// Whether there's a `break` or `return` anywhere in `block`, looking through
+/// conditionals and nested loops. Deliberately generous, a `break` out of an
+/// inner loop still counts, so the infinite loop warning only fires when
+/// there's plainly no way out.
+fn may_exit(block: &BlockStatement) -> bool {
+    block.statements.iter().any(|statement| match statement {
+        Statement::BreakStatement | Statement::ReturnStatement(_) => true,
+        Statement::BlockStatement(block) | Statement::LoopStatement(block) => may_exit(block),
+        Statement::WhileStatement(while_statement) => may_exit(&while_statement.body),
+        Statement::ForStatement(for_statement) => may_exit(&for_statement.body),
+        Statement::ExpressStatement(Expression::IfExpression(_, consequence, alternative)) => {
+            may_exit(consequence) || alternative.as_ref().is_some_and(|alt| may_exit(alt))
+        }
+        _ => false,
+    })
+}
+
+fn check_global_symbol_table(symbol_table: &SymbolTable) -> Result<(), CompilerError> {
+    let inconsistent = |why: String| Err(CompilerError::InconsistentSymbolTable(why));
+
+    if symbol_table.outer.is_some() {
+        return inconsistent("not the global scope".to_string());
+    }
+    if !symbol_table.free_symbols.borrow().is_empty() {
+        return inconsistent("the global scope has free variables".to_string());
+    }
+
+    for scope in symbol_table.snapshot() {
+        for symbol in scope.symbols {
+            match symbol.scope {
+                GLOBAL_SCOPE if symbol.index >= symbol_table.num_definitions => {
+                    return inconsistent(format!(
+                        "{} is in global slot {} but only {} are defined",
+                        symbol.name(),
+                        symbol.index,
+                        symbol_table.num_definitions
+                    ));
+                }
+                GLOBAL_SCOPE | BUILTIN_SCOPE => {}
+                scope => {
+                    return inconsistent(format!(
+                        "{} is a {scope} symbol in the global scope",
+                        symbol.name()
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether evaluating `expression` can run any code, calls and the blocks of
+/// `if` being the only ways to. Function literals only build the function.
+fn may_run_code(expression: &Expression) -> bool {
+    match expression {
+        Expression::CallExpression(..) | Expression::IfExpression(..) => true,
+        Expression::PrefixExpression((_, right)) => may_run_code(right),
+        Expression::InfixExpression((_, left, right))
+        | Expression::IndexExpression(left, right) => may_run_code(left) || may_run_code(right),
+        Expression::ArrayExpression(items) => items.iter().any(may_run_code),
+        Expression::HashLiteral(hash) => hash
+            .pairs
+            .iter()
+            .any(|(key, value)| may_run_code(key) || may_run_code(value)),
+        Expression::MemberExpression(module, _) => may_run_code(module),
+        Expression::IdentExpression(_)
+        | Expression::IntExpression(_)
+        | Expression::FloatExpression(_)
+        | Expression::StringExpression(_)
+        | Expression::BoolExpression(_)
+        | Expression::FunctionLiteral(..)
+        | Expression::UnknownExpression(_) => false,
+    }
+}
+
+/// The value of an expression made up only of literals, or `None` if anything
+/// in it has to be worked out at runtime. Names are never looked up, so a
+/// definition that refers to itself, `let x = x + 1;`, can't send folding in
+/// circles, it's just left for runtime.
+fn literal_constant(expression: &Expression) -> Option<ObjectType> {
+    match expression {
+        Expression::IntExpression(t)
+        | Expression::FloatExpression(t)
+        | Expression::StringExpression(t)
+        | Expression::BoolExpression(t) => Some(t.clone().into()),
+        Expression::ArrayExpression(items) => items
+            .iter()
+            .map(literal_constant)
+            .collect::<Option<Vec<_>>>()
+            .map(ObjectType::ArrayObj),
+        Expression::HashLiteral(hash) => {
+            let mut pairs = HashMap::new();
+            for (key, value) in hash.pairs.iter() {
+                let key = literal_constant(key)?;
+                // leave unusable keys for the vm to report
+                let hash_key = key.hash().ok()?;
+                let value = literal_constant(value)?;
+                pairs.insert(hash_key, HashPair { key, value });
+            }
+            Some(ObjectType::HashObj(pairs))
+        }
+        _ => None,
+    }
+}
+
 impl<'a> Compiler<'a> {
-    pub fn new(constants: &'a mut Vec<ObjectType>, mut symbol_table: SymbolTable) -> Self {
+    /// Fails when `symbol_table` couldn't have come from compiling at the top
+    /// level, say one left inside a function or pointing a global at a slot it
+    /// never defined, as anything compiled against it would use the wrong slots.
+    pub fn new(
+        constants: &'a mut Vec<ObjectType>,
+        mut symbol_table: SymbolTable,
+    ) -> Result<Self, CompilerError> {
+        check_global_symbol_table(&symbol_table)?;
+
         for (i, v) in object::BUILTINS.iter().enumerate() {
             symbol_table.define_builtin(i, &v.name);
         }
-        Self {
+        Ok(Self {
             constants,
             symbol_table,
             scopes: vec![CompilationScope {
                 instructions: Vec::new(),
                 last_instruction: EmittedInstruction::default(),
                 previous_instruction: EmittedInstruction::default(),
+                loops: Vec::new(),
             }],
             scope_index: 0,
-            break_position: Vec::new(),
-        }
+            warnings: Vec::new(),
+            fold_literals: true,
+        })
+    }
+
+    /// Turns on or off building array and hash literals made up only of
+    /// literals into a single constant. On by default.
+    pub fn with_literal_folding(mut self, fold: bool) -> Self {
+        self.fold_literals = fold;
+        self
+    }
+
+    /// Appends to `instructions` compiled earlier rather than starting an
+    /// empty program, so the repl can grow one program line by line. Pair it
+    /// with the constants and symbol table those instructions were built with.
+    pub fn with_instructions(mut self, instructions: code::Instructions) -> Self {
+        self.scopes[0].instructions = instructions;
+        self
     }
 
     pub fn compile(&mut self, node: ast::Program) -> Result<(), CompilerError> {
@@ -447,11 +856,64 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Shows how names currently resolve in each active scope, outermost
+    /// first. Handy for tools that visualise variable resolution.
+    pub fn scope_snapshot(&self) -> Vec<ScopeInfo> {
+        self.symbol_table.snapshot()
+    }
+
+    /// Emits `expression` as one constant if it can be built at compile time,
+    /// returning whether it was.
+    fn fold_literal(&mut self, expression: &Expression) -> bool {
+        if !self.fold_literals {
+            return false;
+        }
+        let Some(constant) = literal_constant(expression) else {
+            return false;
+        };
+
+        let i = self.add_constant(constant);
+        self.emit(&Op::Constant, vec![i]);
+        true
+    }
+
+    fn enter_loop(&mut self) {
+        self.scopes[self.scope_index]
+            .loops
+            .push(LoopJumps::default());
+    }
+
+    /// Patches the innermost loop's breaks to just past its last instruction
+    /// and its continues to `continue_target`, returning where the loop ends.
+    /// A loop may have no breaks at all if it's left with a return.
+    fn leave_loop(&mut self, continue_target: usize) -> usize {
+        let loop_end = self.current_instructions().len();
+        let jumps = self.scopes[self.scope_index]
+            .loops
+            .pop()
+            .unwrap_or_default();
+
+        for break_position in jumps.breaks {
+            self.change_operand(break_position, loop_end);
+        }
+        for continue_position in jumps.continues {
+            self.change_operand(continue_position, continue_target);
+        }
+
+        loop_end
+    }
+
+    // only loops in the function being compiled, a break can't leave a function
+    fn current_loop(&mut self) -> Option<&mut LoopJumps> {
+        self.scopes[self.scope_index].loops.last_mut()
+    }
+
     fn enter_scope(&mut self) {
         let scope = CompilationScope {
             instructions: Vec::new(),
             last_instruction: EmittedInstruction::default(),
             previous_instruction: EmittedInstruction::default(),
+            loops: Vec::new(),
         };
 
         self.scopes.push(scope);
@@ -558,6 +1020,31 @@ impl<'a> Compiler<'a> {
         pos_new_instruction
     }
 
+    /// Resolves a name that's about to be assigned to.
+    fn assignable_symbol(&self, name: &Token) -> Result<Symbol, CompilerError> {
+        let Token::Ident(name) = name else {
+            return Err(CompilerError::InvalidToken(name.clone()));
+        };
+
+        self.symbol_table
+            .resolve(name)
+            .ok_or_else(|| CompilerError::UndeclaredAssignment(name.to_string()))
+    }
+
+    /// Pops the top of the stack into an existing variable.
+    fn store_symbol(&mut self, symbol: &Symbol) -> Result<(), CompilerError> {
+        match symbol.scope {
+            GLOBAL_SCOPE => self.emit(&Op::SetGlobal, vec![symbol.index]),
+            LOCAL_SCOPE => self.emit(&Op::SetLocal, vec![symbol.index]),
+            FREE_SCOPE | FUNCTION_SCOPE | BUILTIN_SCOPE => {
+                return Err(CompilerError::InvalidMutation);
+            }
+            _ => return Err(CompilerError::UndefinedVariable),
+        };
+
+        Ok(())
+    }
+
     pub fn load_symbol(&mut self, symbol: &Symbol) {
         match symbol.scope {
             GLOBAL_SCOPE => self.emit(&Op::GetGlobal, vec![symbol.index]),
@@ -602,11 +1089,7 @@ mod test {
         }};
 
         ($input:expr, $instructions:expr, ($($constant:expr), *)) => {{
-            #[allow(unused)]
-            let mut expected_constants = Vec::<Box<dyn Any>>::new();
-            $(
-                expected_constants.push(Box::new($constant));
-            )*
+            let expected_constants: Vec<Box<dyn Any>> = vec![$(Box::new($constant)), *];
             CompilerTestCase {
                 input: $input,
                 expected_constants,
@@ -625,7 +1108,7 @@ mod test {
 
         assert_eq!(
             code::instruction_to_string(&concatted),
-            code::instruction_to_string(&actual)
+            code::instruction_to_string(actual)
         );
 
         for (i, instruction) in actual.iter().enumerate() {
@@ -642,7 +1125,7 @@ mod test {
 
     fn test_integer_object(expected: f64, actual: &object::ObjectType) {
         match actual {
-            ObjectType::IntegerObj(x) => assert_eq!(expected, *x),
+            ObjectType::IntegerObj(x) => assert_eq!(expected, *x as f64),
             _ => panic!("expected only integer objects"),
         }
     }
@@ -654,7 +1137,7 @@ mod test {
         }
     }
 
-    fn test_constants(expected: Vec<Box<dyn Any>>, actual: &mut Vec<ObjectType>) {
+    fn test_constants(expected: Vec<Box<dyn Any>>, actual: &[ObjectType]) {
         assert_eq!(expected.len(), actual.len());
 
         for (i, constant) in expected.into_iter().enumerate() {
@@ -690,10 +1173,13 @@ mod test {
             let mut constants = Vec::new();
             let symbol_table = SymbolTable::new();
 
-            let mut compiler = Compiler::new(&mut constants, symbol_table);
+            // keep the element by element bytecode these tests are written against
+            let mut compiler = Compiler::new(&mut constants, symbol_table)
+                .unwrap()
+                .with_literal_folding(false);
             compiler.compile(program).unwrap();
 
-            test_instructions(test.expected_instructions, &compiler.current_instructions());
+            test_instructions(test.expected_instructions, compiler.current_instructions());
             test_constants(test.expected_constants, compiler.constants);
         }
     }
@@ -751,6 +1237,16 @@ mod test {
                 ],
                 (2.0, 1.0)
             ),
+            compiler_test_case!(
+                "5 % 3",
+                vec![
+                    make::it!(&Op::Constant, vec![0]),
+                    make::it!(&Op::Constant, vec![1]),
+                    make::it!(&Op::Mod),
+                    make::it!(&Op::Pop),
+                ],
+                (5.0, 3.0)
+            ),
             compiler_test_case!(
                 "-1",
                 vec![
@@ -1119,7 +1615,7 @@ mod test {
                     make::it!(&Op::Closure, vec![1, 0]), // the compiled function
                     make::it!(&Op::SetGlobal, vec![0]),
                     make::it!(&Op::GetGlobal, vec![0]),
-                    make::it!(&Op::Call, vec![0]),
+                    make::it!(&Op::CallGlobal, vec![0, 0]),
                     make::it!(&Op::Pop)
                 ],
                 (
@@ -1147,7 +1643,7 @@ mod test {
         let mut constants = Vec::new();
         let symbol_table = SymbolTable::new();
 
-        let mut compiler = Compiler::new(&mut constants, symbol_table);
+        let mut compiler = Compiler::new(&mut constants, symbol_table).unwrap();
 
         assert_eq!(compiler.scope_index, 0);
         compiler.emit(&Op::Mul, vec![]);
@@ -1265,7 +1761,7 @@ mod test {
                     make::it!(&Op::SetGlobal, vec![0]),
                     make::it!(&Op::GetGlobal, vec![0]),
                     make::it!(&Op::Constant, vec![1]),
-                    make::it!(&Op::Call, vec![1]),
+                    make::it!(&Op::CallGlobal, vec![0, 1]),
                     make::it!(&Op::Pop),
                 ],
                 (
@@ -1290,7 +1786,7 @@ mod test {
                     make::it!(&Op::Constant, vec![1]),
                     make::it!(&Op::Constant, vec![2]),
                     make::it!(&Op::Constant, vec![3]),
-                    make::it!(&Op::Call, vec![3]),
+                    make::it!(&Op::CallGlobal, vec![0, 3]),
                     make::it!(&Op::Pop),
                 ],
                 (
@@ -1307,6 +1803,30 @@ mod test {
                     26.0
                 )
             ),
+            // the inner call could reassign `oneArg`, so the outer one isn't cached
+            compiler_test_case!(
+                r#"
+                    let oneArg = fn(a) { a };
+                    oneArg(oneArg(24));
+                "#,
+                vec![
+                    make::it!(&Op::Closure, vec![0, 0]),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::Constant, vec![1]),
+                    make::it!(&Op::CallGlobal, vec![0, 1]),
+                    make::it!(&Op::Call, vec![1]),
+                    make::it!(&Op::Pop),
+                ],
+                (
+                    vec![
+                        make::it!(&Op::GetLocal, vec![0]),
+                        make::it!(&Op::ReturnValue)
+                    ],
+                    24.0
+                )
+            ),
         ]);
     }
 
@@ -1474,7 +1994,7 @@ mod test {
                 make::it!(&Op::SetGlobal, vec![0]),
                 make::it!(&Op::GetGlobal, vec![0]),
                 make::it!(&Op::Constant, vec![2]),
-                make::it!(&Op::Call, vec![1]),
+                make::it!(&Op::CallGlobal, vec![0, 1]),
                 make::it!(&Op::Pop),
             ],
             (
@@ -1529,6 +2049,107 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn test_while() {
+        run_compiler_tests(vec![compiler_test_case!(
+            "let i = 0; while (i < 5) { i = i + 1; }",
+            vec![
+                make::it!(&Op::Constant, vec![0]),
+                make::it!(&Op::SetGlobal, vec![0]),
+                // condition
+                make::it!(&Op::Constant, vec![1]),
+                make::it!(&Op::GetGlobal, vec![0]),
+                make::it!(&Op::GreaterThan),
+                make::it!(&Op::JumpNotTruthy, vec![29]),
+                // body
+                make::it!(&Op::GetGlobal, vec![0]),
+                make::it!(&Op::Constant, vec![2]),
+                make::it!(&Op::Add),
+                make::it!(&Op::SetGlobal, vec![0]),
+                // back to the condition
+                make::it!(&Op::Jump, vec![6]),
+            ],
+            (0.0, 5.0, 1.0)
+        )]);
+    }
+
+    #[test]
+    fn test_index_assignment() {
+        run_compiler_tests(vec![
+            compiler_test_case!(
+                "let a = [1]; a[0] = 2;",
+                vec![
+                    make::it!(&Op::Constant, vec![0]),
+                    make::it!(&Op::Array, vec![1]),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::Constant, vec![1]),
+                    make::it!(&Op::Constant, vec![2]),
+                    make::it!(&Op::SetIndex),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                ],
+                (1.0, 0.0, 2.0)
+            ),
+            compiler_test_case!(
+                "let a = [1]; a[0][1] = 2;",
+                vec![
+                    make::it!(&Op::Constant, vec![0]),
+                    make::it!(&Op::Array, vec![1]),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                    // a with [0] replaced by...
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::Constant, vec![1]),
+                    // ...a[0] with [1] replaced by 2
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::Constant, vec![2]),
+                    make::it!(&Op::Index),
+                    make::it!(&Op::Constant, vec![3]),
+                    make::it!(&Op::Constant, vec![4]),
+                    make::it!(&Op::SetIndex),
+                    make::it!(&Op::SetIndex),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                ],
+                (1.0, 0.0, 0.0, 1.0, 2.0)
+            ),
+        ]);
+
+        let program = test_setup!("[1][0] = 2;");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        assert_eq!(
+            compiler.compile(program).unwrap_err().to_string(),
+            "can't mutate a non variable"
+        );
+    }
+
+    #[test]
+    fn test_continue() {
+        run_compiler_tests(vec![
+            compiler_test_case!(
+                "loop { continue; }",
+                vec![make::it!(&Op::Jump, vec![0]), make::it!(&Op::Jump, vec![0])],
+                ()
+            ),
+            compiler_test_case!(
+                "for (let i = 0; true; i++) { continue; }",
+                vec![
+                    make::it!(&Op::Constant, vec![0]),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                    make::it!(&Op::True),
+                    make::it!(&Op::JumpNotTruthy, vec![26]),
+                    // continue goes to the update
+                    make::it!(&Op::Jump, vec![13]),
+                    make::it!(&Op::GetGlobal, vec![0]),
+                    make::it!(&Op::Constant, vec![1]),
+                    make::it!(&Op::Add),
+                    make::it!(&Op::SetGlobal, vec![0]),
+                    make::it!(&Op::Jump, vec![6]),
+                ],
+                (0.0, 1.0)
+            ),
+        ]);
+    }
+
     #[test]
     fn test_nested_loops() {
         run_compiler_tests(vec![compiler_test_case!(
@@ -1578,4 +2199,330 @@ mod test {
             ),
         ]);
     }
+
+    #[test]
+    fn test_assignment_to_undeclared_variable() {
+        let inputs = [
+            ("b = 5;", "b"),
+            ("b++;", "b"),
+            ("let a = 1; c--;", "c"),
+            ("d += 1;", "d"),
+        ];
+
+        for (input, name) in inputs {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+            assert_eq!(
+                compiler.compile(program).unwrap_err().to_string(),
+                format!("cannot assign to undeclared variable: {name}")
+            );
+        }
+
+        run_compiler_tests(vec![compiler_test_case!(
+            "let b = 0; b = 5;",
+            vec![
+                make::it!(&Op::Constant, vec![0]),
+                make::it!(&Op::SetGlobal, vec![0]),
+                make::it!(&Op::Constant, vec![1]),
+                make::it!(&Op::SetGlobal, vec![0]),
+            ],
+            (0.0, 5.0)
+        )]);
+    }
+
+    #[test]
+    fn test_scope_snapshot() {
+        let program = test_setup!("let a = 1;");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        compiler.compile(program).unwrap();
+
+        // fn(b) { fn(c) { a + b + c } }, paused inside the inner function
+        compiler.enter_scope();
+        compiler.symbol_table.define("b");
+        compiler.enter_scope();
+        compiler.symbol_table.define("c");
+        compiler.symbol_table.resolve("a");
+        compiler.symbol_table.resolve("b");
+
+        let snapshot = compiler.scope_snapshot();
+        assert_eq!(snapshot.len(), 3);
+
+        let global = &snapshot[0];
+        assert_eq!(global.depth, 0);
+        assert!(
+            global
+                .symbols
+                .iter()
+                .any(|s| s.name() == "a" && s.scope == GLOBAL_SCOPE)
+        );
+        assert!(
+            global
+                .symbols
+                .iter()
+                .any(|s| s.name() == "len" && s.scope == BUILTIN_SCOPE)
+        );
+
+        let outer = &snapshot[1];
+        assert_eq!(outer.symbols.len(), 1);
+        assert_eq!(
+            (outer.symbols[0].name(), outer.symbols[0].scope),
+            ("b", LOCAL_SCOPE)
+        );
+
+        let inner = &snapshot[2];
+        let inner = inner
+            .symbols
+            .iter()
+            .map(|s| (s.name(), s.scope, s.index))
+            .collect::<Vec<_>>();
+        assert_eq!(inner, vec![("b", FREE_SCOPE, 0), ("c", LOCAL_SCOPE, 0)]);
+    }
+
+    #[test]
+    fn test_disassembly() {
+        let program = test_setup!("1 + 2");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        compiler.compile(program).unwrap();
+
+        assert_eq!(
+            code::instruction_to_string(&compiler.bytecode().instructions),
+            "0000 OpConstant 0
+0003 OpConstant 1
+0006 OpAdd
+0007 OpPop
+"
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_symbol_table() {
+        let mut constants = Vec::new();
+
+        // a table carried over from a previous run is fine
+        let mut previous = SymbolTable::new();
+        previous.define("a");
+        assert!(Compiler::new(&mut constants, previous.clone()).is_ok());
+
+        let mut skipped = previous.clone();
+        skipped.num_definitions = 0;
+
+        let mut function_name = SymbolTable::new();
+        function_name.define_function_name("f");
+
+        let inputs = [
+            (
+                SymbolTable::new_enclosed(Box::new(previous)),
+                "inconsistent symbol table: not the global scope",
+            ),
+            (
+                skipped,
+                "inconsistent symbol table: a is in global slot 0 but only 0 are defined",
+            ),
+            (
+                function_name,
+                "inconsistent symbol table: f is a FUNCTION symbol in the global scope",
+            ),
+        ];
+
+        for (symbol_table, expected) in inputs {
+            match Compiler::new(&mut constants, symbol_table) {
+                Ok(_) => panic!("expected {expected:?}"),
+                Err(e) => assert_eq!(e.to_string(), expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_literal_folding() {
+        let compile = |input, fold| {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut compiler = Compiler::new(&mut constants, SymbolTable::new())
+                .unwrap()
+                .with_literal_folding(fold);
+            compiler.compile(program).unwrap();
+
+            let instructions = compiler.bytecode().instructions;
+            (instructions, constants)
+        };
+
+        let one_constant = [
+            make::it!(&Op::Constant, vec![0]),
+            make::it!(&Op::Pop, vec![]),
+        ]
+        .concat();
+
+        let (folded, constants) = compile("[1, 2, 3]", true);
+        assert_eq!(folded, one_constant);
+        assert_eq!(
+            constants,
+            vec![ObjectType::ArrayObj(vec![
+                ObjectType::IntegerObj(1),
+                ObjectType::IntegerObj(2),
+                ObjectType::IntegerObj(3),
+            ])]
+        );
+
+        // three constants and an array against a single constant, each then popped
+        let (unfolded, _) = compile("[1, 2, 3]", false);
+        assert_eq!(unfolded.len(), 13);
+        assert_eq!(folded.len(), 4);
+
+        let (folded, constants) = compile("{1: [true], \"a\": {}}", true);
+        assert_eq!(folded, one_constant);
+        assert_eq!(constants.len(), 1);
+
+        // anything not known until runtime keeps the element by element code
+        let (folded, _) = compile("let x = 1; [1, x]", true);
+        let (unfolded, _) = compile("let x = 1; [1, x]", false);
+        assert_eq!(folded, unfolded);
+
+        let (folded, _) = compile("let x = x + 1; let y = [y];", true);
+        assert_eq!(
+            folded,
+            [
+                make::it!(&Op::GetGlobal, vec![0]),
+                make::it!(&Op::Constant, vec![0]),
+                make::it!(&Op::Add),
+                make::it!(&Op::SetGlobal, vec![0]),
+                make::it!(&Op::GetGlobal, vec![1]),
+                make::it!(&Op::Array, vec![1]),
+                make::it!(&Op::SetGlobal, vec![1]),
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_unreachable_code_warnings() {
+        let compile = |input| {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+            compiler.compile(program).unwrap();
+
+            (compiler.bytecode().instructions, compiler.warnings)
+        };
+
+        let (instructions, warnings) = compile("loop { break; puts(\"x\"); }");
+        assert_eq!(warnings, vec!["unreachable code after `break`"]);
+        assert_eq!(instructions, compile("loop { break; }").0);
+
+        let (_, warnings) = compile("fn() { return 1; 2 }");
+        assert_eq!(warnings, vec!["unreachable code after `return`"]);
+
+        // the code after the if still runs when the condition is false
+        let (_, warnings) = compile("fn(x) { if (x) { return 1; } 2 }");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_infinite_loop_warning() {
+        let warnings = |input| {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+            compiler.compile(program).unwrap();
+
+            compiler.warnings
+        };
+
+        assert_eq!(
+            warnings("loop {}"),
+            vec!["loop never exits, it has no `break` or `return`"]
+        );
+        assert_eq!(warnings("let i = 0; loop { i++; }").len(), 1);
+
+        assert!(warnings("loop { break; }").is_empty());
+        assert!(warnings("let x = true; loop { if (x) { break; }; }").is_empty());
+        assert!(warnings("fn() { loop { if (true) { 1 } else { return 2; }; } }").is_empty());
+        assert!(warnings("loop { loop { break; } }").is_empty());
+
+        assert_eq!(
+            warnings("while(true){}"),
+            vec!["while (true) never exits, it has no `break` or `return`"]
+        );
+        assert!(warnings("while(true){ break; }").is_empty());
+        assert!(warnings("let x = false; while(x){}").is_empty());
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        let program = test_setup!("break;");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+        assert_eq!(
+            compiler.compile(program).unwrap_err().to_string(),
+            "break outside of a loop"
+        );
+    }
+
+    #[test]
+    fn test_unusable_hash_keys() {
+        let inputs = [
+            ("{fn(){}: 1}", "unusable as a hash key: fn  () "),
+            ("{[1]: 1}", "unusable as a hash key: [1]"),
+            ("{1: {{}: 2}}", "unusable as a hash key: {}"),
+        ];
+
+        for (input, expected) in inputs {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+            assert_eq!(compiler.compile(program).unwrap_err().to_string(), expected);
+        }
+
+        // keys that are only known at runtime are left for the vm to check
+        let program = test_setup!("let f = fn() {}; {f: 1}");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+        assert!(compiler.compile(program).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_parameter_names() {
+        let program = test_setup!("fn(a, a) {}");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+        assert_eq!(
+            compiler.compile(program).unwrap_err().to_string(),
+            "duplicate parameter name: a"
+        );
+        // the failed function's scope isn't left behind
+        assert_eq!(compiler.scope_snapshot().len(), 1);
+
+        let program = test_setup!("fn(a) { b }");
+        assert!(compiler.compile(program).is_err());
+        assert_eq!(compiler.scope_snapshot().len(), 1);
+
+        let program = test_setup!("fn(a, b) {}");
+        let mut constants = Vec::new();
+        let mut compiler = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+
+        assert!(compiler.compile(program).is_ok());
+    }
+
+    #[test]
+    fn test_destructure_statements() {
+        run_compiler_tests(vec![compiler_test_case!(
+            "let [a, b] = [1, 2];",
+            vec![
+                make::it!(&Op::Constant, vec![0]),
+                make::it!(&Op::Constant, vec![1]),
+                make::it!(&Op::Array, vec![2]),
+                make::it!(&Op::Destructure, vec![2]),
+                make::it!(&Op::SetGlobal, vec![0]),
+                make::it!(&Op::SetGlobal, vec![1]),
+            ],
+            (1.0, 2.0)
+        )]);
+    }
 }
@@ -2,6 +2,7 @@ use std::{
     cell::{RefCell, RefMut},
     collections::HashMap,
     rc::Rc,
+    sync::Arc,
 };
 
 // If I don't do it this way I'd need to it to be &'static, and I think
@@ -14,11 +15,25 @@ pub const FUNCTION_SCOPE: &str = "FUNCTION";
 
 #[derive(Debug, Clone)]
 pub struct Symbol {
-    name: String,
+    name: Arc<str>,
     pub scope: &'static str,
     pub index: usize,
 }
 
+impl Symbol {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The symbols defined in one scope, as seen at the time of the snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeInfo {
+    // 0 is the global scope
+    pub depth: usize,
+    pub symbols: Vec<Symbol>,
+}
+
 impl PartialEq for Symbol {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name && self.scope == other.scope && self.index == other.index
@@ -28,7 +43,7 @@ impl PartialEq for Symbol {
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
     pub outer: Option<Box<Self>>,
-    store: Rc<RefCell<HashMap<String, Symbol>>>,
+    store: Rc<RefCell<HashMap<Arc<str>, Symbol>>>,
     pub num_definitions: usize,
     pub free_symbols: Rc<RefCell<Vec<Symbol>>>,
 }
@@ -58,7 +73,20 @@ impl SymbolTable {
         }
     }
 
-    pub fn define(&mut self, name: String) -> Symbol {
+    /// A copy that shares nothing with this table. Clones share their
+    /// definitions, so a `clone` kept as a fallback still sees names defined
+    /// after it was taken.
+    pub fn detached(&self) -> Self {
+        Self {
+            outer: self.outer.as_ref().map(|outer| Box::new(outer.detached())),
+            store: Rc::new(RefCell::new(self.store.borrow().clone())),
+            num_definitions: self.num_definitions,
+            free_symbols: Rc::new(RefCell::new(self.free_symbols.borrow().clone())),
+        }
+    }
+
+    pub fn define(&mut self, name: impl Into<Arc<str>>) -> Symbol {
+        let name = name.into();
         let symbol = if self.outer.is_none() {
             Symbol {
                 name: name.clone(),
@@ -79,7 +107,39 @@ impl SymbolTable {
         symbol
     }
 
-    pub fn define_function_name(&mut self, name: String) -> Symbol {
+    /// Defines `name` for a stretch of code, like a for loop's variable,
+    /// also handing back what `name` meant in this scope before so `restore`
+    /// can put it back afterwards.
+    pub fn shadow(&mut self, name: impl Into<Arc<str>>) -> (Symbol, Option<Symbol>) {
+        let name = name.into();
+        let previous = self.store.borrow().get(&name).cloned();
+
+        (self.define(name), previous)
+    }
+
+    pub fn restore(&mut self, name: &str, previous: Option<Symbol>) {
+        let mut store = self.store.borrow_mut();
+        match previous {
+            Some(symbol) => store.insert(symbol.name.clone(), symbol),
+            None => store.remove(name),
+        };
+    }
+
+    /// Makes `symbol` reachable as `module.member` as well, `.` can't be part
+    /// of an identifier so these never collide with ordinary names.
+    pub fn define_member(&mut self, module: &str, symbol: &Symbol) -> Symbol {
+        let name: Arc<str> = format!("{module}.{}", symbol.name).into();
+        let member = Symbol {
+            name: name.clone(),
+            ..symbol.clone()
+        };
+        self.store.borrow_mut().insert(name, member.clone());
+
+        member
+    }
+
+    pub fn define_function_name(&mut self, name: impl Into<Arc<str>>) -> Symbol {
+        let name = name.into();
         let symbol = Symbol {
             name: name.clone(),
             index: 0,
@@ -94,29 +154,43 @@ impl SymbolTable {
     pub fn resolve(&self, name: &str) -> Option<Symbol> {
         let rc = self.store.borrow_mut();
         let symbol = rc.get(name);
-        if symbol.is_none() && self.outer.is_some() {
-            if let Some(outer) = self.outer.as_ref() {
-                let obj = outer.resolve(name);
-
-                match obj {
-                    None => return obj,
-                    Some(obj) => {
-                        if obj.scope == GLOBAL_SCOPE || obj.scope == BUILTIN_SCOPE {
-                            return Some(obj);
-                        }
-
-                        let free = self.define_free(&obj, rc);
-                        return Some(free);
+        if symbol.is_none()
+            && self.outer.is_some()
+            && let Some(outer) = self.outer.as_ref()
+        {
+            let obj = outer.resolve(name);
+
+            match obj {
+                None => return obj,
+                Some(obj) => {
+                    if obj.scope == GLOBAL_SCOPE || obj.scope == BUILTIN_SCOPE {
+                        return Some(obj);
                     }
+
+                    let free = self.define_free(&obj, rc);
+                    return Some(free);
                 }
             }
         }
-        if symbol.is_some() {
-            let symbol = symbol.unwrap().clone();
-            Some(symbol)
-        } else {
-            None
-        }
+        symbol.cloned()
+    }
+
+    /// Lists every scope from the global one inwards, with each scope's
+    /// symbols ordered by kind and then index.
+    pub fn snapshot(&self) -> Vec<ScopeInfo> {
+        let mut scopes = match &self.outer {
+            Some(outer) => outer.snapshot(),
+            None => Vec::new(),
+        };
+
+        let mut symbols = self.store.borrow().values().cloned().collect::<Vec<_>>();
+        symbols.sort_by(|a, b| (a.scope, a.index, &a.name).cmp(&(b.scope, b.index, &b.name)));
+
+        scopes.push(ScopeInfo {
+            depth: scopes.len(),
+            symbols,
+        });
+        scopes
     }
 
     pub fn define_builtin(&mut self, index: usize, name: &str) -> Symbol {
@@ -125,7 +199,9 @@ impl SymbolTable {
             index,
             scope: BUILTIN_SCOPE,
         };
-        self.store.borrow_mut().insert(name.into(), symbol.clone());
+        self.store
+            .borrow_mut()
+            .insert(symbol.name.clone(), symbol.clone());
         symbol
     }
 
@@ -133,7 +209,7 @@ impl SymbolTable {
     fn define_free(
         &self,
         original: &Symbol,
-        mut store: RefMut<'_, HashMap<String, Symbol>>,
+        mut store: RefMut<'_, HashMap<Arc<str>, Symbol>>,
     ) -> Symbol {
         self.free_symbols.borrow_mut().push(original.clone());
 
@@ -159,7 +235,7 @@ mod test {
         expected.insert(
             "a".to_string(),
             Symbol {
-                name: "a".to_string(),
+                name: "a".into(),
                 scope: GLOBAL_SCOPE,
                 index: 0,
             },
@@ -167,7 +243,7 @@ mod test {
         expected.insert(
             "b".to_string(),
             Symbol {
-                name: "b".to_string(),
+                name: "b".into(),
                 scope: GLOBAL_SCOPE,
                 index: 1,
             },
@@ -175,7 +251,7 @@ mod test {
         expected.insert(
             "c".to_string(),
             Symbol {
-                name: "c".to_string(),
+                name: "c".into(),
                 scope: LOCAL_SCOPE,
                 index: 0,
             },
@@ -183,7 +259,7 @@ mod test {
         expected.insert(
             "d".to_string(),
             Symbol {
-                name: "d".to_string(),
+                name: "d".into(),
                 scope: LOCAL_SCOPE,
                 index: 1,
             },
@@ -191,7 +267,7 @@ mod test {
         expected.insert(
             "e".to_string(),
             Symbol {
-                name: "e".to_string(),
+                name: "e".into(),
                 scope: LOCAL_SCOPE,
                 index: 0,
             },
@@ -199,51 +275,39 @@ mod test {
         expected.insert(
             "f".to_string(),
             Symbol {
-                name: "f".to_string(),
+                name: "f".into(),
                 scope: LOCAL_SCOPE,
                 index: 1,
             },
         );
 
         let mut global = SymbolTable::new();
-        assert_eq!(global.define("a".to_string()), *expected.get("a").unwrap());
-        assert_eq!(global.define("b".to_string()), *expected.get("b").unwrap());
+        assert_eq!(global.define("a"), *expected.get("a").unwrap());
+        assert_eq!(global.define("b"), *expected.get("b").unwrap());
 
         let mut first_local = SymbolTable::new_enclosed(Box::new(global));
-        assert_eq!(
-            first_local.define("c".to_string()),
-            *expected.get("c").unwrap()
-        );
-        assert_eq!(
-            first_local.define("d".to_string()),
-            *expected.get("d").unwrap()
-        );
+        assert_eq!(first_local.define("c"), *expected.get("c").unwrap());
+        assert_eq!(first_local.define("d"), *expected.get("d").unwrap());
 
         let mut second_local = SymbolTable::new_enclosed(Box::new(first_local));
-        assert_eq!(
-            second_local.define("e".to_string()),
-            *expected.get("e").unwrap()
-        );
-        assert_eq!(
-            second_local.define("f".to_string()),
-            *expected.get("f").unwrap()
-        );
+        assert_eq!(second_local.define("e"), *expected.get("e").unwrap());
+        assert_eq!(second_local.define("f"), *expected.get("f").unwrap());
     }
 
     #[test]
     fn test_resolve_global() {
         let mut global = SymbolTable::new();
-        global.define("a".to_string());
-        global.define("b".to_string());
+        global.define("a");
+        global.define("b");
 
         let expected = vec![
             Symbol {
-                name: "a".to_string(),
+                name: "a".into(),
                 scope: GLOBAL_SCOPE,
                 index: 0,
             },
             Symbol {
-                name: "b".to_string(),
+                name: "b".into(),
                 scope: GLOBAL_SCOPE,
                 index: 1,
             },
@@ -258,14 +322,14 @@ mod test {
     #[test]
     fn test_resolve_local() {
         let mut global = SymbolTable::new();
-        global.define("a".to_string());
-        global.define("b".to_string());
+        global.define("a");
+        global.define("b");
 
         let mut local = SymbolTable::new_enclosed(Box::new(global));
-        local.define("c".into());
-        local.define("d".into());
+        local.define("c");
+        local.define("d");
 
-        let expected = vec![
+        let expected = [
             Symbol {
                 name: "a".into(),
                 scope: GLOBAL_SCOPE,
@@ -297,18 +361,18 @@ mod test {
     #[test]
     fn test_resolve_nested_local() {
         let mut global = SymbolTable::new();
-        global.define("a".to_string());
-        global.define("b".to_string());
+        global.define("a");
+        global.define("b");
 
         let mut local = SymbolTable::new_enclosed(Box::new(global));
-        local.define("c".into());
-        local.define("d".into());
+        local.define("c");
+        local.define("d");
 
         let mut second_local = SymbolTable::new_enclosed(Box::new(local));
-        second_local.define("e".into());
-        second_local.define("f".into());
+        second_local.define("e");
+        second_local.define("f");
 
-        let expected = vec![
+        let expected = [
             Symbol {
                 name: "a".into(),
                 scope: GLOBAL_SCOPE,
@@ -336,7 +400,7 @@ mod test {
             assert_eq!(*symbol, result);
         });
 
-        let expected = vec![
+        let expected = [
             Symbol {
                 name: "a".into(),
                 scope: GLOBAL_SCOPE,
@@ -425,16 +489,16 @@ mod test {
     #[test]
     fn test_resolve_free() {
         let mut global = SymbolTable::new();
-        global.define("a".into());
-        global.define("b".into());
+        global.define("a");
+        global.define("b");
 
         let mut first_local = SymbolTable::new_enclosed(Box::new(global));
-        first_local.define("c".into());
-        first_local.define("d".into());
+        first_local.define("c");
+        first_local.define("d");
 
         let mut second_local = SymbolTable::new_enclosed(Box::new(first_local));
-        second_local.define("e".into());
-        second_local.define("f".into());
+        second_local.define("e");
+        second_local.define("f");
 
         struct Test {
             expected_symbols: Vec<Symbol>,
@@ -552,14 +616,14 @@ mod test {
     #[test]
     fn test_unresolvable_free() {
         let mut global = SymbolTable::new();
-        global.define("a".into());
+        global.define("a");
 
         let mut first_local = SymbolTable::new_enclosed(Box::new(global));
-        first_local.define("c".into());
+        first_local.define("c");
 
         let mut second_local = SymbolTable::new_enclosed(Box::new(first_local));
-        second_local.define("e".into());
-        second_local.define("f".into());
+        second_local.define("e");
+        second_local.define("f");
 
         let expected = vec![
             Symbol {
@@ -592,14 +656,14 @@ mod test {
         let expected_unresolvable = vec!["b", "d"];
 
         for name in expected_unresolvable {
-            assert!(second_local.resolve(&name).is_none());
+            assert!(second_local.resolve(name).is_none());
         }
     }
 
     #[test]
     fn test_define_and_resolve_function_name() {
         let mut global = SymbolTable::new();
-        global.define_function_name("a".into());
+        global.define_function_name("a");
 
         let expected = Symbol {
             name: "a".into(),
@@ -614,8 +678,8 @@ mod test {
     #[test]
     fn test_shadow_function_name() {
         let mut global = SymbolTable::new();
-        global.define_function_name("a".into());
-        global.define("a".into());
+        global.define_function_name("a");
+        global.define("a");
 
         let expected = Symbol {
             name: "a".into(),
@@ -626,4 +690,23 @@ mod test {
         let result = global.resolve("a").unwrap();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_shadow_and_restore() {
+        let mut global = SymbolTable::new();
+        let outer = global.define("i");
+
+        let (inner, previous) = global.shadow("i");
+        assert_eq!(previous, Some(outer.clone()));
+        assert_eq!(inner.index, 1);
+        assert_eq!(global.resolve("i"), Some(inner));
+
+        global.restore("i", previous);
+        assert_eq!(global.resolve("i"), Some(outer));
+
+        let (_, previous) = global.shadow("j");
+        assert_eq!(previous, None);
+        global.restore("j", previous);
+        assert_eq!(global.resolve("j"), None);
+    }
 }
@@ -1,17 +1,122 @@
-use object::{self, ObjectType};
+use object::{self, BuiltinFn};
 use std::{collections::HashMap, sync::LazyLock};
 
-type BuiltinFunction = fn(Vec<ObjectType>) -> ObjectType;
-
-pub static BUILTINS: LazyLock<HashMap<&'static str, BuiltinFunction>> = LazyLock::new(|| {
+pub static BUILTINS: LazyLock<HashMap<&'static str, BuiltinFn>> = LazyLock::new(|| {
     let mut builtins = HashMap::new();
 
     builtins.insert("len", object::get_builtin_by_name("len").unwrap());
     builtins.insert("puts", object::get_builtin_by_name("puts").unwrap());
+    builtins.insert("tap", object::get_builtin_by_name("tap").unwrap());
     builtins.insert("first", object::get_builtin_by_name("first").unwrap());
     builtins.insert("last", object::get_builtin_by_name("last").unwrap());
     builtins.insert("rest", object::get_builtin_by_name("rest").unwrap());
     builtins.insert("push", object::get_builtin_by_name("push").unwrap());
+    builtins.insert("group_by", object::get_builtin_by_name("group_by").unwrap());
+    builtins.insert("max_by", object::get_builtin_by_name("max_by").unwrap());
+    builtins.insert("min_by", object::get_builtin_by_name("min_by").unwrap());
+    builtins.insert("apply", object::get_builtin_by_name("apply").unwrap());
+    builtins.insert("map", object::get_builtin_by_name("map").unwrap());
+    builtins.insert(
+        "transform",
+        object::get_builtin_by_name("transform").unwrap(),
+    );
+    builtins.insert("reduce", object::get_builtin_by_name("reduce").unwrap());
+    builtins.insert("compare", object::get_builtin_by_name("compare").unwrap());
+    builtins.insert("sort", object::get_builtin_by_name("sort").unwrap());
+    builtins.insert("keys", object::get_builtin_by_name("keys").unwrap());
+    builtins.insert("values", object::get_builtin_by_name("values").unwrap());
+    builtins.insert("bytes", object::get_builtin_by_name("bytes").unwrap());
+    builtins.insert(
+        "from_bytes",
+        object::get_builtin_by_name("from_bytes").unwrap(),
+    );
+    builtins.insert("hex", object::get_builtin_by_name("hex").unwrap());
+    builtins.insert("from_hex", object::get_builtin_by_name("from_hex").unwrap());
+    builtins.insert(
+        "assert_type",
+        object::get_builtin_by_name("assert_type").unwrap(),
+    );
+    builtins.insert("divmod", object::get_builtin_by_name("divmod").unwrap());
+    builtins.insert("lines", object::get_builtin_by_name("lines").unwrap());
+    builtins.insert(
+        "starts_with",
+        object::get_builtin_by_name("starts_with").unwrap(),
+    );
+    builtins.insert(
+        "ends_with",
+        object::get_builtin_by_name("ends_with").unwrap(),
+    );
+    builtins.insert("index_of", object::get_builtin_by_name("index_of").unwrap());
+    builtins.insert("split", object::get_builtin_by_name("split").unwrap());
+    builtins.insert("join", object::get_builtin_by_name("join").unwrap());
+    builtins.insert("type", object::get_builtin_by_name("type").unwrap());
+    builtins.insert("int", object::get_builtin_by_name("int").unwrap());
+    builtins.insert("str", object::get_builtin_by_name("str").unwrap());
+    builtins.insert("contains", object::get_builtin_by_name("contains").unwrap());
+    builtins.insert(
+        "equals_ignore_case",
+        object::get_builtin_by_name("equals_ignore_case").unwrap(),
+    );
+    builtins.insert(
+        "count_matches",
+        object::get_builtin_by_name("count_matches").unwrap(),
+    );
+    builtins.insert("split_n", object::get_builtin_by_name("split_n").unwrap());
+    builtins.insert("abs", object::get_builtin_by_name("abs").unwrap());
+    builtins.insert("signum", object::get_builtin_by_name("signum").unwrap());
+    builtins.insert("min", object::get_builtin_by_name("min").unwrap());
+    builtins.insert("max", object::get_builtin_by_name("max").unwrap());
+    builtins.insert("clamp", object::get_builtin_by_name("clamp").unwrap());
+    builtins.insert("gcd", object::get_builtin_by_name("gcd").unwrap());
+    builtins.insert("lcm", object::get_builtin_by_name("lcm").unwrap());
+    builtins.insert("pow", object::get_builtin_by_name("pow").unwrap());
+    builtins.insert("exp", object::get_builtin_by_name("exp").unwrap());
+    builtins.insert("log", object::get_builtin_by_name("log").unwrap());
+    builtins.insert("sin", object::get_builtin_by_name("sin").unwrap());
+    builtins.insert("cos", object::get_builtin_by_name("cos").unwrap());
+    builtins.insert("tan", object::get_builtin_by_name("tan").unwrap());
+    builtins.insert("pi", object::get_builtin_by_name("pi").unwrap());
+    builtins.insert("e", object::get_builtin_by_name("e").unwrap());
+    builtins.insert(
+        "approx_eq",
+        object::get_builtin_by_name("approx_eq").unwrap(),
+    );
+    builtins.insert("trunc", object::get_builtin_by_name("trunc").unwrap());
+    builtins.insert("fract", object::get_builtin_by_name("fract").unwrap());
+    builtins.insert("delete", object::get_builtin_by_name("delete").unwrap());
+    builtins.insert("flat_map", object::get_builtin_by_name("flat_map").unwrap());
+    builtins.insert("set", object::get_builtin_by_name("set").unwrap());
+    builtins.insert(
+        "partition",
+        object::get_builtin_by_name("partition").unwrap(),
+    );
+    builtins.insert("any", object::get_builtin_by_name("any").unwrap());
+    builtins.insert("all", object::get_builtin_by_name("all").unwrap());
+    builtins.insert("filter", object::get_builtin_by_name("filter").unwrap());
+    builtins.insert(
+        "take_while",
+        object::get_builtin_by_name("take_while").unwrap(),
+    );
+    builtins.insert(
+        "drop_while",
+        object::get_builtin_by_name("drop_while").unwrap(),
+    );
+    builtins.insert("scan", object::get_builtin_by_name("scan").unwrap());
+    builtins.insert("chunk", object::get_builtin_by_name("chunk").unwrap());
+    builtins.insert("window", object::get_builtin_by_name("window").unwrap());
+    builtins.insert(
+        "is_sorted",
+        object::get_builtin_by_name("is_sorted").unwrap(),
+    );
+    builtins.insert("seed", object::get_builtin_by_name("seed").unwrap());
+    builtins.insert("shuffle", object::get_builtin_by_name("shuffle").unwrap());
+    builtins.insert("pad_left", object::get_builtin_by_name("pad_left").unwrap());
+    builtins.insert(
+        "pad_right",
+        object::get_builtin_by_name("pad_right").unwrap(),
+    );
+    builtins.insert("substr", object::get_builtin_by_name("substr").unwrap());
+    builtins.insert("fmt_num", object::get_builtin_by_name("fmt_num").unwrap());
 
     builtins
 });
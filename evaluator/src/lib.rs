@@ -1,7 +1,10 @@
 mod builtins;
-use ast::{BlockStatement, Expression, Map, MutateStatement, PostfixStatement, Program, Statement};
+use ast::{
+    BlockStatement, DestructureStatement, Expression, ForStatement, Map, ModStatement,
+    MutateStatement, PostfixStatement, Program, Statement, WhileStatement,
+};
 use builtins::BUILTINS;
-use object::{Environment, Function, HashPair, Object, ObjectType};
+use object::{CallFunction, Environment, Function, HashPair, Object, ObjectType};
 use std::collections::HashMap;
 use token::{Token, TokenLiteral};
 
@@ -51,12 +54,30 @@ pub fn eval(node: &Statement, env: &mut Environment) -> ObjectType {
 
             env.set(&let_statement.name.token_literal(), value)
         }
+        Statement::DestructureStatement(destructure_statement) => {
+            eval_destructure_statement(destructure_statement, env)
+        }
         Statement::BlockStatement(block_statement) => eval_block_statements(block_statement, env),
         Statement::LoopStatement(block_statement) => eval_loop_statement(block_statement, env),
+        Statement::WhileStatement(while_statement) => eval_while_statement(while_statement, env),
+        Statement::ForStatement(for_statement) => eval_for_statement(for_statement, env),
+        Statement::ModStatement(mod_statement) => eval_mod_statement(mod_statement, env),
+        Statement::ImportStatement(import) => {
+            new_error(&format!("unresolved import: {}", import.path))
+        }
         Statement::BreakStatement => ObjectType::Break,
+        Statement::ContinueStatement => ObjectType::Continue,
         Statement::MutateStatement(mutate_statement) => {
             eval_mutate_statement(mutate_statement, env)
         }
+        Statement::IndexAssignStatement(statement) => {
+            let value = eval_expression(&statement.value, env);
+            if is_error(&value) {
+                return value;
+            }
+
+            eval_index_assignment(&statement.left, &statement.index, value, env)
+        }
         Statement::PostfixStatement(postfix_statement) => {
             eval_postfix_statement(postfix_statement, env)
         }
@@ -66,39 +87,69 @@ pub fn eval(node: &Statement, env: &mut Environment) -> ObjectType {
 fn eval_postfix_statement(statement: &PostfixStatement, env: &mut Environment) -> ObjectType {
     let obj = match env.get(&statement.name.token_literal()) {
         Some(obj) => obj,
-        None => return ObjectType::ErrorObj(format!("Uninitated variable: {}", statement.name)),
+        None => {
+            return ObjectType::ErrorObj(format!(
+                "cannot assign to undeclared variable: {}",
+                statement.name
+            ));
+        }
     };
 
-    if let ObjectType::IntegerObj(value) = obj {
-        match statement.postfix {
-            Token::PlusPlus => {
-                env.set(
-                    &statement.name.token_literal(),
-                    ObjectType::IntegerObj(value + 1.0),
-                );
-            }
-            Token::MinusMinus => {
-                env.set(
-                    &statement.name.token_literal(),
-                    ObjectType::IntegerObj(value - 1.0),
-                );
-            }
-            _ => unreachable!(),
+    let step = match statement.postfix {
+        Token::PlusPlus => 1,
+        Token::MinusMinus => -1,
+        _ => unreachable!(),
+    };
+    let updated = match obj {
+        ObjectType::IntegerObj(value) => ObjectType::IntegerObj(value.wrapping_add(step)),
+        ObjectType::FloatObj(value) => ObjectType::FloatObj(value + step as f64),
+        _ => {
+            return ObjectType::ErrorObj(format!(
+                "Invalid postfix operation, expected: INTEGER, got: {}",
+                obj.r#type()
+            ));
         }
-    } else {
-        return ObjectType::ErrorObj(format!(
-            "Invalid postfix operation, expected: INTEGER, got: {}",
-            obj.r#type()
-        ));
-    }
+    };
+    env.set(&statement.name.token_literal(), updated);
 
     ObjectType::default()
 }
 
+fn eval_destructure_statement(
+    statement: &DestructureStatement,
+    env: &mut Environment,
+) -> ObjectType {
+    let value = eval_expression(&statement.value, env);
+    if is_error(&value) {
+        return value;
+    }
+
+    match value {
+        ObjectType::ArrayObj(array) if array.len() == statement.names.len() => {
+            for (name, element) in statement.names.iter().zip(array) {
+                env.set(&name.token_literal(), element);
+            }
+
+            NULL
+        }
+        ObjectType::ArrayObj(array) => new_error(&format!(
+            "cannot destructure ARRAY of length {} into {} names",
+            array.len(),
+            statement.names.len()
+        )),
+        _ => new_error(&format!("cannot destructure {value}, expected ARRAY")),
+    }
+}
+
 fn eval_mutate_statement(statement: &MutateStatement, env: &mut Environment) -> ObjectType {
     match env.get(&statement.name.token_literal()) {
         Some(_) => {}
-        None => return ObjectType::ErrorObj(format!("Uninitated variable: {}", statement.name)),
+        None => {
+            return ObjectType::ErrorObj(format!(
+                "cannot assign to undeclared variable: {}",
+                statement.name
+            ));
+        }
     }
 
     let value = eval_expression(&statement.value, env);
@@ -107,6 +158,71 @@ fn eval_mutate_statement(statement: &MutateStatement, env: &mut Environment) ->
     ObjectType::default()
 }
 
+/// Sets `left[index]` to `value` and stores the updated collection back
+/// where `left` came from, working outwards through nested index targets.
+fn eval_index_assignment(
+    left: &Expression,
+    index: &Expression,
+    value: ObjectType,
+    env: &mut Environment,
+) -> ObjectType {
+    let collection = eval_expression(left, env);
+    if is_error(&collection) {
+        return collection;
+    }
+    let index = eval_expression(index, env);
+    if is_error(&index) {
+        return index;
+    }
+
+    let updated = match (collection, index) {
+        (ObjectType::ArrayObj(mut array), ObjectType::IntegerObj(int)) => {
+            let index = if int < 0 {
+                array.len().checked_sub(int.unsigned_abs() as usize)
+            } else {
+                usize::try_from(int).ok()
+            };
+
+            match index.and_then(|i| array.get_mut(i)) {
+                Some(slot) => *slot = value,
+                None => {
+                    return new_error(&format!(
+                        "index {int} out of bounds for ARRAY of length {}",
+                        array.len()
+                    ));
+                }
+            }
+
+            ObjectType::ArrayObj(array)
+        }
+        (ObjectType::HashObj(mut map), key) => match key.hash() {
+            Ok(hashed) => {
+                map.insert(hashed, HashPair { key, value });
+                ObjectType::HashObj(map)
+            }
+            Err(msg) => return new_error(&msg),
+        },
+        (collection, index) => {
+            return new_error(&format!(
+                "index assignment not supported: {}[{}]",
+                collection.r#type(),
+                index.r#type()
+            ));
+        }
+    };
+
+    match left {
+        Expression::IdentExpression(name) => {
+            env.set(&name.token_literal(), updated);
+            NULL
+        }
+        Expression::IndexExpression(outer, outer_index) => {
+            eval_index_assignment(outer, outer_index, updated, env)
+        }
+        _ => new_error(&format!("cannot assign to {left}")),
+    }
+}
+
 fn eval_loop_statement(block: &BlockStatement, env: &mut Environment) -> ObjectType {
     let result = eval_block_statements(block, env);
     if result == ObjectType::Break {
@@ -116,9 +232,103 @@ fn eval_loop_statement(block: &BlockStatement, env: &mut Environment) -> ObjectT
     eval_loop_statement(block, env)
 }
 
+fn eval_while_statement(statement: &WhileStatement, env: &mut Environment) -> ObjectType {
+    loop {
+        let value = eval_expression(&statement.condition, env);
+        if is_error(&value) {
+            return value;
+        }
+
+        if let Some(name) = &statement.name {
+            env.set(&name.token_literal(), value.clone());
+        }
+        if !is_truthy(value) {
+            return NULL;
+        }
+
+        match eval_block_statements(&statement.body, env) {
+            ObjectType::Break => return NULL,
+            result @ (ObjectType::ReturnValueObj(_) | ObjectType::ErrorObj(_)) => return result,
+            _ => {}
+        }
+    }
+}
+
+fn eval_for_statement(statement: &ForStatement, env: &mut Environment) -> ObjectType {
+    let value = eval_expression(&statement.init.value, env);
+    if is_error(&value) {
+        return value;
+    }
+
+    // the loop variable only lives as long as the loop, whatever had its name
+    // before is put back after
+    let name = statement.init.name.token_literal();
+    let previous = env.store.get(&name).cloned();
+    env.set(&name, value);
+
+    let result = loop {
+        let condition = eval_expression(&statement.condition, env);
+        if is_error(&condition) {
+            break condition;
+        }
+        if !is_truthy(condition) {
+            break NULL;
+        }
+
+        match eval_block_statements(&statement.body, env) {
+            ObjectType::Break => break NULL,
+            result @ (ObjectType::ReturnValueObj(_) | ObjectType::ErrorObj(_)) => break result,
+            _ => {}
+        }
+
+        let update = eval(&statement.update, env);
+        if is_error(&update) {
+            break update;
+        }
+    };
+
+    match previous {
+        Some(previous) => env.set(&name, previous),
+        None => env.store.remove(&name).unwrap_or_default(),
+    };
+
+    result
+}
+
+fn eval_mod_statement(statement: &ModStatement, env: &mut Environment) -> ObjectType {
+    let module = statement.name.token_literal();
+
+    // members see each other by their plain names while the mod is being
+    // evaluated, afterwards only as module.member
+    let mut shadowed = Vec::new();
+    let mut result = NULL;
+    for member in statement.members.iter() {
+        let name = member.name.token_literal();
+        shadowed.push((name.clone(), env.store.get(&name).cloned()));
+
+        let value = eval_expression(&member.value, env);
+        if is_error(&value) {
+            result = value;
+            break;
+        }
+
+        env.set(&name, value.clone());
+        env.set(&format!("{module}.{name}"), value);
+    }
+
+    for (name, previous) in shadowed.into_iter().rev() {
+        match previous {
+            Some(previous) => env.set(&name, previous),
+            None => env.store.remove(&name).unwrap_or_default(),
+        };
+    }
+
+    result
+}
+
 fn eval_expression(expression: &Expression, env: &mut Environment) -> ObjectType {
     match expression {
-        Expression::IntExpression(int) => int.to_owned().into(),
+        Expression::IntExpression(int) | Expression::FloatExpression(int) => int.to_owned().into(),
         Expression::BoolExpression(bool) => bool.to_owned().into(),
         Expression::PrefixExpression((t, expression_statement)) => {
             let right = eval_expression(expression_statement, env);
@@ -186,6 +396,13 @@ fn eval_expression(expression: &Expression, env: &mut Environment) -> ObjectType
 
             eval_index_expression(left, index)
         }
+        Expression::MemberExpression(module, member) => {
+            let qualified = format!("{module}.{}", member.token_literal());
+            match env.get(&qualified) {
+                Some(value) => value.clone(),
+                None => new_error(&format!("undefined member: {qualified}")),
+            }
+        }
         Expression::HashLiteral(map) => eval_hash_literal_node(map, env),
         Expression::UnknownExpression(token) => ObjectType::ErrorObj(format!(
             "Encounted unknown token: {}",
@@ -219,10 +436,15 @@ fn eval_hash_literal_node(map: &Map, env: &mut Environment) -> ObjectType {
 }
 
 fn eval_index_expression(left: ObjectType, index: ObjectType) -> ObjectType {
-    if let ObjectType::ArrayObj(ref array) = left {
-        if let ObjectType::IntegerObj(int) = index {
-            return eval_array_index_expression(array, int);
-        }
+    if let ObjectType::ArrayObj(ref array) = left
+        && let ObjectType::IntegerObj(int) = index
+    {
+        return eval_array_index_expression(array, int);
+    }
+    if let ObjectType::StringObj(ref string) = left
+        && let ObjectType::IntegerObj(int) = index
+    {
+        return eval_string_index_expression(string, int);
     }
     if let ObjectType::HashObj(map) = left {
         return eval_hash_index_expression(&map, index);
@@ -243,12 +465,22 @@ fn eval_hash_index_expression(map: &HashMap<u64, HashPair>, index: ObjectType) -
     }
 }
 
-fn eval_array_index_expression(array: &[ObjectType], index: f64) -> ObjectType {
-    if index < 0.0 || index > array.len() as f64 - 1.0 {
-        return NULL;
-    }
+// negative indices count back from the end
+fn eval_array_index_expression(array: &[ObjectType], index: i64) -> ObjectType {
+    let index = if index < 0 {
+        array.len().checked_sub(index.unsigned_abs() as usize)
+    } else {
+        usize::try_from(index).ok()
+    };
+
+    index.and_then(|i| array.get(i)).cloned().unwrap_or(NULL)
+}
 
-    array[index as usize].clone()
+fn eval_string_index_expression(string: &str, index: i64) -> ObjectType {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| string.chars().nth(index))
+        .map_or(NULL, |char| ObjectType::StringObj(char.to_string()))
 }
 
 fn apply_function(
@@ -257,6 +489,13 @@ fn apply_function(
     env: &mut Environment,
 ) -> ObjectType {
     match function {
+        ObjectType::FunctionObj(func) if func.parameters.len() != args.len() => {
+            new_error(&format!(
+                "wrong number of arguments: want={}; got={}",
+                func.parameters.len(),
+                args.len()
+            ))
+        }
         ObjectType::FunctionObj(func) => {
             let mut extended_env = extend_func_env(func.clone(), args, env);
             let evaluated = eval(
@@ -265,11 +504,23 @@ fn apply_function(
             );
             unwrap_retrun_value(evaluated)
         }
-        ObjectType::BuiltinFunction(builtin) => builtin(args),
+        ObjectType::BuiltinFunction(builtin) => builtin(args, &mut Caller { env }),
         _ => new_error(&format!("not a function: {}", function.r#type())),
     }
 }
 
+/// Calls functions on behalf of builtins with the environment they were
+/// invoked from.
+struct Caller<'a> {
+    env: &'a mut Environment,
+}
+
+impl CallFunction for Caller<'_> {
+    fn call_function(&mut self, function: ObjectType, args: Vec<ObjectType>) -> ObjectType {
+        apply_function(function, args, self.env)
+    }
+}
+
 fn extend_func_env(func: Function, args: Vec<ObjectType>, env: &mut Environment) -> Environment {
     let mut env = env.clone();
     env.inner_store = Some(Box::new(func.inner_env.clone()));
@@ -340,6 +591,7 @@ fn eval_block_statements(block: &BlockStatement, env: &mut Environment) -> Objec
             && result_type == std::mem::discriminant(&ObjectType::ReturnValueObj(Box::default()))
             || result_type == std::mem::discriminant(&ObjectType::ErrorObj(String::default()))
             || result_type == std::mem::discriminant(&ObjectType::Break)
+            || result_type == std::mem::discriminant(&ObjectType::Continue)
         {
             return result;
         }
@@ -363,7 +615,7 @@ fn eval_if_expression(
     if is_truthy(c) {
         eval_block_statements(consequence, env)
     } else if let Some(a) = alt {
-        return eval_block_statements(a, env);
+        eval_block_statements(a, env)
     } else {
         NULL
     }
@@ -374,7 +626,20 @@ fn is_truthy(obj: ObjectType) -> bool {
 }
 
 fn eval_infix_statement(token: &Token, left: &ObjectType, right: &ObjectType) -> ObjectType {
+    // mixing in a FLOAT makes the whole operation FLOAT
+    if let (ObjectType::FloatObj(_), _) | (_, ObjectType::FloatObj(_)) = (left, right)
+        && let (Some(float_left), Some(float_right)) = (left.as_float(), right.as_float())
+    {
+        return eval_float_infix_statement(token, float_left, float_right);
+    }
+
     if std::mem::discriminant(&right.r#type()) != std::mem::discriminant(&left.r#type()) {
+        // values of different types are never equal, there's no coercion
+        match token {
+            Token::Eq => return FALSE,
+            Token::Not_eq => return TRUE,
+            _ => {}
+        }
         return new_error(&format!(
             "type mismatch: {} {} {}",
             left.r#type(),
@@ -383,16 +648,16 @@ fn eval_infix_statement(token: &Token, left: &ObjectType, right: &ObjectType) ->
         ));
     }
 
-    if let ObjectType::IntegerObj(int_left) = left {
-        if let ObjectType::IntegerObj(int_right) = right {
-            return eval_integer_infix_statement(token, int_left, int_right);
-        }
+    if let ObjectType::IntegerObj(int_left) = left
+        && let ObjectType::IntegerObj(int_right) = right
+    {
+        return eval_integer_infix_statement(token, int_left, int_right);
     }
 
-    if let ObjectType::StringObj(str_left) = left {
-        if let ObjectType::StringObj(str_right) = right {
-            return eval_string_infix_statement(token, str_left, str_right);
-        }
+    if let ObjectType::StringObj(str_left) = left
+        && let ObjectType::StringObj(str_right) = right
+    {
+        return eval_string_infix_statement(token, str_left, str_right);
     }
 
     match token {
@@ -418,12 +683,18 @@ fn eval_string_infix_statement(operator: &Token, left: &str, right: &str) -> Obj
     }
 }
 
-fn eval_integer_infix_statement(operator: &Token, left: &f64, right: &f64) -> ObjectType {
+fn eval_integer_infix_statement(operator: &Token, left: &i64, right: &i64) -> ObjectType {
     match operator {
-        Token::Plus => ObjectType::IntegerObj(left + right),
-        Token::Minus => ObjectType::IntegerObj(left - right),
-        Token::Asterisk => ObjectType::IntegerObj(left * right),
-        Token::Slash => ObjectType::IntegerObj(left / right),
+        Token::Plus => ObjectType::IntegerObj(left.wrapping_add(*right)),
+        Token::Minus => ObjectType::IntegerObj(left.wrapping_sub(*right)),
+        Token::Asterisk => ObjectType::IntegerObj(left.wrapping_mul(*right)),
+        Token::Slash | Token::Percent if *right == 0 => new_error("division by zero"),
+        // stays an INTEGER only when it divides evenly, `5 / 2` is 2.5
+        Token::Slash if left.wrapping_rem(*right) != 0 => {
+            ObjectType::FloatObj(*left as f64 / *right as f64)
+        }
+        Token::Slash => ObjectType::IntegerObj(left.wrapping_div(*right)),
+        Token::Percent => ObjectType::IntegerObj(left.wrapping_rem(*right)),
         Token::Lt => native_bool_to_bool_obj(left < right),
         Token::Gt => native_bool_to_bool_obj(left > right),
         Token::Eq => native_bool_to_bool_obj(left == right),
@@ -432,6 +703,22 @@ fn eval_integer_infix_statement(operator: &Token, left: &f64, right: &f64) -> Ob
     }
 }
 
+fn eval_float_infix_statement(operator: &Token, left: f64, right: f64) -> ObjectType {
+    match operator {
+        Token::Plus => ObjectType::FloatObj(left + right),
+        Token::Minus => ObjectType::FloatObj(left - right),
+        Token::Asterisk => ObjectType::FloatObj(left * right),
+        Token::Slash | Token::Percent if right == 0.0 => new_error("division by zero"),
+        Token::Slash => ObjectType::FloatObj(left / right),
+        Token::Percent => ObjectType::FloatObj(left % right),
+        Token::Lt => native_bool_to_bool_obj(left < right),
+        Token::Gt => native_bool_to_bool_obj(left > right),
+        Token::Eq => native_bool_to_bool_obj(left == right),
+        Token::Not_eq => native_bool_to_bool_obj(left != right),
+        _ => new_error(&format!("unknown operator: FLOAT {} FLOAT", operator)),
+    }
+}
+
 fn eval_prefix_expression(operator: &Token, right: ObjectType) -> ObjectType {
     match operator {
         Token::Bang => eval_bang_operator(right),
@@ -442,7 +729,8 @@ fn eval_prefix_expression(operator: &Token, right: ObjectType) -> ObjectType {
 
 fn eval_minus_prefix(right: ObjectType) -> ObjectType {
     match right {
-        ObjectType::IntegerObj(int) => ObjectType::IntegerObj(-int),
+        ObjectType::IntegerObj(int) => ObjectType::IntegerObj(int.wrapping_neg()),
+        ObjectType::FloatObj(float) => ObjectType::FloatObj(-float),
         _ => new_error(&format!("unknown operator: -{}", right.r#type())),
     }
 }
@@ -477,9 +765,7 @@ mod test {
 
     fn test_integer_object(object: &dyn Object, expected: f64) {
         match object.r#type() {
-            ObjectType::IntegerObj(_) => {
-                assert_eq!(object.inspect(), expected.to_string())
-            }
+            ObjectType::IntegerObj(_) => assert_eq!(object.inspect(), expected.to_string()),
             _ => panic!("Expected integer object, got {}", object.r#type()),
         }
     }
@@ -509,9 +795,13 @@ mod test {
             "50 / 2 * 2 + 10",
             "2 * (5 + 10)",
             "3 * (3 * 3) + 10",
+            "5 % 3",
+            "-7 % 3",
+            "1 + 10 % 4 * 2",
         ];
         let expected = vec![
-            5.0, 10.0, -5.0, -10.0, 10.0, 32.0, 0.0, 20.0, 25.0, 0.0, 60.0, 30.0, 37.0,
+            5.0, 10.0, -5.0, -10.0, 10.0, 32.0, 0.0, 20.0, 25.0, 0.0, 60.0, 30.0, 37.0, 2.0, -1.0,
+            5.0,
         ];
 
         for (i, v) in input.iter().enumerate() {
@@ -520,6 +810,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_integer_and_float_arithmetic() {
+        assert_eq!(test_eval("4 / 2"), ObjectType::IntegerObj(2));
+        assert_eq!(test_eval("5 / 2"), ObjectType::FloatObj(2.5));
+        assert_eq!(test_eval("5 / 2 * 2"), ObjectType::FloatObj(5.0));
+        assert_eq!(test_eval("-(5 / 2)"), ObjectType::FloatObj(-2.5));
+        assert_eq!(test_eval("5 / 2 == 2"), FALSE);
+
+        assert_eq!(test_eval("4 / 2").inspect(), "2");
+        assert_eq!(test_eval("9 / 2").inspect(), "4.5");
+    }
+
+    #[test]
+    fn test_float_literals() {
+        assert_eq!(test_eval("2.5"), ObjectType::FloatObj(2.5));
+        assert_eq!(test_eval("-0.5"), ObjectType::FloatObj(-0.5));
+        assert_eq!(test_eval("1.5 + 1"), ObjectType::FloatObj(2.5));
+        assert_eq!(test_eval("let x = 1.25; x * 4"), ObjectType::FloatObj(5.0));
+        assert_eq!(test_eval("2.5 == 5 / 2"), TRUE);
+        assert_eq!(test_eval("2.0").inspect(), "2");
+    }
+
     #[test]
     fn test_eval_bool() {
         let inputs = vec![
@@ -540,10 +852,14 @@ mod test {
             "false != true",
             "(1 < 2) == true",
             "(1 < 2) == false",
+            "1 == true",
+            "0 == false",
+            "\"1\" == 1",
+            "1 != true",
         ];
         let expected = vec![
             true, false, true, false, false, false, true, false, false, true, true, true, false,
-            true, true, true, false,
+            true, true, true, false, false, false, false, true,
         ];
 
         for (i, v) in inputs.iter().enumerate() {
@@ -554,8 +870,8 @@ mod test {
 
     #[test]
     fn test_bang_operator() {
-        let inputs = vec!["!true", "!false", "!5", "!!true", "!!false", "!!5"];
-        let expected = vec![false, true, false, true, false, true];
+        let inputs = ["!true", "!false", "!5", "!!true", "!!false", "!!5"];
+        let expected = [false, true, false, true, false, true];
 
         for (i, v) in inputs.iter().enumerate() {
             let evaluated = test_eval(v);
@@ -565,7 +881,7 @@ mod test {
 
     #[test]
     fn test_if_else_expressions() {
-        let inputs = vec![
+        let inputs = [
             "if (true) { 10 }",
             "if (false) { 10 }",
             "if (1) { 10 }",
@@ -574,7 +890,7 @@ mod test {
             "if (1 < 2) { 10 } else { 20 }",
             "if (1 > 2) { 10 } else { 20 }",
         ];
-        let expected = vec![
+        let expected = [
             Some(10.0),
             None,
             Some(10.0),
@@ -587,7 +903,7 @@ mod test {
         for (i, v) in inputs.iter().enumerate() {
             let evaluted = test_eval(v);
             match evaluted {
-                ObjectType::IntegerObj(int) => assert_eq!(int, expected[i].unwrap()),
+                ObjectType::IntegerObj(int) => assert_eq!(int as f64, expected[i].unwrap()),
                 ObjectType::NullObj => assert!(expected[i].is_none()),
                 _ => panic!("Only expected ints or nulls"),
             }
@@ -596,7 +912,7 @@ mod test {
 
     #[test]
     fn test_return_statements() {
-        let inputs = vec![
+        let inputs = [
             "return 10;",
             "return 10; 9;",
             "return 2 * 5; 9;",
@@ -633,6 +949,8 @@ mod test {
             }"#,
             "foobar",
             "\"Hello\" - \"World\";",
+            "5 % 0",
+            "5 / 0",
         ];
         let expected = vec![
             "type mismatch: INTEGER + BOOLEAN",
@@ -644,6 +962,8 @@ mod test {
             "unknown operator: BOOLEAN + BOOLEAN",
             "identifier not found: foobar",
             "unknown operator: STRING - STRING",
+            "division by zero",
+            "division by zero",
         ];
 
         for (i, v) in inputs.iter().enumerate() {
@@ -675,7 +995,7 @@ mod test {
     fn test_function_object() {
         let input = "fn(x) { x + 2; };";
 
-        if let ObjectType::FunctionObj(func) = test_eval(&input) {
+        if let ObjectType::FunctionObj(func) = test_eval(input) {
             assert_eq!(func.parameters.len(), 1);
             assert_eq!(func.parameters[0].token_literal(), "x");
             assert_eq!(func.body.to_string(), "(x + 2)");
@@ -686,7 +1006,7 @@ mod test {
 
     #[test]
     fn test_function_application() {
-        let inputs = vec![
+        let inputs = [
             "let identity = fn(x) { x; }; identity(5);",
             "let identity = fn(x) { return x; }; identity(5);",
             "let double = fn(x) { x * 2}; double(5);",
@@ -694,7 +1014,7 @@ mod test {
             "let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));",
             "fn(x) { x; }(5)",
         ];
-        let expected = vec![5.0, 5.0, 10.0, 10.0, 20.0, 5.0];
+        let expected = [5.0, 5.0, 10.0, 10.0, 20.0, 5.0];
 
         for (i, v) in inputs.iter().enumerate() {
             test_integer_object(&test_eval(v), expected[i]);
@@ -711,7 +1031,7 @@ mod test {
         let addTwo = newAdder(2);
         addTwo(2);"#;
 
-        test_integer_object(&test_eval(&input), 4.0);
+        test_integer_object(&test_eval(input), 4.0);
     }
 
     #[test]
@@ -752,7 +1072,7 @@ mod test {
 
     #[test]
     fn test_builtin_funcs() {
-        let inputs = vec![
+        let inputs = [
             "len(\"\")",
             "len(\"four\")",
             "len(\"hello world\")",
@@ -768,7 +1088,7 @@ mod test {
 
         for (i, input) in inputs.iter().enumerate() {
             match test_eval(input) {
-                ObjectType::IntegerObj(e) => assert_eq!(e, expected_int[i]),
+                ObjectType::IntegerObj(e) => assert_eq!(e as f64, expected_int[i]),
                 ObjectType::ErrorObj(s) => assert_eq!(s, expected_error[i - 3]),
                 _ => unreachable!("only ints and errors expected"),
             }
@@ -777,7 +1097,7 @@ mod test {
 
     #[test]
     fn test_array_builtin_funcs() {
-        let inputs = vec![
+        let inputs = [
             "len([1,2,0])",
             "len([])",
             "first([3,2,1])",
@@ -788,7 +1108,7 @@ mod test {
 
         for (i, input) in inputs.iter().enumerate() {
             match test_eval(input) {
-                ObjectType::IntegerObj(e) => assert_eq!(e, expected_int[i]),
+                ObjectType::IntegerObj(e) => assert_eq!(e as f64, expected_int[i]),
                 _ => unreachable!("only ints expected"),
             }
         }
@@ -803,7 +1123,7 @@ mod test {
                 array
                     .iter()
                     .map(|obj| match obj {
-                        ObjectType::IntegerObj(v) => v.to_owned(),
+                        ObjectType::IntegerObj(v) => *v as f64,
                         _ => unreachable!("only ints in array expected"),
                     })
                     .collect::<Vec<_>>(),
@@ -841,7 +1161,7 @@ mod test {
 
     #[test]
     fn test_index_expressions() {
-        let inputs = vec![
+        let inputs = [
             ("[1,2,3][0]", 1.0),
             ("[1,2,3][1]", 2.0),
             ("[1,2,3][2]", 3.0),
@@ -849,18 +1169,40 @@ mod test {
 
         for (i, v) in inputs.iter().enumerate() {
             match test_eval(v.0) {
-                ObjectType::IntegerObj(int) => assert_eq!(int, inputs[i].1),
+                ObjectType::IntegerObj(int) => assert_eq!(int as f64, inputs[i].1),
                 _ => unreachable!("expected an integer object"),
             }
         }
     }
 
+    #[test]
+    fn test_string_index() {
+        assert_eq!(
+            test_eval(r#""abc"[1]"#),
+            ObjectType::StringObj("b".to_string())
+        );
+        assert_eq!(
+            test_eval(r#""héllo"[1]"#),
+            ObjectType::StringObj("é".to_string())
+        );
+        assert_eq!(test_eval(r#""abc"[9]"#), ObjectType::NullObj);
+        assert_eq!(test_eval(r#""abc"[-1]"#), ObjectType::NullObj);
+    }
+
     #[test]
     fn test_out_of_bounds_index() {
         let input = "[1,2][2]";
         assert_eq!(test_eval(input), ObjectType::NullObj)
     }
 
+    #[test]
+    fn test_negative_index() {
+        test_integer_object(&test_eval("[1,2,3][-1]"), 3.0);
+        test_integer_object(&test_eval("[1,2,3][-3]"), 1.0);
+        assert_eq!(test_eval("[1,2,3][-4]"), ObjectType::NullObj);
+        assert_eq!(test_eval("[][-1]"), ObjectType::NullObj);
+    }
+
     #[test]
     fn test_hash_literals() {
         let input = r#"
@@ -897,7 +1239,7 @@ mod test {
                     3.0,
                 );
                 test_integer_object(
-                    &map.get(&ObjectType::IntegerObj(4.0).hash().unwrap())
+                    &map.get(&ObjectType::IntegerObj(4).hash().unwrap())
                         .unwrap()
                         .value,
                     4.0,
@@ -985,9 +1327,289 @@ mod test {
         test_integer_object(&test_eval(input), 7.0);
     }
 
+    #[test]
+    fn test_while_let() {
+        let input = r#"
+        let items = [1, 2, 3];
+        let i = 0;
+        let total = 0;
+        while (let x = items[i]) { total = total + x; i = i + 1; }
+        total;
+        "#;
+        test_integer_object(&test_eval(input), 6.0);
+    }
+
+    #[test]
+    fn test_while() {
+        let input = "let i = 0; let sum = 0; while (i < 5) { sum = sum + i; i = i + 1; } sum;";
+        test_integer_object(&test_eval(input), 10.0);
+    }
+
+    #[test]
+    fn test_apply() {
+        test_integer_object(&test_eval("apply(fn(a, b) { a + b }, [1, 2])"), 3.0);
+        test_integer_object(&test_eval("apply(fn() { 7 }, [])"), 7.0);
+        assert_eq!(
+            test_eval("apply(fn(a, b) { a + b }, [1])"),
+            ObjectType::ErrorObj("wrong number of arguments: want=2; got=1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        match test_eval("map([1, 2, 3], fn(x) { x * 2 })") {
+            ObjectType::ArrayObj(array) => {
+                test_integer_object(&array[0], 2.0);
+                test_integer_object(&array[1], 4.0);
+                test_integer_object(&array[2], 6.0);
+            }
+            other => panic!("expected an array, got {other:?}"),
+        }
+        assert_eq!(
+            test_eval("map([1, 2], 3)"),
+            ObjectType::ErrorObj(
+                "second argument to `map` must be FUNCTION, got INTEGER".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_type() {
+        for (input, expected) in [
+            ("type(5)", "INTEGER"),
+            (r#"type("x")"#, "STRING"),
+            ("type([1])", "ARRAY"),
+            ("type(fn(x) { x })", "FUNCTION"),
+        ] {
+            assert_eq!(
+                test_eval(input),
+                ObjectType::StringObj(expected.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_assert_type() {
+        test_integer_object(&test_eval(r#"assert_type(len("ab"), "INTEGER")"#), 2.0);
+        assert_eq!(
+            test_eval(r#"assert_type("a", "ARRAY")"#),
+            ObjectType::ErrorObj("type assertion failed: expected ARRAY, got STRING".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reduce() {
+        test_integer_object(
+            &test_eval("reduce([1, 2, 3, 4], 0, fn(a, b) { a + b })"),
+            10.0,
+        );
+        test_integer_object(&test_eval("reduce([], 7, fn(a, b) { a + b })"), 7.0);
+        assert_eq!(
+            test_eval("reduce([1], 0, fn(a) { a })"),
+            ObjectType::ErrorObj(
+                "function passed to `reduce` must take 2 arguments, takes 1".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_array_and_hash_builtins() {
+        let inputs = [
+            ("filter([1, 2, 3, 4], fn(x) { x > 2 })", "[3, 4]"),
+            ("partition([1, 2, 3], fn(x) { x < 2 })", "[[1], [2, 3]]"),
+            ("take_while([1, 2, 3, 1], fn(x) { x < 3 })", "[1, 2]"),
+            ("drop_while([1, 2, 3, 1], fn(x) { x < 3 })", "[3, 1]"),
+            ("scan([1, 2, 3], 0, fn(a, x) { a + x })", "[0, 1, 3, 6]"),
+            ("chunk([1, 2, 3], 2)", "[[1, 2], [3]]"),
+            ("window([1, 2, 3], 2)", "[[1, 2], [2, 3]]"),
+            ("any([1, 2], fn(x) { x > 1 })", "true"),
+            ("all([1, 2], fn(x) { x > 1 })", "false"),
+            ("is_sorted([1, 2, 2])", "true"),
+            ("len(set({}, 1, 2))", "1"),
+        ];
+
+        for (input, expected) in inputs {
+            assert_eq!(test_eval(input), test_eval(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_seeded_shuffle() {
+        let input = "seed(3); shuffle([1, 2, 3, 4, 5, 6])";
+        let shuffled = test_eval(input);
+
+        assert_eq!(test_eval(input), shuffled);
+        assert_eq!(
+            test_eval("len(shuffle([1, 2, 3]))"),
+            ObjectType::IntegerObj(3)
+        );
+    }
+
+    #[test]
+    fn test_index_assignment() {
+        test_integer_object(&test_eval("let a = [1, 2, 3]; a[0] = 9; a[0]"), 9.0);
+        test_integer_object(&test_eval("let a = [[1], [2]]; a[1][0] = 7; a[1][0]"), 7.0);
+        test_integer_object(&test_eval(r#"let h = {}; h["k"] = 3; h["k"]"#), 3.0);
+        test_integer_object(&test_eval("let a = [1, 2, 3]; a[-1] = 9; a[2]"), 9.0);
+        assert_eq!(
+            test_eval("let a = [1]; a[1] = 2;"),
+            ObjectType::ErrorObj("index 1 out of bounds for ARRAY of length 1".to_string())
+        );
+        assert_eq!(
+            test_eval("let a = [1]; a[-2] = 2;"),
+            ObjectType::ErrorObj("index -2 out of bounds for ARRAY of length 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_for() {
+        let input = "let sum = 0; for (let i = 0; i < 5; i = i + 1) { sum = sum + i } sum";
+        test_integer_object(&test_eval(input), 10.0);
+
+        let input = r#"
+        let i = 7;
+        let n = 0;
+        for (let i = 0; i < 3; i++) {
+            for (let i = 0; i < 2; i++) { n++; }
+        }
+        n + i
+        "#;
+        test_integer_object(&test_eval(input), 13.0);
+    }
+
+    #[test]
+    fn test_modules() {
+        let input = "mod math { let pi = 3; let area = fn(r) { pi * r * r }; } math.area(2)";
+        test_integer_object(&test_eval(input), 12.0);
+        test_integer_object(&test_eval("let pi = 1; mod math { let pi = 3; } pi"), 1.0);
+        assert_eq!(
+            test_eval("mod math { let pi = 3; } math.tau"),
+            ObjectType::ErrorObj("undefined member: math.tau".to_string())
+        );
+    }
+
+    #[test]
+    fn test_continue() {
+        let input = "let sum = 0; for (let i = 0; i < 6; i++) { if (i % 2 == 1) { continue; }; sum = sum + i; } sum";
+        test_integer_object(&test_eval(input), 6.0);
+
+        let input = "let i = 0; let n = 0; loop { i++; if (i > 4) { break; }; if (i == 2) { continue; }; n++; } n";
+        test_integer_object(&test_eval(input), 3.0);
+    }
+
     #[test]
     fn test_mutate() {
         let input = "let a = 5; a = a + 1; a;";
         test_integer_object(&test_eval(input), 6.0);
     }
+
+    #[test]
+    fn test_group_by() {
+        let input = "group_by([1, 2, 3, 4], fn(x) { x > 2 })";
+
+        match test_eval(input) {
+            ObjectType::HashObj(map) => {
+                assert_eq!(map.len(), 2);
+                assert_eq!(
+                    map[&FALSE.hash().unwrap()].value,
+                    ObjectType::ArrayObj(vec![
+                        ObjectType::IntegerObj(1),
+                        ObjectType::IntegerObj(2)
+                    ])
+                );
+                assert_eq!(
+                    map[&TRUE.hash().unwrap()].value,
+                    ObjectType::ArrayObj(vec![
+                        ObjectType::IntegerObj(3),
+                        ObjectType::IntegerObj(4)
+                    ])
+                );
+            }
+            _ => panic!("expected hash object"),
+        }
+    }
+
+    #[test]
+    fn test_max_by_and_min_by() {
+        let input = "max_by([[1], [1, 2], [1, 2, 3]], fn(a) { len(a) })";
+        assert_eq!(
+            test_eval(input),
+            ObjectType::ArrayObj(vec![
+                ObjectType::IntegerObj(1),
+                ObjectType::IntegerObj(2),
+                ObjectType::IntegerObj(3)
+            ])
+        );
+
+        let input = "min_by([3, -7, 5], fn(x) { -x })";
+        test_integer_object(&test_eval(input), 5.0);
+    }
+
+    #[test]
+    fn test_assignment_to_undeclared_variable() {
+        for input in ["b = 5;", "b++;"] {
+            assert_eq!(
+                test_eval(input),
+                ObjectType::ErrorObj("cannot assign to undeclared variable: b".to_string())
+            );
+        }
+
+        test_integer_object(&test_eval("let b = 0; b = 5; b;"), 5.0);
+    }
+
+    #[test]
+    fn test_substr() {
+        assert_eq!(
+            test_eval(r#"substr("hello", 1, 3)"#),
+            ObjectType::StringObj("ell".to_string())
+        );
+        assert_eq!(
+            test_eval(r#"substr("hello", 3)"#),
+            ObjectType::StringObj("lo".to_string())
+        );
+        assert_eq!(
+            test_eval(r#"substr("hello", 1, "3")"#),
+            ObjectType::ErrorObj("length for `substr` must be INTEGER, got STRING".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fmt_num() {
+        assert_eq!(
+            test_eval("fmt_num(3.14159, 2)"),
+            ObjectType::StringObj("3.14".to_string())
+        );
+        assert_eq!(
+            test_eval("fmt_num(2 / 3, 3)"),
+            ObjectType::StringObj("0.667".to_string())
+        );
+        assert_eq!(
+            test_eval("fmt_num(5, 2)"),
+            ObjectType::StringObj("5.00".to_string())
+        );
+        assert_eq!(
+            test_eval("fmt_num(1, 70000)"),
+            ObjectType::ErrorObj(
+                "decimals for `fmt_num` must be at most 100, got 70000".to_string()
+            )
+        );
+        assert_eq!(
+            test_eval(r#"fmt_num("1", 2)"#),
+            ObjectType::ErrorObj(
+                "arguments to `fmt_num` must be INTEGER or FLOAT, got STRING and INTEGER"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_divmod_and_destructuring() {
+        test_integer_object(&test_eval("let [q, r] = divmod(17, 5); q;"), 3.0);
+        test_integer_object(&test_eval("let [q, r] = divmod(17, 5); r;"), 2.0);
+
+        assert_eq!(
+            test_eval("let [a, b] = [1];"),
+            ObjectType::ErrorObj("cannot destructure ARRAY of length 1 into 2 names".to_string())
+        );
+    }
 }
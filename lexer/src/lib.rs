@@ -1,8 +1,21 @@
 mod helpers;
 
 use helpers::{is_digit, is_letter};
-use std::char;
-use token::{Token, look_up_ident};
+use std::{char, collections::HashMap, sync::Arc};
+use token::{Token, keyword};
+
+/// Where a token starts, both counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, col {}", self.line, self.column)
+    }
+}
 
 /// The Monkey lexer.
 ///
@@ -16,7 +29,7 @@ use token::{Token, look_up_ident};
 ///
 /// let expected: [Token; 6] = [
 ///     Token::Let,
-///     Token::Ident("a".to_string()),
+///     Token::Ident("a".into()),
 ///     Token::Assign,
 ///     Token::Int(5),
 ///     Token::Semicolon,
@@ -45,28 +58,86 @@ pub struct Lexer<'a> {
     read_position: usize,
     // ascii only
     ch: u8,
+    // where the last token returned started, relative to the input
+    token_start: usize,
+    // the line ch is on and the line the last token returned started on
+    line: usize,
+    token_line: usize,
+    // where those lines start in the input
+    line_start: usize,
+    token_line_start: usize,
+    // added to every position reported, for lexing a slice of a larger source
+    base_offset: usize,
+    // every identifier seen so far, so repeats share one allocation
+    idents: HashMap<&'a str, Arc<str>>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_offset(input, 0)
+    }
+
+    /// Lexes `input` as a slice starting `base_offset` bytes into some larger
+    /// source, so the positions it reports line up with that source.
+    pub fn with_offset(input: &'a str, base_offset: usize) -> Self {
         let mut lexer = Lexer {
             input,
             position: 0,
             read_position: 0,
             ch: u8::default(),
+            token_start: 0,
+            line: 1,
+            token_line: 1,
+            line_start: 0,
+            token_line_start: 0,
+            base_offset,
+            idents: HashMap::new(),
         };
 
         lexer.init();
         lexer
     }
 
+    /// The byte offset the most recently returned token started at.
+    pub fn token_start(&self) -> usize {
+        self.base_offset + self.token_start
+    }
+
+    /// The line, counting from 1, the most recently returned token started on.
+    /// `\r\n`, `\n` and a lone `\r` each end a line. Lines are counted from
+    /// the start of this lexer's input, `with_offset` doesn't shift them.
+    pub fn token_line(&self) -> usize {
+        self.token_line
+    }
+
+    /// The line and column the most recently returned token started at.
+    /// Columns count characters, not bytes, from the start of the line.
+    pub fn token_position(&self) -> Position {
+        let before = self
+            .input
+            .get(self.token_line_start..self.token_start)
+            .map_or(self.token_start - self.token_line_start, |s| {
+                s.chars().count()
+            });
+
+        Position {
+            line: self.token_line,
+            column: before + 1,
+        }
+    }
+
     fn init(&mut self) {
         self.read_char();
     }
 
     pub fn next_token(&mut self) -> Token {
         self.skip_white_space();
-        self.skip_comments();
+        if let Err(unterminated) = self.skip_comments() {
+            return unterminated;
+        }
+        self.token_start = self.position;
+        self.token_line = self.line;
+        self.token_line_start = self.line_start;
 
         let tok = match self.ch as char {
             '=' => {
@@ -101,22 +172,29 @@ impl<'a> Lexer<'a> {
             '[' => Token::Lbracket,
             ']' => Token::Rbracket,
             ',' => Token::Comma,
-            '+' => {
-                if self.peek_char() == b'+' {
+            '.' => Token::Dot,
+            '+' => match self.peek_char() {
+                b'+' => {
                     self.read_char();
                     Token::PlusPlus
-                } else {
-                    Token::Plus
                 }
-            }
-            '-' => {
-                if self.peek_char() == b'-' {
+                b'=' => {
+                    self.read_char();
+                    Token::PlusEq
+                }
+                _ => Token::Plus,
+            },
+            '-' => match self.peek_char() {
+                b'-' => {
                     self.read_char();
                     Token::MinusMinus
-                } else {
-                    Token::Minus
                 }
-            }
+                b'=' => {
+                    self.read_char();
+                    Token::MinusEq
+                }
+                _ => Token::Minus,
+            },
             '!' => {
                 if self.peek_char() == b'=' {
                     self.read_char();
@@ -126,24 +204,41 @@ impl<'a> Lexer<'a> {
                     Token::Bang
                 }
             }
-            '*' => Token::Asterisk,
-            '/' => Token::Slash,
+            '*' => {
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    Token::AsteriskEq
+                } else {
+                    Token::Asterisk
+                }
+            }
+            '/' => {
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    Token::SlashEq
+                } else {
+                    Token::Slash
+                }
+            }
+            '%' => Token::Percent,
             '>' => Token::Gt,
             '<' => Token::Lt,
             '\0' => Token::Eof,
             ':' => Token::Colon,
             '"' => Token::String(self.read_string()),
             ch => {
-                if is_letter(ch) {
-                    let literal = self.read_identifier();
-                    match look_up_ident(literal) {
-                        Token::Ident(_) => return Token::Ident(literal.to_string()),
-                        token => return token,
-                    };
+                if ch == 'r'
+                    && let Some(raw) = self.read_raw_string()
+                {
+                    Token::String(raw)
+                } else if is_letter(ch) {
+                    let ident = self.read_identifier();
+                    return keyword(ident).unwrap_or_else(|| Token::Ident(self.intern(ident)));
                 } else if is_digit(ch) {
-                    return Token::Int(self.read_number());
+                    return self.read_number();
+                } else {
+                    Token::Illegal(self.input[self.position..self.position + 1].to_string())
                 }
-                Token::Illegal(self.input[self.position..self.position + 1].to_string())
             }
         };
 
@@ -162,15 +257,53 @@ impl<'a> Lexer<'a> {
         self.input[position..self.position].to_string()
     }
 
-    fn read_number(&mut self) -> usize {
+    /// Reads `r"..."` or `r#"..."#` starting at the `r`, taking everything up
+    /// to the matching close as is. Returns `None`, consuming nothing, when the
+    /// `r` doesn't start a raw string.
+    fn read_raw_string(&mut self) -> Option<String> {
+        let rest = &self.input[self.read_position..];
+        let hashes = rest.bytes().take_while(|&b| b == b'#').count();
+        if rest.as_bytes().get(hashes) != Some(&b'"') {
+            return None;
+        }
+
+        let start = self.read_position + hashes + 1;
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let end = self.input[start..]
+            .find(&closing)
+            .map_or(self.input.len(), |i| start + i);
+        let raw = self.input[start..end].to_string();
+
+        // leave ch on the last char of the closing delimiter
+        let last = (end + closing.len() - 1).min(self.input.len());
+        while self.position < last {
+            self.read_char();
+        }
+
+        Some(raw)
+    }
+
+    /// Reads an INTEGER, or a FLOAT when the digits go on past a `.`, as in
+    /// `3.14`. A `.` with no digit after it is left for the next token.
+    fn read_number(&mut self) -> Token {
         let position = self.position;
         while is_digit(self.ch as char) {
             self.read_char();
         }
 
-        self.input[position..self.position]
-            .parse::<usize>()
-            .unwrap()
+        if self.ch == b'.' && is_digit(self.peek_char() as char) {
+            self.read_char();
+            while is_digit(self.ch as char) {
+                self.read_char();
+            }
+            return Token::Float(self.input[position..self.position].to_string());
+        }
+
+        Token::Int(
+            self.input[position..self.position]
+                .parse::<usize>()
+                .unwrap(),
+        )
     }
 
     fn skip_white_space(&mut self) {
@@ -179,17 +312,62 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn skip_comments(&mut self) {
-        if self.ch as char == '/' && self.peek_char() == b'/' {
-            while !(self.ch == b'\n' || self.ch == b'\r') {
-                self.read_char();
+    fn skip_comments(&mut self) -> Result<(), Token> {
+        loop {
+            match (self.ch, self.peek_char()) {
+                (b'/', b'/') => {
+                    while !(self.ch == b'\n' || self.ch == b'\r' || self.ch == 0) {
+                        self.read_char();
+                    }
+                }
+                (b'/', b'*') => self.skip_block_comment()?,
+                _ => return Ok(()),
             }
             self.skip_white_space();
-            self.skip_comments();
         }
     }
 
-    fn read_identifier(&mut self) -> &str {
+    /// Skips a `/* */` comment, which can have others nested inside it. One
+    /// still open at the end of input is returned as an illegal token placed
+    /// where it started, rather than quietly taking the rest of the file.
+    fn skip_block_comment(&mut self) -> Result<(), Token> {
+        let (start, line, line_start) = (self.position, self.line, self.line_start);
+        let mut depth = 0;
+
+        loop {
+            match (self.ch, self.peek_char()) {
+                (b'/', b'*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                (b'*', b'/') => {
+                    depth -= 1;
+                    self.read_char();
+                }
+                (0, _) => {
+                    self.token_start = start;
+                    self.token_line = line;
+                    self.token_line_start = line_start;
+                    return Err(Token::Illegal("unterminated comment".to_string()));
+                }
+                _ => {}
+            }
+            self.read_char();
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn intern(&mut self, ident: &'a str) -> Arc<str> {
+        self.idents
+            .entry(ident)
+            .or_insert_with(|| ident.into())
+            .clone()
+    }
+
+    fn read_identifier(&mut self) -> &'a str {
         let position = self.position;
         while is_letter(self.ch as char) {
             self.read_char();
@@ -207,6 +385,12 @@ impl<'a> Lexer<'a> {
     }
 
     fn read_char(&mut self) {
+        // the \r of a \r\n is passed over without counting, the \n counts
+        if self.ch == b'\n' || (self.ch == b'\r' && self.peek_char() != b'\n') {
+            self.line += 1;
+            self.line_start = self.read_position;
+        }
+
         if self.read_position >= self.input.len() {
             self.ch = b'\0'
         } else {
@@ -1,4 +1,5 @@
 use lexer::*;
+use std::sync::Arc;
 use token::Token;
 
 #[test]
@@ -41,7 +42,7 @@ fn test_next_token_complex() {
             };
 
             let result = add(five, ten);
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -65,39 +66,39 @@ fn test_next_token_complex() {
 
     let expected = vec![
         Token::Let,
-        Token::Ident("five".to_string()),
+        Token::Ident("five".into()),
         Token::Assign,
         Token::Int(5),
         Token::Semicolon,
         Token::Let,
-        Token::Ident("ten".to_string()),
+        Token::Ident("ten".into()),
         Token::Assign,
         Token::Int(10),
         Token::Semicolon,
         Token::Let,
-        Token::Ident("add".to_string()),
+        Token::Ident("add".into()),
         Token::Assign,
         Token::Function,
         Token::Lparen,
-        Token::Ident("x".to_string()),
+        Token::Ident("x".into()),
         Token::Comma,
-        Token::Ident("y".to_string()),
+        Token::Ident("y".into()),
         Token::Rparen,
         Token::Lbrace,
-        Token::Ident("x".to_string()),
+        Token::Ident("x".into()),
         Token::Plus,
-        Token::Ident("y".to_string()),
+        Token::Ident("y".into()),
         Token::Semicolon,
         Token::Rbrace,
         Token::Semicolon,
         Token::Let,
-        Token::Ident("result".to_string()),
+        Token::Ident("result".into()),
         Token::Assign,
-        Token::Ident("add".to_string()),
+        Token::Ident("add".into()),
         Token::Lparen,
-        Token::Ident("five".to_string()),
+        Token::Ident("five".into()),
         Token::Comma,
-        Token::Ident("ten".to_string()),
+        Token::Ident("ten".into()),
         Token::Rparen,
         Token::Semicolon,
         Token::Bang,
@@ -167,10 +168,10 @@ fn test_next_token_complex() {
         Token::Semicolon,
         Token::Rbrace,
         Token::Semicolon,
-        Token::Ident("a".to_string()),
+        Token::Ident("a".into()),
         Token::PlusPlus,
         Token::Semicolon,
-        Token::Ident("b".to_string()),
+        Token::Ident("b".into()),
         Token::MinusMinus,
         Token::Semicolon,
         Token::Eof,
@@ -188,3 +189,303 @@ fn test_next_token_complex() {
         }
     }
 }
+
+#[test]
+fn test_comment_at_end_of_input() {
+    let mut lexer = Lexer::new("5; // no trailing newline");
+
+    assert_eq!(lexer.next_token(), Token::Int(5));
+    assert_eq!(lexer.next_token(), Token::Semicolon);
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_line_comments() {
+    let input = "let x = 5; // this is x, let y = 6;\n// a whole line // of comment\nx / 2 // last";
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token() {
+            Token::Eof => break,
+            token => tokens.push(token),
+        }
+    }
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Ident("x".into()),
+            Token::Assign,
+            Token::Int(5),
+            Token::Semicolon,
+            Token::Ident("x".into()),
+            Token::Slash,
+            Token::Int(2),
+        ]
+    );
+
+    // not a comment inside a string
+    let mut lexer = Lexer::new(r#""http://example.com" // trailing"#);
+    assert_eq!(
+        lexer.next_token(),
+        Token::String("http://example.com".to_string())
+    );
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_block_comments() {
+    let inputs = [
+        "1 /* one */ + 2",
+        "1 /* outer /* inner */ still comment */ + 2",
+        "1 /**/ + /* a\nfew\r\nlines */ 2 /* /* /**/ */ */",
+        "/* 3 */ 1 + // /* not opened\n2",
+    ];
+    for input in inputs {
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.next_token(), Token::Int(1), "{input}");
+        assert_eq!(lexer.next_token(), Token::Plus, "{input}");
+        assert_eq!(lexer.next_token(), Token::Int(2), "{input}");
+        assert_eq!(lexer.next_token(), Token::Eof, "{input}");
+    }
+
+    let mut lexer = Lexer::new("1 / 2 * 3");
+    assert_eq!(lexer.next_token(), Token::Int(1));
+    assert_eq!(lexer.next_token(), Token::Slash);
+
+    for input in ["1;\n  /* open", "1;\n  /* a /* b */", "1;\n  /*/"] {
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.next_token(), Token::Int(1));
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+        assert_eq!(
+            lexer.next_token(),
+            Token::Illegal("unterminated comment".to_string()),
+            "{input}"
+        );
+        assert_eq!(lexer.token_position(), Position { line: 2, column: 3 });
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+}
+
+#[test]
+fn test_raw_strings() {
+    let mut lexer = Lexer::new(r###"r"C:\path\n" r#"say "hi""# r "x""###);
+
+    assert_eq!(lexer.next_token(), Token::String(r"C:\path\n".to_string()));
+    assert_eq!(lexer.next_token(), Token::String(r#"say "hi""#.to_string()));
+    assert_eq!(lexer.next_token(), Token::Ident("r".into()));
+    assert_eq!(lexer.next_token(), Token::String("x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+
+    let mut lexer = Lexer::new(r#"r"unterminated"#);
+    assert_eq!(
+        lexer.next_token(),
+        Token::String("unterminated".to_string())
+    );
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_token_positions() {
+    let source = "let total = 5 + x;";
+    let mut lexer = Lexer::new(source);
+    let mut starts = Vec::new();
+    while lexer.next_token() != Token::Eof {
+        starts.push(lexer.token_start());
+    }
+    assert_eq!(starts, vec![0, 4, 10, 12, 14, 16, 17]);
+
+    // re-lex "5 + x" out of the middle of the source
+    let mut lexer = Lexer::with_offset(&source[12..17], 12);
+    let mut sub_starts = Vec::new();
+    while lexer.next_token() != Token::Eof {
+        sub_starts.push(lexer.token_start());
+    }
+    assert_eq!(sub_starts, starts[3..6]);
+    assert_eq!(lexer.token_start(), 17);
+}
+
+/// Every token with the line it started on.
+fn tokens_with_lines(input: &str) -> Vec<(Token, usize)> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        tokens.push((token.clone(), lexer.token_line()));
+        if token == Token::Eof {
+            return tokens;
+        }
+    }
+}
+
+#[test]
+fn test_line_endings() {
+    let unix = "let a = 1; // one\nlet b = \"x\";\n\n// a comment\nb\n";
+    let windows = unix.replace('\n', "\r\n");
+    let old_mac = unix.replace('\n', "\r");
+
+    let expected = tokens_with_lines(unix);
+    assert_eq!(expected[0], (Token::Let, 1));
+    assert_eq!(expected[5], (Token::Let, 2));
+    assert_eq!(expected[10], (Token::Ident("b".into()), 5));
+    assert_eq!(expected[11], (Token::Eof, 6));
+
+    assert_eq!(tokens_with_lines(&windows), expected);
+    assert_eq!(tokens_with_lines(&old_mac), expected);
+
+    // mixed in one file
+    let mixed = "a\r\nb\nc\rd\n\r\ne";
+    let lines = tokens_with_lines(mixed)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines, vec![1, 2, 3, 4, 6, 6]);
+}
+
+#[test]
+fn test_token_positions_by_line() {
+    let input = "let a = 1;\r\n  let b = \"üü\";\n\tb";
+    let mut lexer = Lexer::new(input);
+    let mut positions = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let Position { line, column } = lexer.token_position();
+        positions.push((line, column));
+        if token == Token::Eof {
+            break;
+        }
+    }
+
+    assert_eq!(
+        positions,
+        vec![
+            (1, 1),
+            (1, 5),
+            (1, 7),
+            (1, 9),
+            (1, 10),
+            (2, 3),
+            (2, 7),
+            (2, 9),
+            (2, 11),
+            (2, 15),
+            (3, 2),
+            (3, 3),
+        ]
+    );
+    assert_eq!(
+        Position {
+            line: 3,
+            column: 12
+        }
+        .to_string(),
+        "line 3, col 12"
+    );
+}
+
+#[test]
+fn test_identifiers_are_interned() {
+    let mut lexer = Lexer::new("total + other + total");
+    let mut idents = Vec::new();
+    loop {
+        match lexer.next_token() {
+            Token::Ident(name) => idents.push(name),
+            Token::Eof => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(idents.len(), 3);
+    assert!(Arc::ptr_eq(&idents[0], &idents[2]));
+    assert!(!Arc::ptr_eq(&idents[0], &idents[1]));
+    assert_eq!(&*idents[2], "total");
+}
+
+#[test]
+fn test_percent() {
+    let mut lexer = Lexer::new("10 % 3;");
+
+    assert_eq!(lexer.next_token(), Token::Int(10));
+    assert_eq!(lexer.next_token(), Token::Percent);
+    assert_eq!(lexer.next_token(), Token::Int(3));
+    assert_eq!(lexer.next_token(), Token::Semicolon);
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_compound_assignment() {
+    let mut lexer = Lexer::new("a += 1; a -= 2; a *= 3; a /= 4; a + = 5;");
+
+    for operator in [
+        Token::PlusEq,
+        Token::MinusEq,
+        Token::AsteriskEq,
+        Token::SlashEq,
+    ] {
+        assert_eq!(lexer.next_token(), Token::Ident("a".into()));
+        assert_eq!(lexer.next_token(), operator);
+        lexer.next_token();
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+    }
+
+    // only written together
+    assert_eq!(lexer.next_token(), Token::Ident("a".into()));
+    assert_eq!(lexer.next_token(), Token::Plus);
+    assert_eq!(lexer.next_token(), Token::Assign);
+}
+
+#[test]
+fn test_dot() {
+    let mut lexer = Lexer::new("math.pi");
+
+    assert_eq!(lexer.next_token(), Token::Ident("math".into()));
+    assert_eq!(lexer.next_token(), Token::Dot);
+    assert_eq!(lexer.next_token(), Token::Ident("pi".into()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_floats() {
+    let mut lexer = Lexer::new("3.14 0.5 7. x.y");
+
+    assert_eq!(lexer.next_token(), Token::Float("3.14".to_string()));
+    assert_eq!(lexer.next_token(), Token::Float("0.5".to_string()));
+    // no digits after the dot, so it's not part of the number
+    assert_eq!(lexer.next_token(), Token::Int(7));
+    assert_eq!(lexer.next_token(), Token::Dot);
+    assert_eq!(lexer.next_token(), Token::Ident("x".into()));
+    assert_eq!(lexer.next_token(), Token::Dot);
+    assert_eq!(lexer.next_token(), Token::Ident("y".into()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_keywords() {
+    let keywords = [
+        ("fn", Token::Function),
+        ("let", Token::Let),
+        ("true", Token::True),
+        ("false", Token::False),
+        ("if", Token::If),
+        ("else", Token::Else),
+        ("return", Token::Return),
+        ("loop", Token::Loop),
+        ("while", Token::While),
+        ("for", Token::For),
+        ("mod", Token::Mod),
+        ("break", Token::Break),
+        ("continue", Token::Continue),
+    ];
+
+    for (input, expected) in keywords {
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.next_token(), expected);
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    for input in ["func", "lets", "True", "looping", "_break"] {
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.next_token(), Token::Ident(input.into()));
+    }
+}
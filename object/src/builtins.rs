@@ -1,5 +1,6 @@
-use super::{BuiltinFn, Object, ObjectType};
+use super::{BuiltinFn, CallFunction, HashPair, MapObj, Object, ObjectType};
 use std::sync::LazyLock;
+use std::{cmp::Ordering, collections::HashMap};
 
 pub struct Builtin {
     pub name: String,
@@ -25,11 +26,79 @@ pub static BUILTINS: LazyLock<Vec<Builtin>> = LazyLock::new(|| {
     builtin!(last);
     builtin!(rest);
     builtin!(push);
+    builtin!(group_by);
+    builtin!(max_by);
+    builtin!(min_by);
+    builtin!(divmod);
+    builtin!(lines);
+    builtin!(starts_with);
+    builtin!(ends_with);
+    builtin!(index_of);
+    builtin!(pad_left);
+    builtin!(pad_right);
+    builtin!(substr);
+    builtin!(fmt_num);
+    builtin!(tap);
+    builtin!(apply);
+    builtin!(map);
+    builtin!(assert_type);
+    builtin!(reduce);
+    builtin!(compare);
+    builtin!(sort);
+    builtin!(keys);
+    builtin!(values);
+    builtin!(bytes);
+    builtin!(from_bytes);
+    builtin!(hex);
+    builtin!(from_hex);
+    builtin!(split);
+    builtin!(join);
+    builtin!(@ type, type_name);
+    builtin!(transform);
+    builtin!(int);
+    builtin!(str);
+    builtin!(contains);
+    builtin!(equals_ignore_case);
+    builtin!(count_matches);
+    builtin!(split_n);
+    builtin!(abs);
+    builtin!(signum);
+    builtin!(min);
+    builtin!(max);
+    builtin!(clamp);
+    builtin!(gcd);
+    builtin!(lcm);
+    builtin!(pow);
+    builtin!(exp);
+    builtin!(log);
+    builtin!(sin);
+    builtin!(cos);
+    builtin!(tan);
+    builtin!(pi);
+    builtin!(e);
+    builtin!(approx_eq);
+    builtin!(trunc);
+    builtin!(fract);
+    builtin!(delete);
+    builtin!(flat_map);
+    builtin!(set);
+    builtin!(partition);
+    builtin!(any);
+    builtin!(all);
+    builtin!(filter);
+    builtin!(take_while);
+    builtin!(drop_while);
+    builtin!(scan);
+    builtin!(chunk);
+    builtin!(window);
+    builtin!(is_sorted);
+    builtin!(seed);
+    builtin!(shuffle);
 
     builtins
 });
 
-fn len(args: Vec<ObjectType>) -> ObjectType {
+fn len(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
     if args.len() != 1 {
         return new_error(&format!(
             "wrong number of arguments. got={}, want=1",
@@ -38,8 +107,9 @@ fn len(args: Vec<ObjectType>) -> ObjectType {
     }
 
     match &args[0] {
-        ObjectType::StringObj(string) => ObjectType::IntegerObj(string.len() as f64),
-        ObjectType::ArrayObj(array) => ObjectType::IntegerObj(array.len() as f64),
+        ObjectType::StringObj(string) => ObjectType::IntegerObj(string.len() as i64),
+        ObjectType::ArrayObj(array) => ObjectType::IntegerObj(array.len() as i64),
+        ObjectType::HashObj(hash) => ObjectType::IntegerObj(hash.len() as i64),
         _ => new_error(&format!(
             "argument to `len` not supported, got {}",
             args[0].r#type()
@@ -47,14 +117,105 @@ fn len(args: Vec<ObjectType>) -> ObjectType {
     }
 }
 
-fn puts(args: Vec<ObjectType>) -> ObjectType {
+fn puts(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
     for arg in args {
-        println!("{}", arg.inspect());
+        caller.output(&arg.inspect());
     }
     ObjectType::NullObj
 }
 
-fn first(args: Vec<ObjectType>) -> ObjectType {
+/// The name of a value's type, the same one error messages use, like
+/// `"INTEGER"` or `"ARRAY"`.
+fn type_name(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    ObjectType::StringObj(args[0].r#type().to_string())
+}
+
+/// A string parsed as an integer, or a number with any fraction cut off,
+/// `int("42")` and `int(7 / 2)` are 42 and 3.
+fn int(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    match &args[0] {
+        ObjectType::IntegerObj(int) => ObjectType::IntegerObj(*int),
+        ObjectType::FloatObj(float) => ObjectType::IntegerObj(float.trunc() as i64),
+        ObjectType::StringObj(string) => match string.trim().parse() {
+            Ok(int) => ObjectType::IntegerObj(int),
+            Err(_) => new_error(&format!("could not parse \"{string}\" as INTEGER")),
+        },
+        other => new_error(&format!(
+            "argument to `int` must be STRING, INTEGER or FLOAT, got {}",
+            other.r#type()
+        )),
+    }
+}
+
+/// Any value as the string `puts` would print for it.
+fn str(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    ObjectType::StringObj(args[0].inspect())
+}
+
+/// Hands back its first argument when its type is named by the second,
+/// `assert_type(x, "ARRAY")`, and errors with both types otherwise.
+fn assert_type(mut args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let ObjectType::StringObj(expected) = &args[1] else {
+        return new_error(&format!(
+            "second argument to `assert_type` must be STRING, got {}",
+            args[1].r#type()
+        ));
+    };
+
+    let actual = args[0].r#type().to_string();
+    if actual != *expected {
+        return new_error(&format!(
+            "type assertion failed: expected {expected}, got {actual}"
+        ));
+    }
+
+    args.remove(0)
+}
+
+/// Prints its argument like `puts`, then hands it back untouched so it can sit
+/// in the middle of an expression.
+fn tap(mut args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    let value = args.remove(0);
+    caller.output(&value.inspect());
+    value
+}
+
+fn first(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
     if args.len() != 1 {
         return new_error(&format!(
             "wrong number of arguments. got={}, want=1",
@@ -72,7 +233,7 @@ fn first(args: Vec<ObjectType>) -> ObjectType {
     }
 }
 
-fn last(args: Vec<ObjectType>) -> ObjectType {
+fn last(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
     if args.len() != 1 {
         return new_error(&format!(
             "wrong number of arguments. got={}, want=1",
@@ -90,7 +251,7 @@ fn last(args: Vec<ObjectType>) -> ObjectType {
     }
 }
 
-fn rest(args: Vec<ObjectType>) -> ObjectType {
+fn rest(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
     if args.len() != 1 {
         return new_error(&format!(
             "wrong number of arguments. got={}, want=1",
@@ -112,7 +273,7 @@ fn rest(args: Vec<ObjectType>) -> ObjectType {
     }
 }
 
-fn push(args: Vec<ObjectType>) -> ObjectType {
+fn push(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
     if args.len() != 2 {
         return new_error(&format!(
             "wrong number of arguments. got={}, want=2",
@@ -133,11 +294,1776 @@ fn push(args: Vec<ObjectType>) -> ObjectType {
     }
 }
 
+fn group_by(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let array = match &args[0] {
+        ObjectType::ArrayObj(array) => array,
+        _ => {
+            return new_error(&format!(
+                "argument to `group_by` must be ARRAY, got {}",
+                args[0].r#type()
+            ));
+        }
+    };
+
+    if !is_callable(&args[1]) {
+        return new_error(&format!(
+            "second argument to `group_by` must be FUNCTION, got {}",
+            args[1].r#type()
+        ));
+    }
+
+    let mut groups: MapObj = HashMap::new();
+
+    for element in array {
+        let key = caller.call_function(args[1].clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = key {
+            return key;
+        }
+
+        let hash_key = match key.hash() {
+            Ok(hash_key) => hash_key,
+            Err(msg) => return new_error(&msg),
+        };
+
+        let pair = groups.entry(hash_key).or_insert(HashPair {
+            key,
+            value: ObjectType::ArrayObj(Vec::new()),
+        });
+        if let ObjectType::ArrayObj(group) = &mut pair.value {
+            group.push(element.clone());
+        }
+    }
+
+    ObjectType::HashObj(groups)
+}
+
+fn max_by(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    extreme_by("max_by", Ordering::Greater, args, caller)
+}
+
+fn min_by(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    extreme_by("min_by", Ordering::Less, args, caller)
+}
+
+/// Finds the element whose computed key compares as `wanted` against every
+/// other key. Ties go to the earliest element.
+fn extreme_by(
+    name: &str,
+    wanted: Ordering,
+    args: Vec<ObjectType>,
+    caller: &mut dyn CallFunction,
+) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let array = match &args[0] {
+        ObjectType::ArrayObj(array) => array,
+        _ => {
+            return new_error(&format!(
+                "argument to `{name}` must be ARRAY, got {}",
+                args[0].r#type()
+            ));
+        }
+    };
+
+    if array.is_empty() {
+        return new_error(&format!("argument to `{name}` must not be empty"));
+    }
+
+    if !is_callable(&args[1]) {
+        return new_error(&format!(
+            "second argument to `{name}` must be FUNCTION, got {}",
+            args[1].r#type()
+        ));
+    }
+
+    let mut best: Option<(&ObjectType, ObjectType)> = None;
+
+    for element in array {
+        let key = caller.call_function(args[1].clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = key {
+            return key;
+        }
+
+        best = match best {
+            None => Some((element, key)),
+            Some((best_element, best_key)) => match compare_keys(&key, &best_key) {
+                Some(ordering) if ordering == wanted => Some((element, key)),
+                Some(_) => Some((best_element, best_key)),
+                None => {
+                    return new_error(&format!(
+                        "keys for `{name}` must be comparable, got {} and {}",
+                        best_key.r#type(),
+                        key.r#type()
+                    ));
+                }
+            },
+        };
+    }
+
+    best.map(|(element, _)| element.clone()).unwrap_or_default()
+}
+
+/// Orders hash keys booleans first, then integers, then strings.
+pub(crate) fn compare_hash_keys(left: &ObjectType, right: &ObjectType) -> Ordering {
+    // only booleans, integers and strings can be keys
+    let rank = |key: &ObjectType| match key {
+        ObjectType::BoolObj(_) => 0,
+        ObjectType::IntegerObj(_) => 1,
+        _ => 2,
+    };
+
+    match (left, right) {
+        (ObjectType::BoolObj(l), ObjectType::BoolObj(r)) => l.cmp(r),
+        (l, r) => compare_keys(l, r).unwrap_or_else(|| rank(l).cmp(&rank(r))),
+    }
+}
+
+fn min(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    extreme("min", Ordering::Less, args)
+}
+
+fn max(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    extreme("max", Ordering::Greater, args)
+}
+
+/// Either the smaller or larger of two numbers, `min(3, 7)`, or of the
+/// elements of an array, `min([3, 7])`, which may be numbers or strings.
+/// Ties go to the earlier value.
+fn extreme(name: &str, wanted: Ordering, args: Vec<ObjectType>) -> ObjectType {
+    let candidates = match args.as_slice() {
+        [ObjectType::ArrayObj(array)] if array.is_empty() => {
+            return new_error(&format!("argument to `{name}` must not be empty"));
+        }
+        [ObjectType::ArrayObj(array)] => array,
+        [other] => {
+            return new_error(&format!(
+                "argument to `{name}` must be ARRAY, got {}",
+                other.r#type()
+            ));
+        }
+        [a, b] if a.as_float().is_some() && b.as_float().is_some() => &args,
+        [a, b] => {
+            return new_error(&format!(
+                "arguments to `{name}` must be INTEGER or FLOAT, got {} and {}",
+                a.r#type(),
+                b.r#type()
+            ));
+        }
+        _ => {
+            return new_error(&format!(
+                "wrong number of arguments. got={}, want=1 or 2",
+                args.len()
+            ));
+        }
+    };
+
+    let mut best = &candidates[0];
+    for candidate in &candidates[1..] {
+        match compare_keys(candidate, best) {
+            Some(ordering) if ordering == wanted => best = candidate,
+            Some(_) => {}
+            None => {
+                return new_error(&format!(
+                    "elements for `{name}` must be comparable, got {} and {}",
+                    best.r#type(),
+                    candidate.r#type()
+                ));
+            }
+        }
+    }
+
+    best.clone()
+}
+
+fn compare_keys(left: &ObjectType, right: &ObjectType) -> Option<Ordering> {
+    match (left, right) {
+        (ObjectType::IntegerObj(l), ObjectType::IntegerObj(r)) => Some(l.cmp(r)),
+        (ObjectType::StringObj(l), ObjectType::StringObj(r)) => Some(l.cmp(r)),
+        _ => left.as_float()?.partial_cmp(&right.as_float()?),
+    }
+}
+
+/// Three-way comparison of two numbers or two strings, -1 when `a` sorts
+/// first, 1 when `b` does and 0 when they're equal.
+fn compare(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    match compare_keys(&args[0], &args[1]) {
+        Some(ordering) => ObjectType::IntegerObj(ordering as i64),
+        None => new_error(&format!(
+            "cannot compare {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        )),
+    }
+}
+
+/// Sorts numbers numerically and strings lexicographically, or by a
+/// comparator returning a negative number, zero or a positive number like
+/// `compare`, `sort(arr, fn(a, b) { compare(b, a) })`. The sort is stable.
+fn sort(mut args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 && args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1 or 2",
+            args.len()
+        ));
+    }
+
+    let comparator = if args.len() == 2 { args.pop() } else { None };
+    let array = match args.pop().unwrap() {
+        ObjectType::ArrayObj(array) => array,
+        other => {
+            return new_error(&format!(
+                "first argument to `sort` must be ARRAY, got {}",
+                other.r#type()
+            ));
+        }
+    };
+
+    let sorted = match comparator {
+        None => merge_sort(array, &mut |a, b| {
+            compare_keys(a, b).ok_or_else(|| {
+                new_error(&format!(
+                    "cannot sort {} and {} together",
+                    a.r#type(),
+                    b.r#type()
+                ))
+            })
+        }),
+        Some(comparator) if is_callable(&comparator) => merge_sort(array, &mut |a, b| {
+            let result = caller.call_function(comparator.clone(), vec![a.clone(), b.clone()]);
+            match result.as_float() {
+                Some(n) => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+                None if matches!(result, ObjectType::ErrorObj(_)) => Err(result),
+                None => Err(new_error(&format!(
+                    "comparator for `sort` must return a number, got {}",
+                    result.r#type()
+                ))),
+            }
+        }),
+        Some(comparator) => {
+            return new_error(&format!(
+                "second argument to `sort` must be FUNCTION, got {}",
+                comparator.r#type()
+            ));
+        }
+    };
+
+    match sorted {
+        Ok(sorted) => ObjectType::ArrayObj(sorted),
+        Err(err) => err,
+    }
+}
+
+/// A merge sort whose comparisons can fail. `slice::sort_by` can't stop
+/// part way and may panic on a comparator that isn't a total order, which a
+/// Monkey function is free to be.
+fn merge_sort(
+    mut items: Vec<ObjectType>,
+    cmp: &mut dyn FnMut(&ObjectType, &ObjectType) -> Result<Ordering, ObjectType>,
+) -> Result<Vec<ObjectType>, ObjectType> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+
+    let right = merge_sort(items.split_off(items.len() / 2), cmp)?;
+    let left = merge_sort(items, cmp)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // only take from the right when it's strictly smaller, to stay stable
+        if cmp(l, r)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+
+    Ok(merged)
+}
+
+/// A hash's keys as an array, sorted so the same hash always gives the
+/// same order: booleans first, then integers, then strings.
+fn keys(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match sorted_pairs("keys", &args) {
+        Ok(pairs) => ObjectType::ArrayObj(pairs.into_iter().map(|p| p.key.clone()).collect()),
+        Err(err) => err,
+    }
+}
+
+/// A hash's values as an array, in the same order as `keys`.
+fn values(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match sorted_pairs("values", &args) {
+        Ok(pairs) => ObjectType::ArrayObj(pairs.into_iter().map(|p| p.value.clone()).collect()),
+        Err(err) => err,
+    }
+}
+
+/// A copy of a hash without `key`, which doesn't have to be in it.
+fn delete(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let [hash, key] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    };
+
+    let ObjectType::HashObj(hash) = hash else {
+        return new_error(&format!(
+            "argument to `delete` must be HASH, got {}",
+            hash.r#type()
+        ));
+    };
+
+    match key.hash() {
+        Ok(key) => {
+            let mut hash = hash.clone();
+            hash.remove(&key);
+            ObjectType::HashObj(hash)
+        }
+        Err(err) => new_error(&err),
+    }
+}
+
+/// A copy of a hash with `key` set to `value`, whether or not it was there.
+fn set(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let [hash, key, value] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        ));
+    };
+
+    let ObjectType::HashObj(hash) = hash else {
+        return new_error(&format!(
+            "argument to `set` must be HASH, got {}",
+            hash.r#type()
+        ));
+    };
+
+    match key.hash() {
+        Ok(hash_key) => {
+            let mut hash = hash.clone();
+            hash.insert(
+                hash_key,
+                HashPair {
+                    key: key.clone(),
+                    value: value.clone(),
+                },
+            );
+            ObjectType::HashObj(hash)
+        }
+        Err(err) => new_error(&err),
+    }
+}
+
+/// Whether an array of numbers or strings is in non-decreasing order, the
+/// order `sort` would put it in.
+fn is_sorted(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let [array] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    };
+
+    let ObjectType::ArrayObj(array) = array else {
+        return new_error(&format!(
+            "argument to `is_sorted` must be ARRAY, got {}",
+            array.r#type()
+        ));
+    };
+
+    for pair in array.windows(2) {
+        match compare_keys(&pair[0], &pair[1]) {
+            Some(Ordering::Greater) => return ObjectType::BoolObj(false),
+            Some(_) => {}
+            None => {
+                return new_error(&format!(
+                    "cannot compare {} and {}",
+                    pair[0].r#type(),
+                    pair[1].r#type()
+                ));
+            }
+        }
+    }
+
+    ObjectType::BoolObj(true)
+}
+
+/// Seeds the random numbers `shuffle` uses, so runs can be repeated.
+fn seed(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match args.as_slice() {
+        [ObjectType::IntegerObj(seed)] => {
+            caller.seed(*seed as u64);
+            ObjectType::NullObj
+        }
+        [other] => new_error(&format!(
+            "argument to `seed` must be INTEGER, got {}",
+            other.r#type()
+        )),
+        _ => new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )),
+    }
+}
+
+/// A copy of an array in a random order, repeatable after `seed`.
+fn shuffle(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    let [array] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    };
+
+    let ObjectType::ArrayObj(array) = array else {
+        return new_error(&format!(
+            "argument to `shuffle` must be ARRAY, got {}",
+            array.r#type()
+        ));
+    };
+
+    // Fisher-Yates, swapping each slot from the back with one at or before it
+    let mut shuffled = array.clone();
+    for i in (1..shuffled.len()).rev() {
+        let j = ((caller.next_random() as u128 * (i as u128 + 1)) >> 64) as usize;
+        shuffled.swap(i, j);
+    }
+
+    ObjectType::ArrayObj(shuffled)
+}
+
+fn sorted_pairs<'a>(name: &str, args: &'a [ObjectType]) -> Result<Vec<&'a HashPair>, ObjectType> {
+    if args.len() != 1 {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    let ObjectType::HashObj(hash) = &args[0] else {
+        return Err(new_error(&format!(
+            "argument to `{name}` must be HASH, got {}",
+            args[0].r#type()
+        )));
+    };
+
+    let mut pairs = hash.values().collect::<Vec<_>>();
+    pairs.sort_by(|a, b| compare_hash_keys(&a.key, &b.key));
+
+    Ok(pairs)
+}
+
+/// A string's UTF-8 encoding as an array of integers.
+fn bytes(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    match &args[0] {
+        ObjectType::StringObj(string) => ObjectType::ArrayObj(
+            string
+                .bytes()
+                .map(|byte| ObjectType::IntegerObj(byte as i64))
+                .collect(),
+        ),
+        other => new_error(&format!(
+            "argument to `bytes` must be STRING, got {}",
+            other.r#type()
+        )),
+    }
+}
+
+/// The string an array of UTF-8 bytes encodes, the reverse of `bytes`.
+fn from_bytes(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    let bytes = match byte_values("from_bytes", &args[0]) {
+        Ok(bytes) => bytes,
+        Err(err) => return err,
+    };
+
+    match String::from_utf8(bytes) {
+        Ok(string) => ObjectType::StringObj(string),
+        Err(err) => new_error(&format!("invalid UTF-8 in `from_bytes`: {err}")),
+    }
+}
+
+/// An array of byte values as lowercase hex, `hex([255, 0])` is `"ff00"`.
+fn hex(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    match byte_values("hex", &args[0]) {
+        Ok(bytes) => ObjectType::StringObj(bytes.iter().map(|b| format!("{b:02x}")).collect()),
+        Err(err) => err,
+    }
+}
+
+/// The byte values a hex string spells out, either case, the reverse of `hex`.
+fn from_hex(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    let ObjectType::StringObj(string) = &args[0] else {
+        return new_error(&format!(
+            "argument to `from_hex` must be STRING, got {}",
+            args[0].r#type()
+        ));
+    };
+
+    if string.len() % 2 != 0 {
+        return new_error(&format!(
+            "hex for `from_hex` must have an even number of digits, got {}",
+            string.len()
+        ));
+    }
+
+    let mut bytes = Vec::with_capacity(string.len() / 2);
+    for pair in string.as_bytes().chunks(2) {
+        let digits = String::from_utf8_lossy(pair);
+        match u8::from_str_radix(&digits, 16) {
+            // from_str_radix takes a leading +, which isn't a hex digit
+            Ok(byte) if pair.iter().all(u8::is_ascii_hexdigit) => {
+                bytes.push(ObjectType::IntegerObj(byte as i64))
+            }
+            _ => return new_error(&format!("invalid hex in `from_hex`: \"{digits}\"")),
+        }
+    }
+
+    ObjectType::ArrayObj(bytes)
+}
+
+/// The elements of an array of integers in 0-255 as bytes.
+fn byte_values(name: &str, array: &ObjectType) -> Result<Vec<u8>, ObjectType> {
+    let ObjectType::ArrayObj(array) = array else {
+        return Err(new_error(&format!(
+            "argument to `{name}` must be ARRAY, got {}",
+            array.r#type()
+        )));
+    };
+
+    let mut bytes = Vec::with_capacity(array.len());
+    for element in array {
+        match element {
+            ObjectType::IntegerObj(int) if (0..=255).contains(int) => bytes.push(*int as u8),
+            ObjectType::IntegerObj(int) => {
+                return Err(new_error(&format!("byte out of range 0-255: {int}")));
+            }
+            other => {
+                return Err(new_error(&format!(
+                    "bytes for `{name}` must be INTEGER, got {}",
+                    other.r#type()
+                )));
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Calls a function with the elements of an array as its arguments,
+/// `apply(f, [1, 2])` is `f(1, 2)`.
+fn apply(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    if !is_callable(&args[0]) {
+        return new_error(&format!(
+            "first argument to `apply` must be FUNCTION, got {}",
+            args[0].r#type()
+        ));
+    }
+
+    match &args[1] {
+        ObjectType::ArrayObj(array) => caller.call_function(args[0].clone(), array.clone()),
+        _ => new_error(&format!(
+            "second argument to `apply` must be ARRAY, got {}",
+            args[1].r#type()
+        )),
+    }
+}
+
+/// `f` called on every leaf of nested arrays and hashes, keeping their shape.
+/// Hash keys are left as they are, only values are passed to `f`.
+fn transform(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    if !is_callable(&args[1]) {
+        return new_error(&format!(
+            "second argument to `transform` must be FUNCTION, got {}",
+            args[1].r#type()
+        ));
+    }
+
+    match transform_leaves(&args[0], &args[1], caller) {
+        Ok(value) | Err(value) => value,
+    }
+}
+
+fn transform_leaves(
+    value: &ObjectType,
+    function: &ObjectType,
+    caller: &mut dyn CallFunction,
+) -> Result<ObjectType, ObjectType> {
+    match value {
+        ObjectType::ArrayObj(array) => array
+            .iter()
+            .map(|element| transform_leaves(element, function, caller))
+            .collect::<Result<_, _>>()
+            .map(ObjectType::ArrayObj),
+        ObjectType::HashObj(hash) => {
+            let mut transformed = HashMap::with_capacity(hash.len());
+            for (hash_key, pair) in hash {
+                let value = transform_leaves(&pair.value, function, caller)?;
+                transformed.insert(
+                    *hash_key,
+                    HashPair {
+                        key: pair.key.clone(),
+                        value,
+                    },
+                );
+            }
+            Ok(ObjectType::HashObj(transformed))
+        }
+        leaf => match caller.call_function(function.clone(), vec![leaf.clone()]) {
+            err @ ObjectType::ErrorObj(_) => Err(err),
+            value => Ok(value),
+        },
+    }
+}
+
+/// A new array of `f` called on each element, `map([1, 2], f)` is
+/// `[f(1), f(2)]`. Stops at the first error `f` returns.
+fn map(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let array = match &args[0] {
+        ObjectType::ArrayObj(array) => array,
+        _ => {
+            return new_error(&format!(
+                "first argument to `map` must be ARRAY, got {}",
+                args[0].r#type()
+            ));
+        }
+    };
+
+    if !is_callable(&args[1]) {
+        return new_error(&format!(
+            "second argument to `map` must be FUNCTION, got {}",
+            args[1].r#type()
+        ));
+    }
+
+    let mut mapped = Vec::with_capacity(array.len());
+    for element in array {
+        let value = caller.call_function(args[1].clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = value {
+            return value;
+        }
+        mapped.push(value);
+    }
+
+    ObjectType::ArrayObj(mapped)
+}
+
+/// Maps each element to an array and joins the results one level deep,
+/// `flat_map([1, 2], fn(x) { [x, x] })` is `[1, 1, 2, 2]`.
+fn flat_map(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let array = match &args[0] {
+        ObjectType::ArrayObj(array) => array,
+        _ => {
+            return new_error(&format!(
+                "first argument to `flat_map` must be ARRAY, got {}",
+                args[0].r#type()
+            ));
+        }
+    };
+
+    if !is_callable(&args[1]) {
+        return new_error(&format!(
+            "second argument to `flat_map` must be FUNCTION, got {}",
+            args[1].r#type()
+        ));
+    }
+
+    let mut flattened = Vec::with_capacity(array.len());
+    for element in array {
+        match caller.call_function(args[1].clone(), vec![element.clone()]) {
+            ObjectType::ArrayObj(items) => flattened.extend(items),
+            err @ ObjectType::ErrorObj(_) => return err,
+            other => {
+                return new_error(&format!(
+                    "function passed to `flat_map` must return ARRAY, got {}",
+                    other.r#type()
+                ));
+            }
+        }
+    }
+
+    ObjectType::ArrayObj(flattened)
+}
+
+/// The elements of an array the predicate holds for, in order.
+fn filter(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match split_by("filter", &args, caller) {
+        Ok((matching, _)) => ObjectType::ArrayObj(matching),
+        Err(err) => err,
+    }
+}
+
+/// Splits an array into `[matching, non_matching]` by a predicate, keeping
+/// the elements in order within each half.
+fn partition(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match split_by("partition", &args, caller) {
+        Ok((matching, non_matching)) => ObjectType::ArrayObj(vec![
+            ObjectType::ArrayObj(matching),
+            ObjectType::ArrayObj(non_matching),
+        ]),
+        Err(err) => err,
+    }
+}
+
+/// The elements the predicate holds for and those it doesn't, with the same
+/// truthiness as `if`.
+fn split_by(
+    name: &str,
+    args: &[ObjectType],
+    caller: &mut dyn CallFunction,
+) -> Result<(Vec<ObjectType>, Vec<ObjectType>), ObjectType> {
+    let (array, predicate) = array_and_function(name, args)?;
+
+    let (mut matching, mut non_matching) = (Vec::new(), Vec::new());
+    for element in array {
+        let keep = caller.call_function(predicate.clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = keep {
+            return Err(keep);
+        }
+
+        if keep.is_truthy() {
+            matching.push(element.clone());
+        } else {
+            non_matching.push(element.clone());
+        }
+    }
+
+    Ok((matching, non_matching))
+}
+
+/// The elements at the start of an array the predicate holds for.
+fn take_while(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match leading_run("take_while", &args, caller) {
+        Ok((array, run)) => ObjectType::ArrayObj(array[..run].to_vec()),
+        Err(err) => err,
+    }
+}
+
+/// The elements left after those at the start the predicate holds for.
+fn drop_while(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match leading_run("drop_while", &args, caller) {
+        Ok((array, run)) => ObjectType::ArrayObj(array[run..].to_vec()),
+        Err(err) => err,
+    }
+}
+
+/// The array and how many elements at its start the predicate holds for,
+/// calling it no further than the first it doesn't.
+fn leading_run<'a>(
+    name: &str,
+    args: &'a [ObjectType],
+    caller: &mut dyn CallFunction,
+) -> Result<(&'a [ObjectType], usize), ObjectType> {
+    let (array, predicate) = array_and_function(name, args)?;
+
+    for (i, element) in array.iter().enumerate() {
+        let keep = caller.call_function(predicate.clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = keep {
+            return Err(keep);
+        }
+
+        if !keep.is_truthy() {
+            return Ok((array, i));
+        }
+    }
+
+    Ok((array, array.len()))
+}
+
+/// An array split into groups of `n` in order, the last one shorter if the
+/// elements don't divide evenly, `chunk([1, 2, 3], 2)` is `[[1, 2], [3]]`.
+fn chunk(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match array_and_size("chunk", &args) {
+        Ok((array, n)) => ObjectType::ArrayObj(
+            array
+                .chunks(n)
+                .map(|chunk| ObjectType::ArrayObj(chunk.to_vec()))
+                .collect(),
+        ),
+        Err(err) => err,
+    }
+}
+
+/// Every run of `n` neighbouring elements, `window([1, 2, 3], 2)` is
+/// `[[1, 2], [2, 3]]`. An array shorter than `n` has none.
+fn window(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match array_and_size("window", &args) {
+        Ok((array, n)) => ObjectType::ArrayObj(
+            array
+                .windows(n)
+                .map(|window| ObjectType::ArrayObj(window.to_vec()))
+                .collect(),
+        ),
+        Err(err) => err,
+    }
+}
+
+/// The array and positive group size `chunk` and `window` take.
+fn array_and_size<'a>(
+    name: &str,
+    args: &'a [ObjectType],
+) -> Result<(&'a [ObjectType], usize), ObjectType> {
+    let [array, n] = args else {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    };
+
+    let ObjectType::ArrayObj(array) = array else {
+        return Err(new_error(&format!(
+            "first argument to `{name}` must be ARRAY, got {}",
+            array.r#type()
+        )));
+    };
+
+    match *n {
+        ObjectType::IntegerObj(n) if n > 0 => Ok((array, n as usize)),
+        ObjectType::IntegerObj(n) => Err(new_error(&format!(
+            "second argument to `{name}` must be positive, got {n}"
+        ))),
+        ref other => Err(new_error(&format!(
+            "second argument to `{name}` must be INTEGER, got {}",
+            other.r#type()
+        ))),
+    }
+}
+
+/// The array and function a higher order builtin like `filter` takes.
+fn array_and_function<'a>(
+    name: &str,
+    args: &'a [ObjectType],
+) -> Result<(&'a [ObjectType], &'a ObjectType), ObjectType> {
+    let [array, function] = args else {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    };
+
+    let ObjectType::ArrayObj(array) = array else {
+        return Err(new_error(&format!(
+            "first argument to `{name}` must be ARRAY, got {}",
+            array.r#type()
+        )));
+    };
+
+    if !is_callable(function) {
+        return Err(new_error(&format!(
+            "second argument to `{name}` must be FUNCTION, got {}",
+            function.r#type()
+        )));
+    }
+
+    Ok((array, function))
+}
+
+/// Whether the predicate holds for some element, stopping at the first that
+/// it does.
+fn any(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    find_by("any", true, args, caller)
+}
+
+/// Whether the predicate holds for every element, stopping at the first that
+/// it doesn't. An empty array gives `true`.
+fn all(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match find_by("all", false, args, caller) {
+        ObjectType::BoolObj(found) => ObjectType::BoolObj(!found),
+        err => err,
+    }
+}
+
+/// Whether the predicate gives `wanted` for any element, calling it no more
+/// than needed.
+fn find_by(
+    name: &str,
+    wanted: bool,
+    args: Vec<ObjectType>,
+    caller: &mut dyn CallFunction,
+) -> ObjectType {
+    let (array, predicate) = match array_and_function(name, &args) {
+        Ok(found) => found,
+        Err(err) => return err,
+    };
+
+    for element in array {
+        let result = caller.call_function(predicate.clone(), vec![element.clone()]);
+        if let ObjectType::ErrorObj(_) = result {
+            return result;
+        }
+
+        if result.is_truthy() == wanted {
+            return ObjectType::BoolObj(true);
+        }
+    }
+
+    ObjectType::BoolObj(false)
+}
+
+/// Folds an array into one value, `reduce([1, 2], 0, f)` is `f(f(0, 1), 2)`.
+/// An empty array gives back the initial value.
+fn reduce(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    match fold("reduce", args, caller, |_| {}) {
+        Ok(accumulator) | Err(accumulator) => accumulator,
+    }
+}
+
+/// Like `reduce` but gives every accumulator along the way, starting with
+/// the initial value, `scan([1, 2], 0, f)` is `[0, f(0, 1), f(f(0, 1), 2)]`.
+fn scan(args: Vec<ObjectType>, caller: &mut dyn CallFunction) -> ObjectType {
+    let mut accumulators = Vec::new();
+    match fold("scan", args, caller, |acc| accumulators.push(acc.clone())) {
+        Ok(_) => ObjectType::ArrayObj(accumulators),
+        Err(err) => err,
+    }
+}
+
+/// Folds `[array, initial, function]` left to right, passing `step` each
+/// accumulator in turn, the initial value included.
+fn fold(
+    name: &str,
+    mut args: Vec<ObjectType>,
+    caller: &mut dyn CallFunction,
+    mut step: impl FnMut(&ObjectType),
+) -> Result<ObjectType, ObjectType> {
+    if args.len() != 3 {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        )));
+    }
+
+    let function = args.pop().unwrap();
+    let initial = args.pop().unwrap();
+    let array = match args.pop().unwrap() {
+        ObjectType::ArrayObj(array) => array,
+        other => {
+            return Err(new_error(&format!(
+                "first argument to `{name}` must be ARRAY, got {}",
+                other.r#type()
+            )));
+        }
+    };
+
+    if !is_callable(&function) {
+        return Err(new_error(&format!(
+            "third argument to `{name}` must be FUNCTION, got {}",
+            function.r#type()
+        )));
+    }
+    if let Some(params) = arity(&function)
+        && params != 2
+    {
+        return Err(new_error(&format!(
+            "function passed to `{name}` must take 2 arguments, takes {params}"
+        )));
+    }
+
+    let mut accumulator = initial;
+    step(&accumulator);
+    for element in array {
+        accumulator = caller.call_function(function.clone(), vec![accumulator, element]);
+        if let ObjectType::ErrorObj(_) = accumulator {
+            return Err(accumulator);
+        }
+        step(&accumulator);
+    }
+
+    Ok(accumulator)
+}
+
+fn divmod(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    match (&args[0], &args[1]) {
+        (_, divisor) if divisor.as_float() == Some(0.0) => {
+            new_error("division by zero in `divmod`")
+        }
+        // floored, so the remainder takes the sign of the divisor
+        (ObjectType::IntegerObj(dividend), ObjectType::IntegerObj(divisor)) => {
+            let mut quotient = dividend.wrapping_div(*divisor);
+            if dividend.wrapping_rem(*divisor) != 0 && (*dividend < 0) != (*divisor < 0) {
+                quotient -= 1;
+            }
+            let remainder = dividend.wrapping_sub(divisor.wrapping_mul(quotient));
+
+            ObjectType::ArrayObj(vec![
+                ObjectType::IntegerObj(quotient),
+                ObjectType::IntegerObj(remainder),
+            ])
+        }
+        (dividend, divisor) if dividend.as_float().is_some() && divisor.as_float().is_some() => {
+            let (dividend, divisor) = (dividend.as_float().unwrap(), divisor.as_float().unwrap());
+            let quotient = (dividend / divisor).floor();
+            let remainder = dividend - divisor * quotient;
+
+            ObjectType::ArrayObj(vec![
+                ObjectType::FloatObj(quotient),
+                ObjectType::FloatObj(remainder),
+            ])
+        }
+        _ => new_error(&format!(
+            "arguments to `divmod` must be INTEGER or FLOAT, got {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        )),
+    }
+}
+
+/// The distance of a number from zero, keeping its type. The most negative
+/// integer has no positive counterpart and wraps back to itself.
+fn abs(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match number_arg("abs", &args) {
+        Ok(ObjectType::IntegerObj(int)) => ObjectType::IntegerObj(int.wrapping_abs()),
+        Ok(ObjectType::FloatObj(float)) => ObjectType::FloatObj(float.abs()),
+        Ok(_) => unreachable!(),
+        Err(err) => err,
+    }
+}
+
+/// The sign of a number as the integer -1, 0 or 1, for floats too.
+fn signum(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match number_arg("signum", &args) {
+        Ok(ObjectType::IntegerObj(int)) => ObjectType::IntegerObj(int.signum()),
+        Ok(ObjectType::FloatObj(float)) => match float.partial_cmp(&0.0) {
+            Some(Ordering::Less) => ObjectType::IntegerObj(-1),
+            Some(Ordering::Greater) => ObjectType::IntegerObj(1),
+            _ => ObjectType::IntegerObj(0),
+        },
+        Ok(_) => unreachable!(),
+        Err(err) => err,
+    }
+}
+
+/// `x` if it's within `lo` and `hi`, otherwise whichever bound it's past.
+fn clamp(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let [x, lo, hi] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        ));
+    };
+
+    if args.iter().any(|arg| arg.as_float().is_none()) {
+        return new_error(&format!(
+            "arguments to `clamp` must be INTEGER or FLOAT, got {}, {} and {}",
+            x.r#type(),
+            lo.r#type(),
+            hi.r#type()
+        ));
+    }
+    if compare_keys(lo, hi) == Some(Ordering::Greater) {
+        return new_error(&format!(
+            "lower bound for `clamp` can't be above the upper bound, got {} and {}",
+            lo.inspect(),
+            hi.inspect()
+        ));
+    }
+
+    if compare_keys(x, lo) == Some(Ordering::Less) {
+        lo.clone()
+    } else if compare_keys(x, hi) == Some(Ordering::Greater) {
+        hi.clone()
+    } else {
+        x.clone()
+    }
+}
+
+/// The greatest common divisor of two integers by Euclid's algorithm, never
+/// negative. `gcd(0, 0)` is 0. The only gcd too big for an INTEGER is that of
+/// the smallest INTEGER with itself or 0, which is an error.
+fn gcd(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match integer_pair("gcd", &args) {
+        Ok((a, b)) => whole_result("gcd", Some(euclid(a.unsigned_abs(), b.unsigned_abs()))),
+        Err(err) => err,
+    }
+}
+
+/// The least common multiple of two integers, never negative. It's 0 when
+/// either is 0, and an error when it's too big for an INTEGER.
+fn lcm(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match integer_pair("lcm", &args) {
+        Ok((0, _) | (_, 0)) => ObjectType::IntegerObj(0),
+        Ok((a, b)) => {
+            let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+            whole_result("lcm", (a / euclid(a, b)).checked_mul(b))
+        }
+        Err(err) => err,
+    }
+}
+
+/// `result` as an INTEGER, or an error when it's `None` or doesn't fit.
+fn whole_result(name: &str, result: Option<u64>) -> ObjectType {
+    match result.and_then(|n| i64::try_from(n).ok()) {
+        Some(n) => ObjectType::IntegerObj(n),
+        None => new_error(&format!("result of `{name}` doesn't fit in an INTEGER")),
+    }
+}
+
+fn euclid(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn integer_pair(name: &str, args: &[ObjectType]) -> Result<(i64, i64), ObjectType> {
+    match args {
+        [ObjectType::IntegerObj(a), ObjectType::IntegerObj(b)] => Ok((*a, *b)),
+        [a, b] => Err(new_error(&format!(
+            "arguments to `{name}` must be INTEGER, got {} and {}",
+            a.r#type(),
+            b.r#type()
+        ))),
+        _ => Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ))),
+    }
+}
+
+/// `base` raised to `exponent`. Integers raised to a non-negative integer
+/// stay integers, wrapping on overflow like `*`; anything else is a float.
+fn pow(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match args.as_slice() {
+        [
+            ObjectType::IntegerObj(base),
+            ObjectType::IntegerObj(exponent),
+        ] if u32::try_from(*exponent).is_ok() => {
+            ObjectType::IntegerObj(base.wrapping_pow(*exponent as u32))
+        }
+        [base, exponent] => match (base.as_float(), exponent.as_float()) {
+            (Some(base), Some(exponent)) => ObjectType::FloatObj(base.powf(exponent)),
+            _ => new_error(&format!(
+                "arguments to `pow` must be INTEGER or FLOAT, got {} and {}",
+                base.r#type(),
+                exponent.r#type()
+            )),
+        },
+        _ => new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )),
+    }
+}
+
+/// e raised to `x`.
+fn exp(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("exp", args, f64::exp)
+}
+
+/// The natural log of `x`, or its log in `base` with `log(x, base)`.
+fn log(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if !(1..=2).contains(&args.len()) {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1 or 2",
+            args.len()
+        ));
+    }
+
+    let mut numbers = Vec::with_capacity(args.len());
+    for arg in &args {
+        match arg.as_float() {
+            Some(number) => numbers.push(number),
+            None => {
+                return new_error(&format!(
+                    "arguments to `log` must be INTEGER or FLOAT, got {}",
+                    arg.r#type()
+                ));
+            }
+        }
+    }
+
+    let x = numbers[0];
+    if x <= 0.0 {
+        return new_error(&format!("can't take the log of {}", args[0].inspect()));
+    }
+    match numbers.get(1) {
+        None => ObjectType::FloatObj(x.ln()),
+        Some(&base) if base > 0.0 && base != 1.0 => ObjectType::FloatObj(x.ln() / base.ln()),
+        Some(_) => new_error(&format!(
+            "base for `log` must be positive and not 1, got {}",
+            args[1].inspect()
+        )),
+    }
+}
+
+/// The sine of an angle in radians.
+fn sin(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("sin", args, f64::sin)
+}
+
+/// The cosine of an angle in radians.
+fn cos(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("cos", args, f64::cos)
+}
+
+/// The tangent of an angle in radians.
+fn tan(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("tan", args, f64::tan)
+}
+
+fn float_fn(name: &str, args: Vec<ObjectType>, f: fn(f64) -> f64) -> ObjectType {
+    match number_arg(name, &args) {
+        Ok(x) => ObjectType::FloatObj(f(x.as_float().unwrap())),
+        Err(err) => err,
+    }
+}
+
+/// The ratio of a circle's circumference to its diameter.
+fn pi(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    constant(args, std::f64::consts::PI)
+}
+
+/// Euler's number, the base of the natural logarithm.
+fn e(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    constant(args, std::f64::consts::E)
+}
+
+/// A number with its fractional part dropped, rounding toward zero.
+fn trunc(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("trunc", args, f64::trunc)
+}
+
+/// The fractional part of a number, keeping its sign.
+fn fract(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    float_fn("fract", args, f64::fract)
+}
+
+fn approx_eq(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let [a, b, epsilon] = args.as_slice() else {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        ));
+    };
+
+    let (Some(a), Some(b), Some(epsilon)) = (a.as_float(), b.as_float(), epsilon.as_float()) else {
+        return new_error(&format!(
+            "arguments to `approx_eq` must be INTEGER or FLOAT, got {}, {} and {}",
+            a.r#type(),
+            b.r#type(),
+            epsilon.r#type()
+        ));
+    };
+    if epsilon < 0.0 {
+        return new_error(&format!(
+            "epsilon for `approx_eq` can't be negative, got {epsilon}"
+        ));
+    }
+
+    ObjectType::BoolObj((a - b).abs() <= epsilon)
+}
+
+fn constant(args: Vec<ObjectType>, value: f64) -> ObjectType {
+    if !args.is_empty() {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=0",
+            args.len()
+        ));
+    }
+
+    ObjectType::FloatObj(value)
+}
+
+/// The single INTEGER or FLOAT argument a numeric builtin takes.
+fn number_arg<'a>(name: &str, args: &'a [ObjectType]) -> Result<&'a ObjectType, ObjectType> {
+    if args.len() != 1 {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        number @ (ObjectType::IntegerObj(_) | ObjectType::FloatObj(_)) => Ok(number),
+        other => Err(new_error(&format!(
+            "argument to `{name}` must be INTEGER or FLOAT, got {}",
+            other.r#type()
+        ))),
+    }
+}
+
+/// Splits a string on `\n`, dropping a trailing `\r` from each line. A final
+/// newline doesn't start another line, so `""` has no lines at all.
+fn lines(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 1 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ));
+    }
+
+    if let ObjectType::StringObj(string) = &args[0] {
+        ObjectType::ArrayObj(
+            string
+                .lines()
+                .map(|line| ObjectType::StringObj(line.to_string()))
+                .collect(),
+        )
+    } else {
+        new_error(&format!(
+            "argument to `lines` must be STRING, got {}",
+            args[0].r#type()
+        ))
+    }
+}
+
+/// Whether an array has an element equal to `x`, a hash has `x` as a key,
+/// or a string has `x` as a substring.
+fn contains(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let needle = &args[1];
+    match (&args[0], needle) {
+        (ObjectType::ArrayObj(items), _) => ObjectType::BoolObj(
+            items
+                .iter()
+                .any(|item| item == needle || compare_keys(item, needle) == Some(Ordering::Equal)),
+        ),
+        (ObjectType::HashObj(hash), _) => match needle.hash() {
+            Ok(key) => ObjectType::BoolObj(hash.contains_key(&key)),
+            Err(err) => new_error(&err),
+        },
+        (ObjectType::StringObj(string), ObjectType::StringObj(substring)) => {
+            ObjectType::BoolObj(string.contains(substring.as_str()))
+        }
+        (ObjectType::StringObj(_), other) => new_error(&format!(
+            "second argument to `contains` must be STRING when searching a STRING, got {}",
+            other.r#type()
+        )),
+        (other, _) => new_error(&format!(
+            "first argument to `contains` must be ARRAY, HASH or STRING, got {}",
+            other.r#type()
+        )),
+    }
+}
+
+fn starts_with(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match string_pair("starts_with", &args) {
+        Ok((string, prefix)) => ObjectType::BoolObj(string.starts_with(prefix)),
+        Err(err) => err,
+    }
+}
+
+fn ends_with(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match string_pair("ends_with", &args) {
+        Ok((string, suffix)) => ObjectType::BoolObj(string.ends_with(suffix)),
+        Err(err) => err,
+    }
+}
+
+/// Whether two strings are equal once both are lowercased, using Unicode
+/// case rules, so `equals_ignore_case("ÄB", "äb")` is true.
+fn equals_ignore_case(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match string_pair("equals_ignore_case", &args) {
+        Ok((left, right)) => ObjectType::BoolObj(left.to_lowercase() == right.to_lowercase()),
+        Err(err) => err,
+    }
+}
+
+/// Splits a string on every `sep`, or into characters when `sep` is `""`.
+/// Splitting `""` gives no parts at all.
+fn split(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    let (string, sep) = match string_pair("split", &args) {
+        Ok(pair) => pair,
+        Err(err) => return err,
+    };
+
+    if string.is_empty() {
+        return ObjectType::ArrayObj(Vec::new());
+    }
+
+    let parts = if sep.is_empty() {
+        string
+            .chars()
+            .map(|c| ObjectType::StringObj(c.to_string()))
+            .collect()
+    } else {
+        string
+            .split(sep)
+            .map(|part| ObjectType::StringObj(part.to_string()))
+            .collect()
+    };
+    ObjectType::ArrayObj(parts)
+}
+
+/// Like `split`, but stops after `n` parts, the last one keeping the rest
+/// of the string, `split_n("a,b,c", ",", 2)` is `["a", "b,c"]`.
+fn split_n(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 3 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        ));
+    }
+
+    let (string, sep) = match string_pair("split_n", &args[..2]) {
+        Ok(pair) => pair,
+        Err(err) => return err,
+    };
+    let n = match args[2] {
+        ObjectType::IntegerObj(n) if n > 0 => n as usize,
+        ObjectType::IntegerObj(n) => {
+            return new_error(&format!(
+                "third argument to `split_n` must be positive, got {n}"
+            ));
+        }
+        ref other => {
+            return new_error(&format!(
+                "third argument to `split_n` must be INTEGER, got {}",
+                other.r#type()
+            ));
+        }
+    };
+
+    if string.is_empty() {
+        return ObjectType::ArrayObj(Vec::new());
+    }
+
+    let parts: Vec<&str> = if sep.is_empty() {
+        // the first n - 1 characters on their own, then whatever's left
+        let rest = string
+            .char_indices()
+            .nth(n - 1)
+            .map_or(string.len(), |(i, _)| i);
+        let mut parts = string[..rest]
+            .char_indices()
+            .map(|(i, c)| &string[i..i + c.len_utf8()])
+            .collect::<Vec<_>>();
+        if rest < string.len() {
+            parts.push(&string[rest..]);
+        }
+        parts
+    } else {
+        string.splitn(n, sep).collect()
+    };
+    ObjectType::ArrayObj(
+        parts
+            .into_iter()
+            .map(|part| ObjectType::StringObj(part.to_string()))
+            .collect(),
+    )
+}
+
+/// How many times `needle` appears in `haystack` without overlapping,
+/// `count_matches("aaaa", "aa")` is 2.
+fn count_matches(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match string_pair("count_matches", &args) {
+        Ok((_, "")) => new_error("second argument to `count_matches` can't be empty"),
+        Ok((haystack, needle)) => ObjectType::IntegerObj(haystack.matches(needle).count() as i64),
+        Err(err) => err,
+    }
+}
+
+/// Joins an array of strings with `sep` between each, the reverse of `split`.
+fn join(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    let (ObjectType::ArrayObj(array), ObjectType::StringObj(sep)) = (&args[0], &args[1]) else {
+        return new_error(&format!(
+            "arguments to `join` must be ARRAY and STRING, got {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        ));
+    };
+
+    let mut parts = Vec::with_capacity(array.len());
+    for element in array {
+        match element {
+            ObjectType::StringObj(part) => parts.push(part.as_str()),
+            other => {
+                return new_error(&format!(
+                    "elements for `join` must be STRING, got {}",
+                    other.r#type()
+                ));
+            }
+        }
+    }
+
+    ObjectType::StringObj(parts.join(sep))
+}
+
+/// Offset of the first match counted in characters, not bytes, so it lines
+/// up with `len` and indexing. Returns -1 when there's no match.
+fn index_of(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    match string_pair("index_of", &args) {
+        Ok((string, substr)) => ObjectType::IntegerObj(match string.find(substr) {
+            Some(byte_offset) => string[..byte_offset].chars().count() as i64,
+            None => -1,
+        }),
+        Err(err) => err,
+    }
+}
+
+fn pad_left(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    pad("pad_left", args, true)
+}
+
+fn pad_right(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    pad("pad_right", args, false)
+}
+
+/// The widest string `pad_left` and `pad_right` will build, so a runaway
+/// width is an error rather than an allocation the host can't make.
+const MAX_PAD_WIDTH: i64 = 1_000_000;
+
+/// Pads `s` with `fill` up to `width` characters, on the left or the right.
+/// Strings already at or over `width` come back unchanged.
+fn pad(name: &str, args: Vec<ObjectType>, left: bool) -> ObjectType {
+    if args.len() != 3 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        ));
+    }
+
+    let (string, width, fill) = match (&args[0], &args[1], &args[2]) {
+        (ObjectType::StringObj(s), ObjectType::IntegerObj(w), ObjectType::StringObj(f)) => {
+            (s, w, f)
+        }
+        _ => {
+            return new_error(&format!(
+                "arguments to `{name}` must be STRING, INTEGER, STRING, got {}, {}, {}",
+                args[0].r#type(),
+                args[1].r#type(),
+                args[2].r#type()
+            ));
+        }
+    };
+
+    let mut fill_chars = fill.chars();
+    let (Some(fill), None) = (fill_chars.next(), fill_chars.next()) else {
+        return new_error(&format!(
+            "fill for `{name}` must be a single character, got \"{fill}\""
+        ));
+    };
+
+    if *width > MAX_PAD_WIDTH {
+        return new_error(&format!(
+            "width for `{name}` must be at most {MAX_PAD_WIDTH}, got {width}"
+        ));
+    }
+
+    let width = usize::try_from(*width).unwrap_or(0);
+    let missing = width.saturating_sub(string.chars().count());
+    let padding = fill.to_string().repeat(missing);
+
+    if left {
+        ObjectType::StringObj(padding + string)
+    } else {
+        ObjectType::StringObj(string.clone() + &padding)
+    }
+}
+
+/// `substr(s, start, len)` takes `len` characters from `start`, or everything
+/// after `start` when `len` is left off. Runs past the end are clamped.
+fn substr(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 && args.len() != 3 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2 or 3",
+            args.len()
+        ));
+    }
+
+    let (ObjectType::StringObj(string), ObjectType::IntegerObj(start)) = (&args[0], &args[1])
+    else {
+        return new_error(&format!(
+            "arguments to `substr` must be STRING and INTEGER, got {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        ));
+    };
+
+    let len = match args.get(2) {
+        Some(ObjectType::IntegerObj(len)) => usize::try_from(*len).unwrap_or(0),
+        Some(other) => {
+            return new_error(&format!(
+                "length for `substr` must be INTEGER, got {}",
+                other.r#type()
+            ));
+        }
+        None => usize::MAX,
+    };
+
+    let Ok(start) = usize::try_from(*start) else {
+        return ObjectType::StringObj(String::new());
+    };
+
+    ObjectType::StringObj(string.chars().skip(start).take(len).collect())
+}
+
+/// The most decimal places `fmt_num` gives, well past where an f64 has any
+/// digits left and within what `format!` accepts.
+const MAX_DECIMALS: i64 = 100;
+
+/// Formats a number with a fixed number of decimal places, rounding to the
+/// nearest.
+fn fmt_num(args: Vec<ObjectType>, _: &mut dyn CallFunction) -> ObjectType {
+    if args.len() != 2 {
+        return new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        ));
+    }
+
+    match (args[0].as_float(), &args[1]) {
+        (Some(n), ObjectType::IntegerObj(decimals)) if (0..=MAX_DECIMALS).contains(decimals) => {
+            ObjectType::StringObj(format!("{n:.*}", *decimals as usize))
+        }
+        (Some(_), ObjectType::IntegerObj(decimals)) if *decimals > MAX_DECIMALS => new_error(
+            &format!("decimals for `fmt_num` must be at most {MAX_DECIMALS}, got {decimals}"),
+        ),
+        (Some(_), decimals @ (ObjectType::IntegerObj(_) | ObjectType::FloatObj(_))) => {
+            new_error(&format!(
+                "decimals for `fmt_num` must be a whole number >= 0, got {}",
+                decimals.inspect()
+            ))
+        }
+        _ => new_error(&format!(
+            "arguments to `fmt_num` must be INTEGER or FLOAT, got {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        )),
+    }
+}
+
+fn string_pair<'a>(name: &str, args: &'a [ObjectType]) -> Result<(&'a str, &'a str), ObjectType> {
+    if args.len() != 2 {
+        return Err(new_error(&format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (ObjectType::StringObj(left), ObjectType::StringObj(right)) => Ok((left, right)),
+        _ => Err(new_error(&format!(
+            "arguments to `{name}` must be STRING, got {} and {}",
+            args[0].r#type(),
+            args[1].r#type()
+        ))),
+    }
+}
+
+/// How many parameters a Monkey function takes. Builtins check their own
+/// arguments, so they have no fixed arity.
+fn arity(obj: &ObjectType) -> Option<usize> {
+    match obj {
+        ObjectType::FunctionObj(function) => Some(function.parameters.len()),
+        ObjectType::Closure(function, _) => match function.as_ref() {
+            ObjectType::CompileFunction(_, _, num_params) => Some(*num_params),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_callable(obj: &ObjectType) -> bool {
+    matches!(
+        obj,
+        ObjectType::Closure(_, _) | ObjectType::FunctionObj(_) | ObjectType::BuiltinFunction(_)
+    )
+}
+
 fn new_error(msg: &str) -> ObjectType {
     ObjectType::ErrorObj(msg.to_string())
 }
 
-pub fn get_builtin_by_name(name: &str) -> Option<fn(Vec<ObjectType>) -> ObjectType> {
+pub fn get_builtin_by_name(name: &str) -> Option<BuiltinFn> {
     for builtin in BUILTINS.iter() {
         if builtin.name == name {
             return Some(builtin.builtin);
@@ -1,6 +1,9 @@
 mod builtins;
+mod rng;
+mod sexpr;
 use ast::BlockStatement;
 pub use builtins::{BUILTINS, get_builtin_by_name};
+pub use rng::Rng;
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
@@ -12,11 +15,37 @@ pub trait Object {
     fn inspect(&self) -> String;
 }
 
-pub type BuiltinFn = fn(Vec<ObjectType>) -> ObjectType;
+/// Lets builtins call functions through whichever engine, the vm or the
+/// evaluator, is running them.
+pub trait CallFunction {
+    fn call_function(&mut self, function: ObjectType, args: Vec<ObjectType>) -> ObjectType;
 
+    /// Where builtins like `puts` send their output, stdout unless the
+    /// engine says otherwise.
+    fn output(&mut self, line: &str) {
+        println!("{line}");
+    }
+
+    /// Restarts the random numbers builtins like `shuffle` draw, so the same
+    /// seed gives the same sequence. Engines without their own generator
+    /// share one per thread.
+    fn seed(&mut self, seed: u64) {
+        rng::thread_seed(seed);
+    }
+
+    /// The next number from the generator `seed` restarts.
+    fn next_random(&mut self) -> u64 {
+        rng::thread_next_u64()
+    }
+}
+
+pub type BuiltinFn = fn(Vec<ObjectType>, &mut dyn CallFunction) -> ObjectType;
+
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, PartialEq, Default, Debug)]
 pub enum ObjectType {
-    IntegerObj(f64),
+    IntegerObj(i64),
+    FloatObj(f64),
     BoolObj(bool),
     #[default]
     NullObj,
@@ -32,6 +61,7 @@ pub enum ObjectType {
     // compiled function, free variables
     Closure(Box<Self>, Vec<ObjectType>),
     Break,
+    Continue,
 }
 
 impl ObjectType {
@@ -40,24 +70,43 @@ impl ObjectType {
             ObjectType::BoolObj(bool) => Ok(*bool as u64),
             ObjectType::StringObj(string) => Ok(string.chars().map(|c| c as u64).sum()),
             ObjectType::IntegerObj(int) => Ok(*int as u64),
+            // floats aren't hashable, rounding makes equal keys too easy to miss
             _ => Err(format!("unusable as a hash key: {}", self.r#type())),
         }
     }
 
     pub fn to_native_bool(&self) -> bool {
         match self {
-            Self::IntegerObj(v) => !(*v == 0.0),
+            Self::IntegerObj(v) => *v != 0,
+            Self::FloatObj(v) => *v != 0.0,
             Self::BoolObj(b) => *b,
             Self::NullObj => false,
             _ => true,
         }
     }
+
+    /// Whether a condition holding this value passes, as in `if`: everything
+    /// but `false` and `null` counts, unlike `to_native_bool` where `0` doesn't.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Self::NullObj | Self::BoolObj(false))
+    }
+
+    /// An INTEGER or FLOAT as a float, for arithmetic that mixes the two.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::IntegerObj(int) => Some(*int as f64),
+            Self::FloatObj(float) => Some(*float),
+            _ => None,
+        }
+    }
 }
 
 impl From<Token> for ObjectType {
     fn from(value: Token) -> Self {
         match value {
-            Token::Int(t) => Self::IntegerObj(t as f64),
+            Token::Int(t) => Self::IntegerObj(t as i64),
+            // the lexer only hands out digits with a single `.` between them
+            Token::Float(t) => Self::FloatObj(t.parse().unwrap()),
             Token::False => Self::BoolObj(false),
             Token::True => Self::BoolObj(true),
             Token::String(s) => Self::StringObj(s),
@@ -83,6 +132,7 @@ impl Object for ObjectType {
         match self {
             Self::BoolObj(b) => b.to_string(),
             Self::IntegerObj(i) => i.to_string(),
+            Self::FloatObj(f) => f.to_string(),
             Self::NullObj => "NULL".to_string(),
             Self::ReturnValueObj(r) => r.inspect(),
             Self::ErrorObj(e) => e.to_string(),
@@ -96,8 +146,8 @@ impl Object for ObjectType {
                 .join(", "),
             Self::HashObj(h) => format!(
                 "{{{}}}",
-                h.iter()
-                    .map(|(_, v)| format!("{}: {}", v.key, v.value))
+                h.values()
+                    .map(|v| format!("{}: {}", v.key, v.value))
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
@@ -108,6 +158,7 @@ impl Object for ObjectType {
                 format!("{f:?}")
             }
             Self::Break => "break".to_string(),
+            Self::Continue => "continue".to_string(),
         }
     }
 }
@@ -116,6 +167,7 @@ impl Display for ObjectType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::IntegerObj(_) => write!(f, "INTEGER"),
+            Self::FloatObj(_) => write!(f, "FLOAT"),
             Self::BoolObj(_) => write!(f, "BOOLEAN"),
             Self::NullObj => write!(f, "NULL"),
             Self::ReturnValueObj(_) => write!(f, "RETURN"),
@@ -125,9 +177,10 @@ impl Display for ObjectType {
             Self::BuiltinFunction(_) => write!(f, "BUILTIN"),
             Self::ArrayObj(_) => write!(f, "ARRAY"),
             Self::HashObj(_) => write!(f, "HASH"),
-            Self::CompileFunction(_, _, _) => write!(f, "COMPILED FUNCTION"),
-            Self::Closure(_, _) => write!(f, "CLOSURE"),
+            // how the vm holds a function is no concern of the program's
+            Self::CompileFunction(_, _, _) | Self::Closure(_, _) => write!(f, "FUNCTION"),
             Self::Break => write!(f, "BREAK"),
+            Self::Continue => write!(f, "CONTINUE"),
         }
     }
 }
@@ -148,10 +201,10 @@ impl Environment {
 
     pub fn get(&self, name: &str) -> Option<&ObjectType> {
         let value = self.store.get(name);
-        if value.is_none() {
-            if let Some(inner_store) = &self.inner_store {
-                return inner_store.get(name);
-            }
+        if value.is_none()
+            && let Some(inner_store) = &self.inner_store
+        {
+            return inner_store.get(name);
         }
         value
     }
@@ -0,0 +1,69 @@
+//! A small seedable random number generator (splitmix64) for builtins like
+//! `shuffle`. It's quick and reproducible from a seed, not secure.
+use std::{
+    cell::Cell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded from the clock, for when a program hasn't called `seed`.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+thread_local! {
+    // used by engines that don't keep their own, like the evaluator
+    static THREAD_RNG: Cell<Rng> = Cell::new(Rng::from_time());
+}
+
+pub(crate) fn thread_seed(seed: u64) {
+    THREAD_RNG.with(|rng| rng.set(Rng::new(seed)));
+}
+
+pub(crate) fn thread_next_u64() -> u64 {
+    THREAD_RNG.with(|cell| {
+        let mut rng = cell.get();
+        let next = rng.next_u64();
+        cell.set(rng);
+        next
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+    }
+}
@@ -0,0 +1,259 @@
+//! A stable, fully typed text form for values, e.g. `(array (int 1) (int 2))`,
+//! for snapshot tests and handing values to other tools. Unlike `inspect` it
+//! keeps every type visible and lists hash pairs sorted by key, so the same
+//! value always prints the same way.
+use super::{HashPair, ObjectType, builtins::compare_hash_keys};
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+impl ObjectType {
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            Self::IntegerObj(i) => format!("(int {i})"),
+            Self::FloatObj(f) => format!("(float {f})"),
+            Self::BoolObj(b) => format!("(bool {b})"),
+            Self::NullObj => "(null)".to_string(),
+            Self::StringObj(s) => format!("(string {})", quote(s)),
+            Self::ErrorObj(e) => format!("(error {})", quote(e)),
+            Self::ReturnValueObj(value) => format!("(return {})", value.to_sexpr()),
+            Self::ArrayObj(items) => {
+                let items = items.iter().map(|item| format!(" {}", item.to_sexpr()));
+                format!("(array{})", items.collect::<String>())
+            }
+            Self::HashObj(hash) => {
+                let mut pairs = hash.values().collect::<Vec<_>>();
+                pairs.sort_by(|a, b| compare_hash_keys(&a.key, &b.key));
+
+                let pairs = pairs
+                    .iter()
+                    .map(|pair| format!(" ({} {})", pair.key.to_sexpr(), pair.value.to_sexpr()));
+                format!("(hash{})", pairs.collect::<String>())
+            }
+            // code can't be written out, only named
+            Self::FunctionObj(_) | Self::CompileFunction(..) | Self::Closure(..) => {
+                "(function)".to_string()
+            }
+            Self::BuiltinFunction(_) => "(builtin)".to_string(),
+            Self::Break => "(break)".to_string(),
+            Self::Continue => "(continue)".to_string(),
+        }
+    }
+
+    /// Reads back a value written by `to_sexpr`. Functions can't be read back.
+    pub fn from_sexpr(input: &str) -> Result<Self, String> {
+        let mut reader = Reader {
+            chars: input.chars().peekable(),
+        };
+
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        match reader.chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected {c:?} after value")),
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Reader<'_> {
+    fn value(&mut self) -> Result<ObjectType, String> {
+        self.expect('(')?;
+
+        let value = match self.atom()?.as_str() {
+            "int" => {
+                let atom = self.atom()?;
+                let int = atom
+                    .parse()
+                    .map_err(|_| format!("expected a number, got {atom:?}"))?;
+                ObjectType::IntegerObj(int)
+            }
+            "float" => {
+                let atom = self.atom()?;
+                let float = atom
+                    .parse()
+                    .map_err(|_| format!("expected a number, got {atom:?}"))?;
+                ObjectType::FloatObj(float)
+            }
+            "bool" => match self.atom()?.as_str() {
+                "true" => ObjectType::BoolObj(true),
+                "false" => ObjectType::BoolObj(false),
+                other => return Err(format!("expected true or false, got {other:?}")),
+            },
+            "null" => ObjectType::NullObj,
+            "string" => ObjectType::StringObj(self.string()?),
+            "error" => ObjectType::ErrorObj(self.string()?),
+            "return" => ObjectType::ReturnValueObj(Box::new(self.value()?)),
+            "break" => ObjectType::Break,
+            "continue" => ObjectType::Continue,
+            "array" => {
+                let mut items = Vec::new();
+                while !self.at(')') {
+                    items.push(self.value()?);
+                }
+                ObjectType::ArrayObj(items)
+            }
+            "hash" => {
+                let mut pairs = HashMap::new();
+                while !self.at(')') {
+                    self.expect('(')?;
+                    let key = self.value()?;
+                    let value = self.value()?;
+                    self.expect(')')?;
+
+                    pairs.insert(key.hash()?, HashPair { key, value });
+                }
+                ObjectType::HashObj(pairs)
+            }
+            other => return Err(format!("can't read a value of kind {other:?}")),
+        };
+
+        self.expect(')')?;
+        Ok(value)
+    }
+
+    fn atom(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+
+        let mut atom = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            atom.push(c);
+            self.chars.next();
+        }
+
+        if atom.is_empty() {
+            return Err("unexpected end of value".to_string());
+        }
+        Ok(atom)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.chars.next() {
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    other => return Err(format!("unknown escape {other:?}")),
+                },
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected {expected:?}, got {c:?}")),
+            None => Err(format!("expected {expected:?}, got the end of input")),
+        }
+    }
+
+    fn at(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.peek() == Some(&expected)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(value: ObjectType, expected: &str) {
+        assert_eq!(value.to_sexpr(), expected);
+        assert_eq!(ObjectType::from_sexpr(expected), Ok(value));
+    }
+
+    #[test]
+    fn test_scalars() {
+        round_trip(ObjectType::IntegerObj(1), "(int 1)");
+        round_trip(ObjectType::IntegerObj(-2), "(int -2)");
+        round_trip(ObjectType::FloatObj(-2.5), "(float -2.5)");
+        round_trip(ObjectType::BoolObj(false), "(bool false)");
+        round_trip(ObjectType::NullObj, "(null)");
+        round_trip(
+            ObjectType::StringObj(r#"say "hi" \ bye"#.to_string()),
+            r#"(string "say \"hi\" \\ bye")"#,
+        );
+    }
+
+    #[test]
+    fn test_nested_arrays() {
+        round_trip(ObjectType::ArrayObj(vec![]), "(array)");
+        round_trip(
+            ObjectType::ArrayObj(vec![
+                ObjectType::IntegerObj(1),
+                ObjectType::ArrayObj(vec![ObjectType::StringObj("a".to_string())]),
+            ]),
+            r#"(array (int 1) (array (string "a")))"#,
+        );
+    }
+
+    #[test]
+    fn test_hashes() {
+        let mut hash = HashMap::new();
+        for (key, value) in [
+            (
+                ObjectType::StringObj("b".to_string()),
+                ObjectType::IntegerObj(2),
+            ),
+            (ObjectType::IntegerObj(1), ObjectType::IntegerObj(2)),
+            (
+                ObjectType::StringObj("a".to_string()),
+                ObjectType::ArrayObj(vec![]),
+            ),
+        ] {
+            hash.insert(key.hash().unwrap(), HashPair { key, value });
+        }
+
+        round_trip(
+            ObjectType::HashObj(hash),
+            r#"(hash ((int 1) (int 2)) ((string "a") (array)) ((string "b") (int 2)))"#,
+        );
+    }
+
+    #[test]
+    fn test_hash_keys_sort_by_value() {
+        let mut hash = HashMap::new();
+        for key in [
+            ObjectType::StringObj("a".to_string()),
+            ObjectType::IntegerObj(10),
+            ObjectType::BoolObj(true),
+            ObjectType::IntegerObj(2),
+            ObjectType::BoolObj(false),
+        ] {
+            let value = ObjectType::NullObj;
+            hash.insert(key.hash().unwrap(), HashPair { key, value });
+        }
+
+        round_trip(
+            ObjectType::HashObj(hash),
+            r#"(hash ((bool false) (null)) ((bool true) (null)) ((int 2) (null)) ((int 10) (null)) ((string "a") (null)))"#,
+        );
+    }
+
+    #[test]
+    fn test_bad_input() {
+        assert!(ObjectType::from_sexpr("(int one)").is_err());
+        assert!(ObjectType::from_sexpr("(int 2.5)").is_err());
+        assert!(ObjectType::from_sexpr("(array (int 1)").is_err());
+        assert!(ObjectType::from_sexpr("(function)").is_err());
+        assert!(ObjectType::from_sexpr("(null) (null)").is_err());
+    }
+}
@@ -1,9 +1,16 @@
 use ast::{
-    BlockStatement, Expression, LetStatement, Map, MutateStatement, PostfixStatement, Program,
-    ReturnStatement, Statement,
+    BlockStatement, DestructureStatement, Expression, ForStatement, ImportStatement,
+    IndexAssignStatement, LetStatement, Map, ModStatement, MutateStatement, PostfixStatement,
+    Program, ReturnStatement, Statement, WhileStatement,
+};
+use lexer::{Lexer, Position};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    mem,
+    rc::Rc,
+    sync::LazyLock,
 };
-use lexer::Lexer;
-use std::{cell::RefCell, collections::HashMap, mem, rc::Rc, sync::LazyLock};
 use token::Token;
 
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
@@ -31,13 +38,21 @@ static TOKEN_PRECEDENCES: LazyLock<HashMap<Token, ExpressionPrecendence>> = Lazy
     map.insert(Token::Plus, ExpressionPrecendence::Sum);
     map.insert(Token::Minus, ExpressionPrecendence::Sum);
     map.insert(Token::Slash, ExpressionPrecendence::Product);
+    map.insert(Token::Percent, ExpressionPrecendence::Product);
     map.insert(Token::Asterisk, ExpressionPrecendence::Product);
     map.insert(Token::Lparen, ExpressionPrecendence::Call);
     map.insert(Token::Lbracket, ExpressionPrecendence::Index);
+    map.insert(Token::Dot, ExpressionPrecendence::Index);
 
     map
 });
 
+/// How deeply expressions and blocks may nest before parsing gives up,
+/// well short of where the recursion would overflow the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+const TOO_DEEP: &str = "expression too deeply nested";
+
 /// The Monkey Parser.
 ///
 /// Parses Tokens into an AST.
@@ -46,7 +61,15 @@ pub struct Parser<'a> {
     lexer: Lexer<'a>,
     pub cur_token: Token,
     peek_token: Token,
+    // where cur_token and peek_token start, for error messages
+    cur_position: Position,
+    peek_position: Position,
+    // tokens read past peek_token by peek_n, handed out before the lexer's
+    lookahead: VecDeque<(Token, Position)>,
     pub errors: Vec<String>,
+    // current and allowed nesting of expressions and blocks
+    depth: usize,
+    max_depth: usize,
 }
 
 impl<'a> Parser<'a> {
@@ -55,7 +78,12 @@ impl<'a> Parser<'a> {
             lexer,
             cur_token: Token::default(),
             peek_token: Token::default(),
+            cur_position: Position::default(),
+            peek_position: Position::default(),
+            lookahead: VecDeque::new(),
             errors: Vec::default(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
         };
 
         parser.next_token();
@@ -64,27 +92,70 @@ impl<'a> Parser<'a> {
         parser
     }
 
+    /// Sets how deeply expressions and blocks may nest.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Goes one level deeper, or records an error and returns false when
+    /// that would pass the maximum depth.
+    fn descend(&mut self) -> bool {
+        if self.depth >= self.max_depth {
+            self.errors.push(TOO_DEEP.to_string());
+            return false;
+        }
+
+        self.depth += 1;
+        true
+    }
+
     #[cfg(test)]
     fn errors(&self) -> &Vec<String> {
         &self.errors
     }
 
     fn no_prefix_parse_error(&mut self) {
-        let msg = format!("no prefix parse function for {:?} found", self.cur_token);
+        let msg = format!(
+            "no prefix parse function for {:?} found at {}",
+            self.cur_token, self.cur_position
+        );
         self.errors.push(msg)
     }
 
     fn peek_error(&mut self, token: &Token) {
         let msg = format!(
-            "expected next token to be {:?}, got {:?} instead",
-            token, self.peek_token
+            "expected next token to be {:?}, got {:?} instead at {}",
+            token, self.peek_token, self.peek_position
         );
         self.errors.push(msg)
     }
 
     pub fn next_token(&mut self) {
         self.cur_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+        self.cur_position = self.peek_position;
+        (self.peek_token, self.peek_position) = match self.lookahead.pop_front() {
+            Some(next) => next,
+            None => (self.lexer.next_token(), self.lexer.token_position()),
+        };
+    }
+
+    /// Looks `k` tokens past the current one without consuming anything, so
+    /// `peek_n(1)` is the peek token. Past the end of input this is `Eof`.
+    pub fn peek_n(&mut self, k: usize) -> &Token {
+        if k == 0 {
+            return &self.cur_token;
+        }
+        if k == 1 {
+            return &self.peek_token;
+        }
+
+        while self.lookahead.len() < k - 1 {
+            let token = self.lexer.next_token();
+            self.lookahead
+                .push_back((token, self.lexer.token_position()));
+        }
+        &self.lookahead[k - 2].0
     }
 
     pub fn parse_program(&mut self) -> Result<Program, String> {
@@ -102,14 +173,34 @@ impl<'a> Parser<'a> {
 
     fn parse_statement(&mut self) -> Result<Statement, String> {
         match &self.cur_token {
+            Token::Let if self.peek_token_is(&Token::Lbracket) => {
+                self.parse_destructure_statement()
+            }
             Token::Let => self.parse_let_statement(),
             Token::Return => self.parse_return_statement(),
             Token::Loop => self.parse_loop_statement(),
-            Token::Break => {
-                self.next_token();
-                Ok(Statement::BreakStatement)
+            Token::While => self.parse_while_statement(),
+            Token::For => self.parse_for_statement(),
+            Token::Mod => self.parse_mod_statement(),
+            Token::Import => self.parse_import_statement(),
+            Token::Break | Token::Continue => {
+                let statement = if self.cur_token_is(Token::Break) {
+                    Statement::BreakStatement
+                } else {
+                    Statement::ContinueStatement
+                };
+
+                if self.peek_token_is(&Token::Semicolon) {
+                    self.next_token();
+                }
+                Ok(statement)
+            }
+            Token::Ident(_)
+                if self.peek_token_is(&Token::Assign)
+                    || compound_operator(&self.peek_token).is_some() =>
+            {
+                self.parse_mutate_statement()
             }
-            Token::Ident(_) if self.peek_token_is(&Token::Assign) => self.parse_mutate_statement(),
             Token::Ident(_)
                 if self.peek_token_is(&Token::PlusPlus)
                     || self.peek_token_is(&Token::MinusMinus) =>
@@ -135,13 +226,41 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_index_assign_statement(
+        &mut self,
+        left: Expression,
+        index: Expression,
+    ) -> Result<Statement, String> {
+        // move onto the =, then past it
+        self.next_token();
+        self.next_token();
+
+        let value = self
+            .parse_expression(ExpressionPrecendence::Lowest)
+            .ok_or("failed to parse expression")?;
+
+        if self.peek_token_is(&Token::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(Statement::IndexAssignStatement(IndexAssignStatement {
+            left,
+            index,
+            value,
+        }))
+    }
+
     fn parse_mutate_statement(&mut self) -> Result<Statement, String> {
         let mut statement = MutateStatement {
             name: self.cur_token.clone(),
             value: Expression::default(),
         };
 
-        if !self.expect_peek(&Token::Assign) {
+        // a += b is read as a = a + b
+        let operator = compound_operator(&self.peek_token);
+        if operator.is_some() {
+            self.next_token();
+        } else if !self.expect_peek(&Token::Assign) {
             return Err("Failed to parse mutate statement, expected an assignment".to_string());
         }
 
@@ -151,6 +270,14 @@ impl<'a> Parser<'a> {
             .parse_expression(ExpressionPrecendence::Lowest)
             .ok_or("failed to parse expression")?;
 
+        if let Some(operator) = operator {
+            statement.value = Expression::InfixExpression((
+                operator,
+                Box::new(Expression::IdentExpression(statement.name.clone())),
+                Box::new(statement.value),
+            ));
+        }
+
         if let Expression::FunctionLiteral(_, _, _, ref rc) = statement.value {
             let mut name = rc.borrow_mut();
             *name = Some(statement.name.to_string());
@@ -164,6 +291,19 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_expression(&mut self, precendence: ExpressionPrecendence) -> Option<Expression> {
+        if !self.descend() {
+            return None;
+        }
+        let expression = self.parse_expression_at_depth(precendence);
+        self.depth -= 1;
+
+        expression
+    }
+
+    fn parse_expression_at_depth(
+        &mut self,
+        precendence: ExpressionPrecendence,
+    ) -> Option<Expression> {
         let prefix_fn = prefix_function(&self.cur_token);
         if prefix_fn.is_none() {
             self.no_prefix_parse_error();
@@ -192,6 +332,19 @@ impl<'a> Parser<'a> {
             .parse_expression(ExpressionPrecendence::Lowest)
             .ok_or("No expression found")?;
 
+        let statement = match statement {
+            Expression::IndexExpression(left, index) if self.peek_token_is(&Token::Assign) => {
+                return self.parse_index_assign_statement(*left, *index);
+            }
+            statement if compound_operator(&self.peek_token).is_some() => {
+                return Err(format!(
+                    "can't use {} on {statement}, only on a variable",
+                    self.peek_token
+                ));
+            }
+            statement => statement,
+        };
+
         if self.peek_token_is(&Token::Semicolon) {
             self.next_token();
         }
@@ -200,6 +353,16 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_block_statement(&mut self) -> Result<BlockStatement, String> {
+        if !self.descend() {
+            return Err(TOO_DEEP.to_string());
+        }
+        let block = self.parse_block_statement_at_depth();
+        self.depth -= 1;
+
+        block
+    }
+
+    fn parse_block_statement_at_depth(&mut self) -> Result<BlockStatement, String> {
         let mut statements = Vec::new();
 
         self.next_token();
@@ -223,6 +386,121 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_while_statement(&mut self) -> Result<Statement, String> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(&Token::Lparen) {
+            return Err("failed to parse while, expected `(`".to_string());
+        }
+
+        let mut name = None;
+        if self.peek_token_is(&Token::Let) {
+            self.next_token();
+            if !self.expect_peek(&Token::Ident(Default::default())) {
+                return Err("failed to parse while, expected a name".to_string());
+            }
+            name = Some(self.cur_token.clone());
+
+            if !self.expect_peek(&Token::Assign) {
+                return Err("failed to parse while, expected an assignment".to_string());
+            }
+        }
+        self.next_token();
+
+        let condition = self
+            .parse_expression(ExpressionPrecendence::Lowest)
+            .ok_or("failed to parse expression")?;
+
+        if !self.expect_peek(&Token::Rparen) || !self.expect_peek(&Token::Lbrace) {
+            return Err("failed to parse while".to_string());
+        }
+
+        Ok(Statement::WhileStatement(WhileStatement {
+            token,
+            name,
+            condition,
+            body: self.parse_block_statement()?,
+        }))
+    }
+
+    fn parse_for_statement(&mut self) -> Result<Statement, String> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(&Token::Lparen) || !self.expect_peek(&Token::Let) {
+            return Err("failed to parse for, expected `(let`".to_string());
+        }
+        let Statement::LetStatement(init) = self.parse_let_statement()? else {
+            unreachable!("parse_let_statement only makes let statements")
+        };
+        if !self.cur_token_is(Token::Semicolon) {
+            return Err("failed to parse for, expected `;` after the let".to_string());
+        }
+        self.next_token();
+
+        let condition = self
+            .parse_expression(ExpressionPrecendence::Lowest)
+            .ok_or("failed to parse expression")?;
+        if !self.expect_peek(&Token::Semicolon) {
+            return Err("failed to parse for, expected `;` after the condition".to_string());
+        }
+        self.next_token();
+
+        let update = self.parse_statement()?;
+        if !self.expect_peek(&Token::Rparen) || !self.expect_peek(&Token::Lbrace) {
+            return Err("failed to parse for".to_string());
+        }
+
+        Ok(Statement::ForStatement(ForStatement {
+            token,
+            init,
+            condition,
+            update: Box::new(update),
+            body: self.parse_block_statement()?,
+        }))
+    }
+
+    fn parse_mod_statement(&mut self) -> Result<Statement, String> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(&Token::Ident(Default::default())) {
+            return Err("failed to parse mod, expected a name".to_string());
+        }
+        let name = self.cur_token.clone();
+
+        if !self.expect_peek(&Token::Lbrace) {
+            return Err("failed to parse mod, expected `{`".to_string());
+        }
+
+        let mut members = Vec::new();
+        for statement in self.parse_block_statement()?.statements {
+            match statement {
+                Statement::LetStatement(member) => members.push(member),
+                other => return Err(format!("only let statements can go in a mod, got {other}")),
+            }
+        }
+
+        Ok(Statement::ModStatement(ModStatement {
+            token,
+            name,
+            members,
+        }))
+    }
+
+    fn parse_import_statement(&mut self) -> Result<Statement, String> {
+        let token = self.cur_token.clone();
+
+        let Token::String(path) = self.peek_token.clone() else {
+            return Err("failed to parse import, expected a path string".to_string());
+        };
+        self.next_token();
+
+        if self.peek_token_is(&Token::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(Statement::ImportStatement(ImportStatement { token, path }))
+    }
+
     fn parse_return_statement(&mut self) -> Result<Statement, String> {
         let mut statement = ReturnStatement {
             token: self.cur_token.clone(),
@@ -253,7 +531,7 @@ impl<'a> Parser<'a> {
             value: Expression::default(),
         };
 
-        if !self.expect_peek(&Token::Ident(String::default())) {
+        if !self.expect_peek(&Token::Ident(Default::default())) {
             return Err("Failed".to_string());
         }
 
@@ -281,6 +559,50 @@ impl<'a> Parser<'a> {
         Ok(Statement::LetStatement(statement))
     }
 
+    fn parse_destructure_statement(&mut self) -> Result<Statement, String> {
+        let token = self.cur_token.clone();
+        let mut names = Vec::new();
+
+        // advance to the [
+        self.next_token();
+
+        loop {
+            if !self.expect_peek(&Token::Ident(Default::default())) {
+                return Err("failed to parse destructuring let, expected a name".to_string());
+            }
+            names.push(self.cur_token.clone());
+
+            if !self.peek_token_is(&Token::Comma) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(&Token::Rbracket) {
+            return Err("failed to parse destructuring let, expected a ]".to_string());
+        }
+
+        if !self.expect_peek(&Token::Assign) {
+            return Err("Failed to parse let statement".to_string());
+        }
+
+        self.next_token();
+
+        let value = self
+            .parse_expression(ExpressionPrecendence::Lowest)
+            .ok_or("failed to parse expression")?;
+
+        if self.peek_token_is(&Token::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(Statement::DestructureStatement(DestructureStatement {
+            token,
+            names,
+            value,
+        }))
+    }
+
     pub fn parse_function_parameters(&mut self) -> Option<Vec<Token>> {
         let mut parameters = Vec::new();
 
@@ -403,6 +725,10 @@ fn parse_int(p: &mut Parser) -> Option<Expression> {
     Some(Expression::IntExpression(p.cur_token.clone()))
 }
 
+fn parse_float(p: &mut Parser) -> Option<Expression> {
+    Some(Expression::FloatExpression(p.cur_token.clone()))
+}
+
 fn parse_prefix_expression(p: &mut Parser) -> Option<Expression> {
     let prefix = p.cur_token.clone();
     p.next_token();
@@ -417,13 +743,13 @@ fn parse_bool_expression(p: &mut Parser) -> Option<Expression> {
 fn parse_grouped_expression(p: &mut Parser) -> Option<Expression> {
     p.next_token();
 
-    let exp = p.parse_expression(ExpressionPrecendence::Lowest);
+    let exp = p.parse_expression(ExpressionPrecendence::Lowest)?;
 
     if !p.expect_peek(&Token::Rparen) {
         return None;
     }
 
-    exp
+    Some(exp)
 }
 
 fn parse_function_literal(p: &mut Parser) -> Option<Expression> {
@@ -527,6 +853,14 @@ fn parse_index_expression(p: &mut Parser, left: Expression) -> Expression {
     Expression::IndexExpression(Box::new(left), Box::new(index))
 }
 
+fn parse_member_expression(p: &mut Parser, left: Expression) -> Expression {
+    if !p.expect_peek(&Token::Ident(Default::default())) {
+        return Expression::UnknownExpression(Token::Illegal("Failed to parse".to_string()));
+    }
+
+    Expression::MemberExpression(Box::new(left), p.cur_token.clone())
+}
+
 fn parse_hash_literal(p: &mut Parser) -> Option<Expression> {
     #[allow(clippy::mutable_key_type)]
     let mut pairs = HashMap::new();
@@ -559,11 +893,23 @@ fn parse_hash_literal(p: &mut Parser) -> Option<Expression> {
     Some(Expression::HashLiteral(Map { pairs }))
 }
 
+/// The operator a compound assignment like `+=` applies before assigning.
+fn compound_operator(token: &Token) -> Option<Token> {
+    match token {
+        Token::PlusEq => Some(Token::Plus),
+        Token::MinusEq => Some(Token::Minus),
+        Token::AsteriskEq => Some(Token::Asterisk),
+        Token::SlashEq => Some(Token::Slash),
+        _ => None,
+    }
+}
+
 pub fn prefix_function(token: &Token) -> Option<fn(&mut Parser) -> Option<Expression>> {
     match token {
         Token::String(_) => Some(parse_string),
         Token::Ident(_) => Some(parse_ident),
         Token::Int(_) => Some(parse_int),
+        Token::Float(_) => Some(parse_float),
         Token::Bang | Token::Minus => Some(parse_prefix_expression),
         Token::True | Token::False => Some(parse_bool_expression),
         Token::Lparen => Some(parse_grouped_expression),
@@ -580,6 +926,7 @@ pub fn infix_function(token: &Token) -> Option<fn(&mut Parser, Expression) -> Ex
         Token::Plus
         | Token::Minus
         | Token::Slash
+        | Token::Percent
         | Token::Asterisk
         | Token::Eq
         | Token::Not_eq
@@ -589,6 +936,7 @@ pub fn infix_function(token: &Token) -> Option<fn(&mut Parser, Expression) -> Ex
         | Token::And => Some(parse_infix_expression),
         Token::Lparen => Some(parse_call_expression),
         Token::Lbracket => Some(parse_index_expression),
+        Token::Dot => Some(parse_member_expression),
         _ => None,
     }
 }
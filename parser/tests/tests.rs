@@ -92,7 +92,7 @@ fn test_let_statements() {
 
     assert_eq!(program.statements.len(), 3);
 
-    let test_idents = vec!["x", "y", "foobar"];
+    let test_idents = ["x", "y", "foobar"];
 
     for (i, expected) in test_idents.iter().enumerate() {
         let statement = program.statements.get(i).unwrap();
@@ -159,6 +159,21 @@ fn test_integer_expression() {
     }
 }
 
+#[test]
+fn test_float_expression() {
+    let program = test_setup!("2.5;");
+
+    assert_eq!(program.statements.len(), 1);
+
+    match &program.statements[0] {
+        Statement::ExpressStatement(Expression::FloatExpression(token)) => {
+            assert_eq!(token, &Token::Float("2.5".to_string()));
+            assert_eq!(&token.token_literal(), "2.5");
+        }
+        _ => panic!("Expected a FloatExpression statement"),
+    }
+}
+
 #[test]
 fn test_bool_expression() {
     let inputs = ["false;", "true;"];
@@ -183,10 +198,10 @@ fn test_bool_expression() {
 
 #[test]
 fn test_prefix_operator() {
-    let inputs = vec!["!5;", "-15", "!false", "!true"];
-    let expected_prefix = vec!["!", "-", "!", "!"];
-    let expected_int = vec![5, 15];
-    let expected_bool = vec!["false", "true"];
+    let inputs = ["!5;", "-15", "!false", "!true"];
+    let expected_prefix = ["!", "-", "!", "!"];
+    let expected_int = [5, 15];
+    let expected_bool = ["false", "true"];
 
     inputs.iter().enumerate().for_each(|(i, input)| {
         let program = test_setup!(input);
@@ -220,11 +235,11 @@ fn test_prefix_operator() {
 
 #[test]
 fn test_infix_operators() {
-    let inputs = vec![
+    let inputs = [
         "5 + 5", "5 - 5", "5 * 5", "5 / 5", "5 > 5", "5 < 5", "5 == 5", "5 != 5",
     ];
-    let expected_infix = vec!["+", "-", "*", "/", ">", "<", "==", "!="];
-    let expected_int = vec![5; 8];
+    let expected_infix = ["+", "-", "*", "/", ">", "<", "==", "!="];
+    let expected_int = [5; 8];
 
     inputs.iter().enumerate().for_each(|(i, input)| {
         let program = test_setup!(input);
@@ -260,10 +275,10 @@ fn test_infix_operators() {
         }
     });
 
-    let inputs = vec!["true == true", "true != false", "true == true"];
-    let expected_infix = vec!["==", "!=", "=="];
+    let inputs = ["true == true", "true != false", "true == true"];
+    let expected_infix = ["==", "!=", "=="];
     // first three are expected left, next three are expected right
-    let expected_bool = vec!["true", "true", "true", "true", "false", "true"];
+    let expected_bool = ["true", "true", "true", "true", "false", "true"];
 
     inputs.iter().enumerate().for_each(|(i, input)| {
         let program = test_setup!(input);
@@ -314,6 +329,8 @@ fn test_operator_precedence_parsing() {
         ("a * b * c", "((a * b) * c)"),
         ("a * b / c", "((a * b) / c)"),
         ("a + b / c", "(a + (b / c))"),
+        ("a + b % c", "(a + (b % c))"),
+        ("a * b % c", "((a * b) % c)"),
         ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
         ("3 + 4; -5 * 5", "(3 + 4)((-5) * 5)"),
         ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
@@ -373,7 +390,7 @@ fn test_if_expression() {
     match &program.statements[0] {
         Statement::ExpressStatement(expression_statement) => match &expression_statement {
             Expression::IfExpression(conditional, consequence, alt) => {
-                test_infix_expression(&conditional, "<", "x", "y");
+                test_infix_expression(conditional, "<", "x", "y");
 
                 match &consequence.statements[0] {
                     Statement::ExpressStatement(exp) => match &exp {
@@ -404,7 +421,7 @@ fn test_if_expression_with_else() {
     match &program.statements[0] {
         Statement::ExpressStatement(expression_statement) => match &expression_statement {
             Expression::IfExpression(conditional, consequence, alt) => {
-                test_infix_expression(&conditional, "<", "x", "y");
+                test_infix_expression(conditional, "<", "x", "y");
                 match **conditional {
                     Expression::InfixExpression((ref t, ref left, ref right)) => {
                         if let Expression::IdentExpression(ref t) = **left {
@@ -459,16 +476,16 @@ fn test_function_literal() {
             Expression::FunctionLiteral(_, params, body, _) => {
                 assert_eq!(params.len(), 2);
                 if let Token::Ident(x) = &params[0] {
-                    assert_eq!(x, "x");
+                    assert_eq!(&**x, "x");
                 }
                 if let Token::Ident(y) = &params[1] {
-                    assert_eq!(y, "y");
+                    assert_eq!(&**y, "y");
                 }
 
                 assert_eq!(body.statements.len(), 1);
                 match &body.statements[0] {
                     Statement::ExpressStatement(exp) => {
-                        test_infix_expression(&exp, "+", "x", "y");
+                        test_infix_expression(exp, "+", "x", "y");
                     }
                     _ => panic!("Expected expression statement"),
                 }
@@ -481,8 +498,8 @@ fn test_function_literal() {
 
 #[test]
 fn test_function_parameter_parsing() {
-    let inputs = vec!["fn() {};", "fn(x) {};", "fn(x, y, z) {};"];
-    let expected = vec![vec![], vec!["x"], vec!["x", "y", "z"]];
+    let inputs = ["fn() {};", "fn(x) {};", "fn(x, y, z) {};"];
+    let expected = [vec![], vec!["x"], vec!["x", "y", "z"]];
 
     for (i, input) in inputs.iter().enumerate() {
         let program = test_setup!(input);
@@ -513,7 +530,7 @@ fn test_call_expression_parsing() {
     match &program.statements[0] {
         Statement::ExpressStatement(expression) => match &expression {
             Expression::CallExpression(func, args) => {
-                test_ident_expression(&func, "add");
+                test_ident_expression(func, "add");
                 assert_eq!(args.len(), 3);
                 test_int_expression(&args[0], 1);
                 test_infix_expression(&args[1], "*", "2", "3");
@@ -574,8 +591,8 @@ fn test_parsing_index_expression() {
     match &program.statements[0] {
         Statement::ExpressStatement(expression) => match &expression {
             Expression::IndexExpression(left, index) => {
-                test_ident_expression(&left, "myArray");
-                test_infix_expression(&index, "+", "1", "1");
+                test_ident_expression(left, "myArray");
+                test_infix_expression(index, "+", "1", "1");
             }
             _ => unreachable!("Expected an index expression, got {expression:?}"),
         },
@@ -628,6 +645,56 @@ fn test_parsing_empty_hash_literal() {
     }
 }
 
+#[test]
+fn test_compound_assignment() {
+    let inputs = [
+        ("a += 1;", "a = (a + 1);"),
+        ("a -= 2;", "a = (a - 2);"),
+        ("a *= 3;", "a = (a * 3);"),
+        ("a /= 4;", "a = (a / 4);"),
+        ("a += 1 * 2;", "a = (a + (1 * 2));"),
+    ];
+
+    for (input, expected) in inputs {
+        let program = test_setup!(input);
+        assert_eq!(program.statements.len(), 1);
+        assert!(matches!(
+            program.statements[0],
+            Statement::MutateStatement(_)
+        ));
+        assert_eq!(program.to_string(), expected);
+    }
+
+    for input in ["5 += 1;", "a[0] -= 1;", "f() *= 2;"] {
+        let mut parser = Parser::new(Lexer::new(input));
+        let err = parser.parse_program().unwrap_err();
+        assert!(err.ends_with("only on a variable"), "{input}: {err}");
+    }
+}
+
+#[test]
+fn test_hash_literal_equality() {
+    let parse = |input| test_setup!(input).statements;
+
+    assert_eq!(parse("{1: 2};"), parse("{1: 2};"));
+    assert_eq!(
+        parse(r#"{1: 2, "a": true};"#),
+        parse(r#"{"a": true, 1: 2};"#)
+    );
+    assert_ne!(parse("{1: 2};"), parse("{1: 3};"));
+    assert_ne!(parse("{1: 2};"), parse("{1: 2, 3: 4};"));
+
+    // a hash literal nested inside a comparison
+    assert_eq!(
+        parse("if ({1: 2} == x) { 1 }"),
+        parse("if ({1: 2} == x) { 1 }")
+    );
+    assert_ne!(
+        parse("if ({1: 2} == x) { 1 }"),
+        parse("if ({1: 3} == x) { 1 }")
+    );
+}
+
 #[test]
 fn test_function_literal_with_name() {
     let input = "let myFunction = fn() { };";
@@ -700,6 +767,155 @@ fn test_loop() {
     }
 }
 
+#[test]
+fn test_while_let() {
+    let program = test_setup!("while (let x = next()) { x; }");
+
+    assert_eq!(program.statements.len(), 1);
+
+    match &program.statements[0] {
+        Statement::WhileStatement(statement) => {
+            assert_eq!(statement.name, Some(Token::Ident("x".into())));
+            assert_eq!(statement.condition.to_string(), "next()");
+            assert_eq!(statement.body.statements.len(), 1);
+        }
+        _ => panic!("expected a while statement"),
+    }
+    assert_eq!(program.to_string(), "while (let x = next()) x");
+}
+
+#[test]
+fn test_while() {
+    let program = test_setup!("while (i < 5) { i = i + 1; }");
+
+    assert_eq!(program.statements.len(), 1);
+
+    match &program.statements[0] {
+        Statement::WhileStatement(statement) => {
+            assert_eq!(statement.name, None);
+            test_infix_expression(&statement.condition, "<", "i", "5");
+            assert_eq!(statement.body.statements.len(), 1);
+        }
+        _ => panic!("expected a while statement"),
+    }
+    assert_eq!(program.to_string(), "while ((i < 5)) i = (i + 1);");
+
+    for input in [
+        "while i < 5 { i++; }",
+        "while (i < 5) i++;",
+        "while (let = 1) {}",
+    ] {
+        let lexer = Lexer::new(input);
+        assert!(Parser::new(lexer).parse_program().is_err(), "{input}");
+    }
+}
+
+#[test]
+fn test_for() {
+    let program = test_setup!("for (let i = 0; i < 5; i = i + 1) { sum = sum + i; }");
+
+    assert_eq!(program.statements.len(), 1);
+
+    match &program.statements[0] {
+        Statement::ForStatement(statement) => {
+            assert_eq!(statement.init.name, Token::Ident("i".into()));
+            test_int_expression(&statement.init.value, 0);
+            test_infix_expression(&statement.condition, "<", "i", "5");
+            assert!(matches!(*statement.update, Statement::MutateStatement(_)));
+            assert_eq!(statement.body.statements.len(), 1);
+        }
+        _ => panic!("expected a for statement"),
+    }
+    assert_eq!(
+        program.to_string(),
+        "for (let i = 0; (i < 5); i = (i + 1)) sum = (sum + i);"
+    );
+
+    let program = test_setup!("for (let i = 0; i < 5; i++) {}");
+    match &program.statements[0] {
+        Statement::ForStatement(statement) => {
+            assert!(matches!(*statement.update, Statement::PostfixStatement(_)));
+        }
+        _ => panic!("expected a for statement"),
+    }
+
+    for input in [
+        "for (i = 0; i < 5; i++) {}",
+        "for (let i = 0; i < 5) {}",
+        "for (let i = 0; i < 5; i++) i++;",
+    ] {
+        let lexer = Lexer::new(input);
+        assert!(Parser::new(lexer).parse_program().is_err(), "{input}");
+    }
+}
+
+#[test]
+fn test_mod() {
+    let program =
+        test_setup!("mod math { let pi = 3; let area = fn(r) { pi * r * r }; } math.area(2);");
+
+    assert_eq!(program.statements.len(), 2);
+
+    match &program.statements[0] {
+        Statement::ModStatement(statement) => {
+            assert_eq!(statement.name, Token::Ident("math".into()));
+            assert_eq!(statement.members.len(), 2);
+            assert_eq!(statement.members[1].name, Token::Ident("area".into()));
+        }
+        _ => panic!("expected a mod statement"),
+    }
+    match &program.statements[1] {
+        Statement::ExpressStatement(Expression::CallExpression(function, args)) => {
+            match function.as_ref() {
+                Expression::MemberExpression(module, member) => {
+                    test_ident_expression(module, "math");
+                    assert_eq!(member, &Token::Ident("area".into()));
+                }
+                _ => panic!("expected a member expression"),
+            }
+            assert_eq!(args.len(), 1);
+        }
+        _ => panic!("expected a call expression"),
+    }
+    assert_eq!(program.statements[1].to_string(), "math.area(2)");
+
+    for input in ["mod math { 1 + 1; }", "mod { let a = 1; }", "math.1"] {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(program.is_err() || !parser.errors.is_empty(), "{input}");
+    }
+}
+
+#[test]
+fn test_break_and_continue() {
+    let program = test_setup!("loop { continue; break }");
+
+    match &program.statements[0] {
+        Statement::LoopStatement(BlockStatement { statements }) => {
+            assert_eq!(
+                statements,
+                &vec![Statement::ContinueStatement, Statement::BreakStatement]
+            );
+        }
+        _ => panic!("expected a loop statement"),
+    }
+}
+
+#[test]
+fn test_import() {
+    let program = test_setup!(r#"import "lib/math.monkey"; 1"#);
+
+    assert_eq!(program.statements.len(), 2);
+    match &program.statements[0] {
+        Statement::ImportStatement(import) => assert_eq!(import.path, "lib/math.monkey"),
+        _ => panic!("expected an import statement"),
+    }
+
+    let lexer = Lexer::new("import math;");
+    assert!(Parser::new(lexer).parse_program().is_err());
+}
+
 #[test]
 fn test_assign_without_let() {
     let input = "a = 6;";
@@ -735,6 +951,34 @@ fn test_assign_without_let() {
     }
 }
 
+#[test]
+fn test_index_assignment() {
+    let program = test_setup!(r#"a[0] = 9; h["k"][i + 1] = [1]; a[0];"#);
+
+    assert_eq!(program.statements.len(), 3);
+
+    match &program.statements[0] {
+        Statement::IndexAssignStatement(statement) => {
+            test_ident_expression(&statement.left, "a");
+            test_int_expression(&statement.index, 0);
+            test_int_expression(&statement.value, 9);
+        }
+        _ => panic!("expected index assign statement"),
+    }
+    assert!(matches!(
+        &program.statements[1],
+        Statement::IndexAssignStatement(IndexAssignStatement {
+            left: Expression::IndexExpression(_, _),
+            ..
+        })
+    ));
+    assert!(matches!(
+        &program.statements[2],
+        Statement::ExpressStatement(Expression::IndexExpression(_, _))
+    ));
+    assert_eq!(program.to_string(), "a[0] = 9;(h[k])[(i + 1)] = [1];(a[0])");
+}
+
 #[test]
 fn test_postfix_operators() {
     let input = "a++";
@@ -745,7 +989,7 @@ fn test_postfix_operators() {
     match &program.statements[0] {
         Statement::PostfixStatement(PostfixStatement { name, postfix }) => {
             match name {
-                Token::Ident(name) => assert_eq!(name, "a"),
+                Token::Ident(name) => assert_eq!(&**name, "a"),
                 _ => panic!("expected ident expression"),
             }
             assert_eq!(*postfix, Token::PlusPlus);
@@ -761,7 +1005,7 @@ fn test_postfix_operators() {
     match &program.statements[0] {
         Statement::PostfixStatement(PostfixStatement { name, postfix }) => {
             match name {
-                Token::Ident(name) => assert_eq!(name, "a"),
+                Token::Ident(name) => assert_eq!(&**name, "a"),
                 _ => panic!("expected ident expression"),
             }
             assert_eq!(*postfix, Token::MinusMinus);
@@ -769,3 +1013,104 @@ fn test_postfix_operators() {
         _ => panic!("expected prefix expression"),
     }
 }
+
+#[test]
+fn test_destructure_statements() {
+    let program = test_setup!("let [q, r] = divmod(17, 5);");
+
+    assert_eq!(program.statements.len(), 1);
+
+    match &program.statements[0] {
+        Statement::DestructureStatement(statement) => {
+            assert_eq!(
+                statement.names,
+                vec![Token::Ident("q".into()), Token::Ident("r".into())]
+            );
+            assert_eq!(statement.value.to_string(), "divmod(17, 5)");
+        }
+        _ => panic!("expected destructure statement"),
+    }
+
+    assert_eq!(program.to_string(), "let [q, r] = divmod(17, 5);");
+
+    let lexer = Lexer::new("let [q, 5] = x;");
+    let mut parser = Parser::new(lexer);
+    assert!(parser.parse_program().is_err());
+}
+
+#[test]
+fn test_peek_n() {
+    let lexer = Lexer::new("let x = 5;");
+    let mut parser = Parser::new(lexer);
+
+    assert_eq!(parser.peek_n(3), &Token::Int(5));
+    assert_eq!(parser.peek_n(1), &Token::Ident("x".into()));
+    assert_eq!(parser.peek_n(4), &Token::Semicolon);
+    assert_eq!(parser.peek_n(9), &Token::Eof);
+    assert_eq!(parser.cur_token, Token::Let);
+
+    let expected = [
+        Token::Ident("x".into()),
+        Token::Assign,
+        Token::Int(5),
+        Token::Semicolon,
+        Token::Eof,
+    ];
+    for token in expected {
+        parser.next_token();
+        assert_eq!(parser.cur_token, token);
+    }
+}
+
+#[test]
+fn test_max_nesting_depth() {
+    for input in [
+        format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+        format!("{}1{}", "[".repeat(100_000), "]".repeat(100_000)),
+        "if (true) { ".repeat(100_000),
+    ] {
+        let mut parser = Parser::new(Lexer::new(&input));
+
+        assert!(parser.parse_program().is_err());
+        assert_eq!(parser.errors, vec!["expression too deeply nested"]);
+    }
+
+    let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+    let input = nested(100);
+    let mut parser = Parser::new(Lexer::new(&input));
+    assert!(parser.parse_program().is_ok());
+    assert!(parser.errors.is_empty());
+
+    let mut parser = Parser::new(Lexer::new(&input)).with_max_depth(50);
+    assert!(parser.parse_program().is_err());
+    assert_eq!(parser.errors, vec!["expression too deeply nested"]);
+}
+
+#[test]
+fn test_error_positions() {
+    let inputs = [
+        (
+            "let a = 1;\nlet b = 2;\nlet c 3;",
+            "expected next token to be Assign, got Int(3) instead at line 3, col 7",
+        ),
+        (
+            "let a = 1;\r\n\r\nlet b = ;",
+            "no prefix parse function for Semicolon found at line 3, col 9",
+        ),
+        (
+            "let a = 1;\nlet b = /* never\nclosed",
+            "no prefix parse function for Illegal(\"unterminated comment\") found at line 2, col 9",
+        ),
+        (
+            "let f = fn(a,\n    b { a };",
+            "expected next token to be Rparen, got Lbrace instead at line 2, col 7",
+        ),
+    ];
+
+    for (input, expected) in inputs {
+        let mut parser = Parser::new(Lexer::new(input));
+        let _ = parser.parse_program();
+        assert_eq!(parser.errors.first().map(String::as_str), Some(expected));
+    }
+}
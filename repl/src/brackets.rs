@@ -0,0 +1,59 @@
+use lexer::Lexer;
+use token::Token;
+
+/// Whether `buffer` closes every `(`, `[` and `{` it opens and every `/*`
+/// comment, so the repl knows to keep reading lines before parsing it.
+/// Brackets inside strings and comments don't count. A stray closing bracket
+/// also counts as complete, there's nothing more to wait for and the parser
+/// can report it.
+pub fn is_complete(buffer: &str) -> bool {
+    let mut lexer = Lexer::new(buffer);
+    let mut depth = 0usize;
+
+    loop {
+        match lexer.next_token() {
+            Token::Lparen | Token::Lbracket | Token::Lbrace => depth += 1,
+            Token::Rparen | Token::Rbracket | Token::Rbrace => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return true,
+            },
+            Token::Illegal(reason) if reason == "unterminated comment" => return false,
+            Token::Eof => return depth == 0,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_complete() {
+        for buffer in [
+            "",
+            "1 + 2",
+            "let f = fn(x) { x };",
+            "let f = fn(x) {\n  [x, {\"a\": (x)}]\n};",
+            r#""{ (" + "[""#,
+            r###"r"{" + r#"("#"###,
+            "1 // {",
+            "1 /* { */",
+            "1 }",
+        ] {
+            assert!(is_complete(buffer), "{buffer:?}");
+        }
+
+        for buffer in [
+            "fn(x) {",
+            "let f = fn(x) {\n  x",
+            "puts(",
+            "[1, [2]",
+            r#"{"}": "#,
+            "1 /* still\n going",
+            "if (x) { 1 } else {",
+        ] {
+            assert!(!is_complete(buffer), "{buffer:?}");
+        }
+    }
+}
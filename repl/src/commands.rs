@@ -0,0 +1,83 @@
+use std::{
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// whether parser errors come with the monkey face
+static SHOW_BANNER: AtomicBool = AtomicBool::new(true);
+
+/// Lines the repl handles itself instead of evaluating.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Exit,
+    Clear,
+    Banner(bool),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Option<Self> {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["/bye"] | ["exit"] => Some(Self::Exit),
+            [":clear"] => Some(Self::Clear),
+            [":banner", "on"] => Some(Self::Banner(true)),
+            [":banner", "off"] => Some(Self::Banner(false)),
+            _ => None,
+        }
+    }
+
+    pub fn run(self) {
+        match self {
+            Self::Exit => process::exit(0),
+            // clear the screen and move the cursor to the top left
+            Self::Clear => print!("\x1b[2J\x1b[H"),
+            Self::Banner(show) => SHOW_BANNER.store(show, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Builds the message shown for parser errors.
+pub fn parse_error_report(errors: &[String]) -> String {
+    let mut report = String::new();
+
+    if SHOW_BANNER.load(Ordering::Relaxed) {
+        report.push_str(crate::MONKEY_FACE);
+        report.push('\n');
+    }
+    report.push_str("Whoops! We ran into some monkey business here!\nparser errors:");
+    for error in errors {
+        report.push_str(&format!("\n\t{error}"));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MONKEY_FACE;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Command::parse("exit\n"), Some(Command::Exit));
+        assert_eq!(Command::parse(":clear"), Some(Command::Clear));
+        assert_eq!(
+            Command::parse(" :banner  off "),
+            Some(Command::Banner(false))
+        );
+        assert_eq!(Command::parse(":banner"), None);
+        assert_eq!(Command::parse("let clear = 1;"), None);
+    }
+
+    #[test]
+    fn test_banner_toggle() {
+        let errors = ["expected next token to be =".to_string()];
+
+        Command::Banner(false).run();
+        let report = parse_error_report(&errors);
+        assert!(!report.contains(MONKEY_FACE));
+        assert!(report.ends_with("\n\texpected next token to be ="));
+
+        Command::Banner(true).run();
+        assert!(parse_error_report(&errors).starts_with(MONKEY_FACE));
+    }
+}
@@ -1,33 +1,127 @@
-use crate::{read, start};
+use crate::{LineReader, read, start};
+use ast::{Program, Statement};
 use compiler::{Compiler, symbol_table::SymbolTable};
 use object::{Object, ObjectType};
 use vm::{GLOBAL_SIZE, VM};
 
+/// Reads one line from the prompt, runs it and prints what it evaluated to.
 pub fn repl_compiler(
+    reader: &mut LineReader,
     constants: &mut Vec<ObjectType>,
+    instructions: &mut code::Instructions,
     symbol_table: SymbolTable,
     globals: &mut [ObjectType; GLOBAL_SIZE],
 ) -> SymbolTable {
-    let buffer = read!();
-    let program = start!(&buffer, symbol_table);
+    let buffer = read!(reader);
+    run_line(
+        &buffer,
+        constants,
+        instructions,
+        symbol_table,
+        globals,
+        &mut |result| println!("{result}"),
+    )
+}
+
+/// Compiles and runs one line, extending `instructions` so the whole session
+/// builds up as one program. Only the line's own instructions are run.
+///
+/// The line runs a statement at a time so `show` gets the value of every
+/// top-level expression, `1 + 1; 2 + 2` shows `2` then `4`, along with
+/// whatever the last statement left behind. A statement that fails stops the
+/// rest of the line.
+///
+/// A line starting with `:disasm` is compiled but not run, and `show` gets
+/// its disassembly instead.
+pub fn run_line(
+    buffer: &str,
+    constants: &mut Vec<ObjectType>,
+    instructions: &mut code::Instructions,
+    mut symbol_table: SymbolTable,
+    globals: &mut [ObjectType; GLOBAL_SIZE],
+    show: &mut dyn FnMut(&str),
+) -> SymbolTable {
+    if let Some(source) = disasm_source(buffer) {
+        disassemble(source, constants, &symbol_table, show);
+        return symbol_table;
+    }
+
+    let Ok(program) = start!(buffer, symbol_table) else {
+        return symbol_table;
+    };
+
+    let last = program.statements.len().saturating_sub(1);
+    for (i, statement) in program.statements.into_iter().enumerate() {
+        let is_expression = matches!(statement, Statement::ExpressStatement(_));
+
+        let statement_start = instructions.len();
+        let mut comp = match Compiler::new(constants, symbol_table.detached()) {
+            Ok(comp) => comp.with_instructions(std::mem::take(instructions)),
+            Err(e) => {
+                eprintln!("woops! compilation failed - {e}");
+                return symbol_table;
+            }
+        };
+        let compiled = comp.compile(Program {
+            statements: vec![statement],
+        });
+        for warning in comp.warnings.iter() {
+            eprintln!("warning: {warning}");
+        }
 
-    if let Ok(program) = program {
-        let mut comp = Compiler::new(constants, symbol_table);
-        if comp.compile(program).is_err() {
+        *instructions = comp.bytecode().instructions;
+        if compiled.is_err() {
+            // drop whatever part of the statement made it in
+            instructions.truncate(statement_start);
             eprintln!("woops! compilation failed");
+            return symbol_table;
         }
 
-        let symbols = comp.symbol_table.clone();
-        let mut machine = VM::new(comp, globals);
+        symbol_table = comp.symbol_table.clone();
+        let mut machine = VM::new(comp, globals).starting_at(statement_start);
         if let Err(e) = machine.run() {
             eprintln!("whoops! executing the bytecode failed:, {e}");
+            return symbol_table;
         }
 
-        let stack_top = machine.last_popped_stack_elem();
-        println!("{}", stack_top.inspect());
-
-        return symbols;
+        if is_expression || i == last {
+            show(&machine.last_popped_stack_elem().inspect());
+        }
     }
 
     symbol_table
 }
+
+/// The code after a leading `:disasm`, if the line has one.
+fn disasm_source(buffer: &str) -> Option<&str> {
+    let rest = buffer.trim_start().strip_prefix(":disasm")?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+}
+
+/// Shows the bytecode `source` compiles to against the session so far. The
+/// session's constants and symbols are copied, so nothing here sticks.
+fn disassemble(
+    source: &str,
+    constants: &[ObjectType],
+    symbol_table: &SymbolTable,
+    show: &mut dyn FnMut(&str),
+) {
+    let Ok(program) = start!(source) else {
+        return;
+    };
+
+    let mut constants = constants.to_vec();
+    let mut comp = match Compiler::new(&mut constants, symbol_table.detached()) {
+        Ok(comp) => comp,
+        Err(e) => {
+            eprintln!("woops! compilation failed - {e}");
+            return;
+        }
+    };
+    if let Err(e) = comp.compile(program) {
+        eprintln!("woops! compilation failed - {e}");
+        return;
+    }
+
+    show(code::instruction_to_string(&comp.bytecode().instructions).trim_end());
+}
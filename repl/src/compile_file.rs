@@ -1,33 +1,44 @@
-use crate::start;
 use compiler::{Compiler, symbol_table::SymbolTable};
 use object::{Object, ObjectType};
+use std::path::Path;
 use vm::{GLOBAL_SIZE, VM};
 
 pub fn compile(
     constants: &mut Vec<ObjectType>,
     symbol_table: SymbolTable,
     globals: &mut [ObjectType; GLOBAL_SIZE],
-    buffer: &str,
+    path: &Path,
 ) -> SymbolTable {
-    let program = start!(buffer, symbol_table);
-
-    if let Ok(program) = program {
-        let mut comp = Compiler::new(constants, symbol_table);
-        if comp.compile(program).is_err() {
-            eprintln!("woops! compilation failed");
+    let program = match crate::load(path) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{e}");
+            return symbol_table;
         }
+    };
 
-        let symbols = comp.symbol_table.clone();
-        let mut machine = VM::new(comp, globals);
-        if let Err(e) = machine.run() {
-            eprintln!("whoops! executing the bytecode failed - {e}");
+    let mut comp = match Compiler::new(constants, symbol_table.clone()) {
+        Ok(comp) => comp,
+        Err(e) => {
+            eprintln!("woops! compilation failed - {e}");
+            return symbol_table;
         }
+    };
+    if comp.compile(program).is_err() {
+        eprintln!("woops! compilation failed");
+    }
+    for warning in comp.warnings.iter() {
+        eprintln!("warning: {warning}");
+    }
 
-        let stack_top = machine.last_popped_stack_elem();
-        println!("{}", stack_top.inspect());
-
-        return symbols;
+    let symbols = comp.symbol_table.clone();
+    let mut machine = VM::new(comp, globals);
+    if let Err(e) = machine.run() {
+        eprintln!("whoops! executing the bytecode failed - {e}");
     }
 
-    symbol_table
+    let stack_top = machine.last_popped_stack_elem();
+    println!("{}", stack_top.inspect());
+
+    symbols
 }
@@ -1,7 +1,31 @@
-use crate::{evaluate, read};
-use object::Environment;
+use crate::{LineReader, read, start};
+use object::{Environment, Object, ObjectType};
 
-pub fn repl_start(env: &mut Environment) {
-    let buffer = read!();
-    evaluate!(&buffer, env)
+/// Reads one line from the prompt, evaluates it and prints the result.
+pub fn repl_start(reader: &mut LineReader, env: &mut Environment) {
+    let buffer = read!(reader);
+    eval_line(&buffer, env, &mut |result| println!("{result}"));
+}
+
+/// Evaluates one line a statement at a time, handing `show` the value of
+/// every top-level expression and of the last statement. A `return` or an
+/// error ends the line early.
+pub fn eval_line(buffer: &str, env: &mut Environment, show: &mut dyn FnMut(&str)) {
+    let Ok(program) = start!(buffer) else {
+        return;
+    };
+
+    let last = program.statements.len().saturating_sub(1);
+    for (i, statement) in program.statements.iter().enumerate() {
+        let result = evaluator::eval(statement, env);
+
+        match result {
+            ObjectType::ReturnValueObj(value) => return show(&value.inspect()),
+            ObjectType::ErrorObj(_) => return show(&result.inspect()),
+            _ if i == last || matches!(statement, ast::Statement::ExpressStatement(_)) => {
+                show(&result.inspect())
+            }
+            _ => {}
+        }
+    }
 }
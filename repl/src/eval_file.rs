@@ -1,6 +1,75 @@
-use crate::evaluate;
-use object::Environment;
+use ast::Program;
+use object::{Environment, Object};
+use std::path::Path;
 
-pub fn eval(env: &mut Environment, buffer: &str) {
-    evaluate!(buffer, env)
+pub fn eval(env: &mut Environment, path: &Path) {
+    match crate::load(path) {
+        Ok(program) => {
+            let evaluated = evaluator::eval_program(&program, env);
+            println!("{}", evaluated.inspect());
+        }
+        Err(e) => eprintln!("{e}"),
+    }
+}
+
+/// Prints the parse tree of the file at `path` instead of running it, for
+/// `--dump-ast`. Imports are left as written. Returns false when the file
+/// couldn't be read or parsed, after reporting why.
+pub fn dump_ast(path: &Path) -> bool {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{}: couldn't read the file, {e}", path.display());
+            return false;
+        }
+    };
+
+    let lexer = lexer::Lexer::new(&source);
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program();
+    if !parser.errors.is_empty() {
+        eprintln!("{}", crate::parse_error_report(&parser.errors));
+        return false;
+    }
+
+    match program {
+        Ok(program) => {
+            println!("{}", format_ast(&program));
+            true
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", path.display());
+            false
+        }
+    }
+}
+
+/// Each statement as it reads back, one per line, then the whole tree.
+fn format_ast(program: &Program) -> String {
+    let mut out = String::new();
+    for statement in &program.statements {
+        out.push_str(&format!("{statement}\n"));
+    }
+    out.push_str(&format!("\n{program:#?}"));
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lexer::Lexer;
+    use parser::{Parser, test_setup};
+
+    #[test]
+    fn test_format_ast() {
+        let program = test_setup!("let a = 1 + 2;\nputs(a);");
+        let dump = format_ast(&program);
+
+        let (source, tree) = dump.split_once("\n\n").unwrap();
+        assert_eq!(source, "let a = (1 + 2);\nputs(a)");
+        assert!(tree.starts_with("Program {\n    statements: ["));
+        assert!(tree.contains("LetStatement("));
+        assert!(tree.contains("CallExpression("));
+    }
 }
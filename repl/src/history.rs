@@ -0,0 +1,119 @@
+use crate::{CONTINUATION_PROMPT, PROMPT, is_complete};
+use rustyline::{Config, DefaultEditor, error::ReadlineError};
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Names a file to keep history in instead of the default, or turns
+/// history off when empty.
+pub const HISTORY_VAR: &str = "MONKEY_HISTORY";
+
+// the default, under the home directory
+const HISTORY_FILE: &str = ".monkey_history";
+
+/// Reads lines from the prompt with line editing and up-arrow recall of
+/// earlier lines, including ones from past sessions.
+pub struct LineReader {
+    editor: DefaultEditor,
+    history: Option<PathBuf>,
+}
+
+impl LineReader {
+    pub fn new() -> rustyline::Result<Self> {
+        let config = Config::builder()
+            .history_ignore_dups(true)?
+            .auto_add_history(false)
+            .build();
+        let mut editor = DefaultEditor::with_config(config)?;
+
+        let history = history_path(env::var_os(HISTORY_VAR), env::var_os("HOME"));
+        if let Some(path) = &history {
+            // there's nothing to load before the first line is ever saved
+            let _ = editor.load_history(path);
+        }
+
+        Ok(Self { editor, history })
+    }
+
+    /// The next input typed at the prompt. Lines are read until every
+    /// bracket opened is closed, so `fn(x) {` carries on onto the next line.
+    /// Ctrl-C throws away what's been typed so far and Ctrl-D leaves the repl.
+    pub fn read_input(&mut self) -> String {
+        'input: loop {
+            let Some(mut buffer) = self.read_line(PROMPT) else {
+                continue;
+            };
+
+            while !is_complete(&buffer) {
+                let Some(line) = self.read_line(CONTINUATION_PROMPT) else {
+                    continue 'input;
+                };
+                buffer.push('\n');
+                buffer.push_str(&line);
+            }
+
+            self.remember(&buffer);
+            return buffer;
+        }
+    }
+
+    // None when the line is interrupted
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Some(line),
+            Err(ReadlineError::Interrupted) => None,
+            Err(ReadlineError::Eof) => process::exit(0),
+            Err(e) => {
+                eprintln!("couldn't read the line - {e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Adds `input` to the history and appends it to the history file,
+    /// unless it's blank or the same as the input before it.
+    fn remember(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+
+        if let Ok(true) = self.editor.add_history_entry(input)
+            && let Some(path) = &self.history
+            && let Err(e) = self.editor.append_history(path)
+        {
+            eprintln!("couldn't save history to {} - {e}", path.display());
+        }
+    }
+}
+
+fn history_path(configured: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    match configured {
+        Some(path) if path.is_empty() => None,
+        Some(path) => Some(path.into()),
+        None => home.map(|home| Path::new(&home).join(HISTORY_FILE)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_history_path() {
+        let home = || Some(OsString::from("/home/monkey"));
+
+        assert_eq!(
+            history_path(None, home()),
+            Some(PathBuf::from("/home/monkey/.monkey_history"))
+        );
+        assert_eq!(
+            history_path(Some("/tmp/history".into()), home()),
+            Some(PathBuf::from("/tmp/history"))
+        );
+        assert_eq!(history_path(Some("".into()), home()), None);
+        assert_eq!(history_path(None, None), None);
+    }
+}
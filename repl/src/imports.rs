@@ -0,0 +1,71 @@
+use ast::{Program, Statement};
+use std::path::{Path, PathBuf};
+
+/// Reads and parses the file at `path`, with its imports resolved.
+pub fn load(path: &Path) -> Result<Program, String> {
+    let mut importing = Vec::new();
+    load_file(path, &mut importing)
+}
+
+/// Swaps each `import "file";` in `program` for the top-level `let`s of that
+/// file, itself with its imports resolved. Paths are relative to `dir`.
+pub fn resolve_imports(program: Program, dir: &Path) -> Result<Program, String> {
+    let mut importing = Vec::new();
+    resolve(program, dir, &mut importing)
+}
+
+// `importing` holds the chain of files being loaded, so a file that ends up
+// importing itself is caught instead of recursing forever
+fn load_file(path: &Path, importing: &mut Vec<PathBuf>) -> Result<Program, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|_| format!("{}: no such file", path.display()))?;
+
+    if importing.contains(&path) {
+        let chain = importing
+            .iter()
+            .chain([&path])
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>();
+        return Err(format!("import cycle: {}", chain.join(" -> ")));
+    }
+
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("{}: couldn't read the file, {e}", path.display()))?;
+
+    let lexer = lexer::Lexer::new(&source);
+    let mut parser = parser::Parser::new(lexer);
+    let program = parser.parse_program();
+    if !parser.errors.is_empty() {
+        return Err(format!("{}: {}", path.display(), parser.errors.join(", ")));
+    }
+    let program = program.map_err(|e| format!("{}: {e}", path.display()))?;
+
+    importing.push(path.clone());
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let program = resolve(program, dir, importing);
+    importing.pop();
+
+    program
+}
+
+fn resolve(program: Program, dir: &Path, importing: &mut Vec<PathBuf>) -> Result<Program, String> {
+    let mut statements = Vec::new();
+
+    for statement in program.statements {
+        match statement {
+            Statement::ImportStatement(import) => {
+                let imported = load_file(&dir.join(&import.path), importing)?;
+                statements.extend(
+                    imported
+                        .statements
+                        .into_iter()
+                        .filter(|s| matches!(s, Statement::LetStatement(_))),
+                );
+            }
+            statement => statements.push(statement),
+        }
+    }
+
+    Ok(Program { statements })
+}
@@ -1,32 +1,32 @@
+mod brackets;
+mod commands;
 mod compile;
 mod compile_file;
 mod eval;
 mod eval_file;
+mod history;
+mod imports;
 
-pub use compile::repl_compiler;
+pub use brackets::is_complete;
+pub use commands::{Command, parse_error_report};
+pub use compile::{repl_compiler, run_line};
 pub use compile_file::compile;
-pub use eval::repl_start;
-pub use eval_file::eval;
+pub use eval::{eval_line, repl_start};
+pub use eval_file::{dump_ast, eval};
+pub use history::{HISTORY_VAR, LineReader};
+pub use imports::{load, resolve_imports};
 
 #[macro_export]
 macro_rules! read {
-    () => {{
-        use std::{
-            io::{self, Write},
-            process::{self},
-        };
+    ($reader:expr) => {{
+        loop {
+            let buffer = $reader.read_input();
 
-        print!("{}", $crate::PROMPT);
-        let _ = io::stdout().flush();
-
-        let mut buffer = String::new();
-        io::stdin().read_line(&mut buffer).unwrap();
-
-        if buffer.trim() == "/bye" || buffer.trim() == "exit" {
-            process::exit(0)
+            match $crate::Command::parse(&buffer) {
+                Some(command) => command.run(),
+                None => break buffer,
+            }
         }
-
-        buffer
     }};
 }
 
@@ -34,24 +34,14 @@ macro_rules! read {
 macro_rules! parse_errors {
     ($p:expr) => {{
         if !$p.errors.is_empty() {
-            eprintln!("{}", $crate::MONKEY_FACE);
-            eprintln!("Whoops! We ran into some monkey business here!");
-            eprintln!("parser errors:");
-            for error in $p.errors.iter() {
-                eprintln!("\t{error}");
-            }
+            eprintln!("{}", $crate::parse_error_report(&$p.errors));
             return;
         }
     }};
 
     ($p:expr, $symbol_table:expr) => {{
         if !$p.errors.is_empty() {
-            eprintln!("{}", $crate::MONKEY_FACE);
-            eprintln!("Whoops! We ran into some monkey business here!");
-            eprintln!("parser errors:");
-            for error in $p.errors.iter() {
-                eprintln!("\t{error}");
-            }
+            eprintln!("{}", $crate::parse_error_report(&$p.errors));
             return $symbol_table;
         }
     }};
@@ -67,7 +57,11 @@ macro_rules! start {
 
         $crate::parse_errors!(parser);
 
-        program
+        // imports typed at the prompt are relative to where the repl started
+        program.and_then(|program| {
+            $crate::resolve_imports(program, std::path::Path::new("."))
+                .inspect_err(|e| eprintln!("{e}"))
+        })
     }};
 
     ($buffer:expr, $symbol_table:expr) => {{
@@ -78,25 +72,16 @@ macro_rules! start {
 
         $crate::parse_errors!(parser, $symbol_table);
 
-        program
-    }};
-}
-
-#[macro_export]
-macro_rules! evaluate {
-    ($buffer:expr, $env:expr) => {{
-        use object::Object;
-
-        let program = $crate::start!($buffer);
-
-        if let Ok(program) = program {
-            let evaluated = evaluator::eval_program(&program, $env);
-            println!("{}", evaluated.inspect());
-        }
+        program.and_then(|program| {
+            $crate::resolve_imports(program, std::path::Path::new("."))
+                .inspect_err(|e| eprintln!("{e}"))
+        })
     }};
 }
 
 pub const PROMPT: &str = ">> ";
+// shown while the brackets of what's been typed are still open
+pub const CONTINUATION_PROMPT: &str = "... ";
 
 pub const MONKEY_FACE: &str = r#"            __,__
    .--.  .-"     "-.  .--.
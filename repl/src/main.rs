@@ -1,7 +1,7 @@
 use clap::{Parser, ValueEnum};
 use compiler::symbol_table::SymbolTable;
 use object::{Environment, ObjectType};
-use std::path::PathBuf;
+use std::{path::PathBuf, process};
 use vm::GLOBAL_SIZE;
 
 #[derive(ValueEnum, Clone, Default)]
@@ -21,51 +21,74 @@ struct Args {
     #[arg(long)]
     /// Optional mode to run in, defaults to compile
     mode: Option<Mode>,
+
+    #[arg(long, requires = "path")]
+    /// Print the file's parse tree instead of running it
+    dump_ast: bool,
 }
 
 fn main() {
     let args = Args::parse();
+    if args.dump_ast {
+        let path = args.path.expect("clap requires a path with --dump-ast");
+        if !repl::dump_ast(&path) {
+            process::exit(1);
+        }
+        return;
+    }
+
     match args.path {
         Some(path) => match args.mode.unwrap_or_default() {
-            Mode::Compile => match std::fs::read_to_string(path) {
-                Ok(file) => {
-                    let mut constants = Vec::new();
-                    let symbol_table = SymbolTable::new();
-                    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+            Mode::Compile => {
+                let mut constants = Vec::new();
+                let symbol_table = SymbolTable::new();
+                let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
 
-                    repl::compile(&mut constants, symbol_table, &mut globals, &file);
-                }
-                Err(_) => eprintln!("no such file"),
-            },
-            Mode::Eval => match std::fs::read_to_string(path) {
-                Ok(file) => {
-                    let mut env = Environment::new();
-                    repl::eval(&mut env, &file);
-                }
-                Err(_) => eprintln!("no such file"),
-            },
-        },
-        None => match args.mode.unwrap_or_default() {
-            Mode::Compile => start(),
+                repl::compile(&mut constants, symbol_table, &mut globals, &path);
+            }
             Mode::Eval => {
                 let mut env = Environment::new();
-                loop {
-                    repl::repl_start(&mut env);
-                }
+                repl::eval(&mut env, &path);
             }
         },
+        None => {
+            let mut reader = match repl::LineReader::new() {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!("couldn't start the repl - {e}");
+                    return;
+                }
+            };
+
+            match args.mode.unwrap_or_default() {
+                Mode::Compile => start(&mut reader),
+                Mode::Eval => {
+                    let mut env = Environment::new();
+                    loop {
+                        repl::repl_start(&mut reader, &mut env);
+                    }
+                }
+            }
+        }
     }
 }
 
 /// Starts the repl using the bytecode compiler and vm.
-fn start() {
+fn start(reader: &mut repl::LineReader) {
     println!("Hello, this is the Monkey programming language!");
     println!("Feel free to type in commands:");
     let mut constants = Vec::new();
+    let mut instructions = Vec::new();
     let mut symbol_table = SymbolTable::new();
     let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
 
     loop {
-        symbol_table = repl::repl_compiler(&mut constants, symbol_table, &mut globals);
+        symbol_table = repl::repl_compiler(
+            reader,
+            &mut constants,
+            &mut instructions,
+            symbol_table,
+            &mut globals,
+        );
     }
 }
@@ -0,0 +1,103 @@
+use compiler::symbol_table::SymbolTable;
+use object::{Environment, ObjectType};
+use std::{fs, path::PathBuf};
+use vm::GLOBAL_SIZE;
+
+/// A fresh directory holding `files`, removed again when dropped.
+struct Files(PathBuf);
+
+impl Files {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("monkey-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, source) in files {
+            fs::write(dir.join(file), source).unwrap();
+        }
+
+        Self(dir)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for Files {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_import_function() {
+    let files = Files::new(
+        "import",
+        &[
+            (
+                "math.monkey",
+                "let square = fn(x) { x * x }; let twice = fn(x) { square(x) * 2 }; puts(\"not run\");",
+            ),
+            (
+                "main.monkey",
+                r#"import "math.monkey"; twice(3) + square(2)"#,
+            ),
+        ],
+    );
+
+    let program = repl::load(&files.path("main.monkey")).unwrap();
+    // only the lets come across
+    assert_eq!(program.statements.len(), 3);
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let result = vm::run_program(&program, SymbolTable::new(), &mut globals).unwrap();
+    assert_eq!(result, ObjectType::IntegerObj(22));
+
+    let mut env = Environment::new();
+    let result = evaluator::eval_program(&program, &mut env);
+    assert_eq!(result, ObjectType::IntegerObj(22));
+}
+
+#[test]
+fn test_nested_imports_are_relative() {
+    let files = Files::new(
+        "nested",
+        &[("main.monkey", r#"import "lib/two.monkey"; one + two"#)],
+    );
+    fs::create_dir_all(files.path("lib")).unwrap();
+    fs::write(files.path("lib/one.monkey"), "let one = 1;").unwrap();
+    fs::write(
+        files.path("lib/two.monkey"),
+        r#"import "one.monkey"; let two = one + 1;"#,
+    )
+    .unwrap();
+
+    let program = repl::load(&files.path("main.monkey")).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let result = vm::run_program(&program, SymbolTable::new(), &mut globals).unwrap();
+    assert_eq!(result, ObjectType::IntegerObj(3));
+}
+
+#[test]
+fn test_import_errors() {
+    let files = Files::new(
+        "errors",
+        &[
+            ("a.monkey", r#"import "b.monkey"; let a = 1;"#),
+            ("b.monkey", r#"import "a.monkey"; let b = 2;"#),
+            ("missing.monkey", r#"import "nowhere.monkey";"#),
+            ("broken.monkey", "let = 5;"),
+            ("uses_broken.monkey", r#"import "broken.monkey";"#),
+        ],
+    );
+
+    let err = repl::load(&files.path("a.monkey")).unwrap_err();
+    assert!(err.starts_with("import cycle: "), "{err}");
+    assert!(err.ends_with("a.monkey"), "{err}");
+
+    let err = repl::load(&files.path("missing.monkey")).unwrap_err();
+    assert!(err.contains("nowhere.monkey: no such file"), "{err}");
+
+    let err = repl::load(&files.path("uses_broken.monkey")).unwrap_err();
+    assert!(err.contains("broken.monkey: "), "{err}");
+}
@@ -0,0 +1,96 @@
+use compiler::symbol_table::SymbolTable;
+use object::{Environment, ObjectType};
+use vm::GLOBAL_SIZE;
+
+/// Runs `lines` as one compiler/vm repl session, returning what each line
+/// showed.
+fn compile_session(lines: &[&str]) -> Vec<Vec<String>> {
+    let mut constants = Vec::new();
+    let mut instructions = Vec::new();
+    let mut symbol_table = SymbolTable::new();
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut shown = Vec::new();
+            symbol_table = repl::run_line(
+                line,
+                &mut constants,
+                &mut instructions,
+                symbol_table.clone(),
+                &mut globals,
+                &mut |result| shown.push(result.to_string()),
+            );
+            shown
+        })
+        .collect()
+}
+
+fn eval_session(lines: &[&str]) -> Vec<Vec<String>> {
+    let mut env = Environment::new();
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut shown = Vec::new();
+            repl::eval_line(line, &mut env, &mut |result| shown.push(result.to_string()));
+            shown
+        })
+        .collect()
+}
+
+#[test]
+fn test_every_expression_is_shown() {
+    for session in [compile_session, eval_session] {
+        assert_eq!(session(&["1+1; 2+2"]), vec![vec!["2", "4"]]);
+        assert_eq!(
+            session(&["let a = 5; a * 2; let b = a + 1;", "a + b"]),
+            vec![vec!["10", "6"], vec!["11"]]
+        );
+        assert_eq!(
+            session(&[r#"let f = fn(x) { x + 1 }; f(1); "done""#]),
+            vec![vec!["2", "done"]]
+        );
+    }
+}
+
+#[test]
+fn test_failing_statement_ends_the_line() {
+    assert_eq!(
+        compile_session(&["1; -true; 3", "4"]),
+        vec![vec!["1"], vec!["4"]]
+    );
+    assert_eq!(
+        eval_session(&["1; -true; 3", "4"]),
+        vec![vec!["1", "unknown operator: -BOOLEAN"], vec!["4"]]
+    );
+}
+
+#[test]
+fn test_failed_definitions_are_forgotten() {
+    assert_eq!(
+        compile_session(&["let b = nope;", "1", "let c = 1; b", "c"]),
+        vec![vec![], vec!["1"], vec![], vec!["1"]]
+    );
+}
+
+#[test]
+fn test_disasm() {
+    let shown = compile_session(&["let a = 1;", ":disasm a + 2", "a"]);
+    assert_eq!(
+        shown[1],
+        vec!["0000 OpGetGlobal 0\n0003 OpConstant 1\n0006 OpAdd\n0007 OpPop"]
+    );
+    assert_eq!(shown[2], vec!["1"]);
+
+    // nothing is run or kept
+    let shown = compile_session(&["let a = 1;", ":disasm a = 5; let b = 2;", "a", "b"]);
+    assert_eq!(shown[1].len(), 1);
+    assert_eq!(shown[2], vec!["1"]);
+    assert!(shown[3].is_empty());
+
+    // compile errors show nothing
+    let shown = compile_session(&[":disasm c + 1", ":disasmx"]);
+    assert_eq!(shown, vec![Vec::<String>::new(), Vec::new()]);
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, fmt::Display, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, LazyLock},
+};
 
 #[allow(non_camel_case_types)]
 #[derive(PartialEq, Debug, Clone, Default, Hash, Eq)]
@@ -7,8 +11,11 @@ pub enum Token {
     Eof,
 
     // Identifiers & literals
-    Ident(String),
+    // shared so the same name can be handed around without copying it
+    Ident(Arc<str>),
     Int(usize),
+    // kept as written, f64 can't be Eq or Hash
+    Float(String),
     String(String),
 
     // Operators
@@ -18,6 +25,7 @@ pub enum Token {
     Bang,
     Asterisk,
     Slash,
+    Percent,
 
     // Comparisons
     Lt,
@@ -33,10 +41,17 @@ pub enum Token {
     PlusPlus,
     MinusMinus,
 
+    // Compound Assignment, ie a += 1 for a = a + 1
+    PlusEq,
+    MinusEq,
+    AsteriskEq,
+    SlashEq,
+
     // Delimiters
     Comma,
     Semicolon,
     Colon,
+    Dot,
 
     Lparen,
     Rparen,
@@ -55,7 +70,12 @@ pub enum Token {
     Else,
     Return,
     Loop,
+    While,
+    For,
     Break,
+    Continue,
+    Mod,
+    Import,
 }
 
 pub trait TokenLiteral {
@@ -75,12 +95,14 @@ impl TokenLiteral for Token {
             Token::Eof => "\n".to_string(),
             Token::Ident(v) => v.to_string(),
             Token::Int(v) => v.to_string(),
+            Token::Float(v) => v.to_string(),
             Token::Assign => "=".to_string(),
             Token::Plus => "+".to_string(),
             Token::Minus => "-".to_string(),
             Token::Bang => "!".to_string(),
             Token::Asterisk => "*".to_string(),
             Token::Slash => "/".to_string(),
+            Token::Percent => "%".to_string(),
             Token::Lt => "<".to_string(),
             Token::Gt => ">".to_string(),
             Token::Eq => "==".to_string(),
@@ -102,12 +124,22 @@ impl TokenLiteral for Token {
             Token::Lbracket => "[".to_string(),
             Token::Rbracket => "]".to_string(),
             Token::Colon => ":".to_string(),
+            Token::Dot => ".".to_string(),
             Token::Or => "||".to_string(),
             Token::And => "&&".to_string(),
             Token::Loop => "loop".to_string(),
+            Token::While => "while".to_string(),
+            Token::For => "for".to_string(),
             Token::Break => "break".to_string(),
+            Token::Continue => "continue".to_string(),
+            Token::Mod => "mod".to_string(),
+            Token::Import => "import".to_string(),
             Token::PlusPlus => "++".to_string(),
             Token::MinusMinus => "--".to_string(),
+            Token::PlusEq => "+=".to_string(),
+            Token::MinusEq => "-=".to_string(),
+            Token::AsteriskEq => "*=".to_string(),
+            Token::SlashEq => "/=".to_string(),
         }
     }
 }
@@ -122,15 +154,23 @@ static KEYWORDS: LazyLock<HashMap<&'static str, Token>> = LazyLock::new(|| {
     map.insert("else", Token::Else);
     map.insert("return", Token::Return);
     map.insert("loop", Token::Loop);
+    map.insert("while", Token::While);
+    map.insert("for", Token::For);
     map.insert("break", Token::Break);
+    map.insert("continue", Token::Continue);
+    map.insert("mod", Token::Mod);
+    map.insert("import", Token::Import);
 
     map
 });
 
+/// Looks `ident` up in the keyword table, anything that isn't a keyword is
+/// an identifier.
 pub fn look_up_ident(ident: &str) -> Token {
-    if let Some((_, token)) = KEYWORDS.get_key_value(ident) {
-        token.clone()
-    } else {
-        Token::Ident(String::default())
-    }
+    keyword(ident).unwrap_or_else(|| Token::Ident(ident.into()))
+}
+
+/// The keyword token spelled `ident`, if it is one.
+pub fn keyword(ident: &str) -> Option<Token> {
+    KEYWORDS.get(ident).cloned()
 }
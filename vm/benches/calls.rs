@@ -0,0 +1,90 @@
+//! Times hot call sites through globals, which go through the VM's call
+//! target cache. Run with `cargo bench -p vm`.
+use compiler::{Compiler, symbol_table::SymbolTable};
+use lexer::Lexer;
+use object::ObjectType;
+use parser::Parser;
+use std::time::{Duration, Instant};
+use vm::{GLOBAL_SIZE, VM};
+
+const ITERATIONS: u32 = 10;
+
+fn run(input: &str) -> Duration {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut machine = VM::new(comp, &mut globals);
+
+    let start = Instant::now();
+    machine.run().unwrap();
+    start.elapsed()
+}
+
+fn bench(name: &str, input: &str) {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        total += run(input);
+    }
+
+    println!("{name}: {:?} per run", total / ITERATIONS);
+}
+
+fn main() {
+    bench(
+        "fibonacci(20)",
+        r#"
+            let fibonacci = fn(x) {
+                if (x == 0) {
+                    return 0;
+                }
+                if (x == 1) {
+                    return 1;
+                }
+                return fibonacci(x - 1) + fibonacci(x - 2);
+            };
+            fibonacci(20);
+        "#,
+    );
+    bench(
+        "global builtin in a loop",
+        r#"
+            let size = len;
+            let i = 0;
+            loop {
+                if (i == 10000) { break; };
+                size("monkey");
+                i++;
+            }
+        "#,
+    );
+    bench(
+        "global function in a loop",
+        r#"
+            let double = fn(x) { x * 2 };
+            let i = 0;
+            loop {
+                if (i == 10000) { break; };
+                double(i);
+                i++;
+            }
+        "#,
+    );
+    bench(
+        "non-capturing function in a loop",
+        r#"
+            let i = 0;
+            loop {
+                if (i == 10000) { break; };
+                let double = fn(x) { x * 2 };
+                double(i);
+                i++;
+            }
+        "#,
+    );
+}
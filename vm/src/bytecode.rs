@@ -0,0 +1,222 @@
+//! Compiled programs saved to and loaded from `.monkeyc` files, so a script
+//! can be run again without recompiling it.
+//!
+//! A file is the magic bytes, a version byte, the main instructions and then
+//! the constants. Each constant is a tag byte followed by its contents.
+//! Numbers are little endian and lengths are `u32`s.
+use anyhow::{Context, anyhow, bail};
+use code::Instructions;
+use object::{HashPair, Object, ObjectType};
+use std::{collections::HashMap, path::Path};
+
+const MAGIC: &[u8; 7] = b"MONKEYC";
+const VERSION: u8 = 1;
+
+const NULL: u8 = 0;
+const INTEGER: u8 = 1;
+const FLOAT: u8 = 2;
+const BOOLEAN: u8 = 3;
+const STRING: u8 = 4;
+const ARRAY: u8 = 5;
+const HASH: u8 = 6;
+const FUNCTION: u8 = 7;
+const CLOSURE: u8 = 8;
+
+/// Saves `instructions` and the `constants` they were compiled with to
+/// `path`, ready for `read_bytecode`.
+pub fn write_bytecode(
+    path: impl AsRef<Path>,
+    instructions: &[u8],
+    constants: &[ObjectType],
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+
+    let mut out = Vec::from(MAGIC.as_slice());
+    out.push(VERSION);
+    write_bytes(&mut out, instructions)?;
+    write_len(&mut out, constants.len())?;
+    for constant in constants {
+        write_constant(&mut out, constant)?;
+    }
+
+    std::fs::write(path, out).with_context(|| format!("{}: couldn't write", path.display()))
+}
+
+/// Loads the instructions and constants saved by `write_bytecode`, ready for
+/// `VM::from_owned`.
+pub fn read_bytecode(path: impl AsRef<Path>) -> anyhow::Result<(Instructions, Vec<ObjectType>)> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).with_context(|| format!("{}: couldn't read", path.display()))?;
+
+    read(&bytes).with_context(|| format!("{}: bad bytecode", path.display()))
+}
+
+fn read(bytes: &[u8]) -> anyhow::Result<(Instructions, Vec<ObjectType>)> {
+    let mut reader = Reader { bytes };
+
+    if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        bail!("not a monkey bytecode file");
+    }
+    let version = reader.byte()?;
+    if version != VERSION {
+        bail!("bytecode version {version} isn't supported, only {VERSION} is");
+    }
+
+    let instructions = reader.bytes()?.to_vec();
+    let constants = (0..reader.len()?)
+        .map(|_| reader.constant())
+        .collect::<anyhow::Result<_>>()?;
+
+    if !reader.bytes.is_empty() {
+        bail!(
+            "{} unexpected bytes after the constants",
+            reader.bytes.len()
+        );
+    }
+    Ok((instructions, constants))
+}
+
+fn write_constant(out: &mut Vec<u8>, constant: &ObjectType) -> anyhow::Result<()> {
+    match constant {
+        ObjectType::NullObj => out.push(NULL),
+        ObjectType::IntegerObj(int) => {
+            out.push(INTEGER);
+            out.extend(int.to_le_bytes());
+        }
+        ObjectType::FloatObj(float) => {
+            out.push(FLOAT);
+            out.extend(float.to_le_bytes());
+        }
+        ObjectType::BoolObj(bool) => out.extend([BOOLEAN, *bool as u8]),
+        ObjectType::StringObj(string) => {
+            out.push(STRING);
+            write_bytes(out, string.as_bytes())?;
+        }
+        ObjectType::ArrayObj(items) => {
+            out.push(ARRAY);
+            write_len(out, items.len())?;
+            for item in items {
+                write_constant(out, item)?;
+            }
+        }
+        ObjectType::HashObj(hash) => {
+            out.push(HASH);
+            write_len(out, hash.len())?;
+            for pair in hash.values() {
+                write_constant(out, &pair.key)?;
+                write_constant(out, &pair.value)?;
+            }
+        }
+        ObjectType::CompileFunction(instructions, num_locals, num_params) => {
+            out.push(FUNCTION);
+            write_bytes(out, instructions)?;
+            write_len(out, *num_locals)?;
+            write_len(out, *num_params)?;
+        }
+        ObjectType::Closure(function, free) => {
+            out.push(CLOSURE);
+            write_constant(out, function)?;
+            write_len(out, free.len())?;
+            for value in free {
+                write_constant(out, value)?;
+            }
+        }
+        other => bail!("a {} can't be saved as bytecode", other.r#type()),
+    }
+
+    Ok(())
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> anyhow::Result<()> {
+    let len = u32::try_from(len).map_err(|_| anyhow!("{len} is too long to save as bytecode"))?;
+    out.extend(len.to_le_bytes());
+    Ok(())
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> anyhow::Result<()> {
+    write_len(out, bytes.len())?;
+    out.extend(bytes);
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            bail!("the file ends part way through");
+        }
+
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn len(&mut self) -> anyhow::Result<usize> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn bytes(&mut self) -> anyhow::Result<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn constant(&mut self) -> anyhow::Result<ObjectType> {
+        let constant = match self.byte()? {
+            NULL => ObjectType::NullObj,
+            INTEGER => ObjectType::IntegerObj(i64::from_le_bytes(self.array()?)),
+            FLOAT => ObjectType::FloatObj(f64::from_le_bytes(self.array()?)),
+            BOOLEAN => ObjectType::BoolObj(self.byte()? != 0),
+            STRING => ObjectType::StringObj(String::from_utf8(self.bytes()?.to_vec())?),
+            ARRAY => {
+                let len = self.len()?;
+                ObjectType::ArrayObj(
+                    (0..len)
+                        .map(|_| self.constant())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            HASH => {
+                let mut hash = HashMap::new();
+                for _ in 0..self.len()? {
+                    let key = self.constant()?;
+                    let value = self.constant()?;
+                    hash.insert(key.hash().map_err(|e| anyhow!(e))?, HashPair { key, value });
+                }
+                ObjectType::HashObj(hash)
+            }
+            FUNCTION => {
+                let instructions = self.bytes()?.to_vec();
+                ObjectType::CompileFunction(instructions, self.len()?, self.len()?)
+            }
+            CLOSURE => {
+                let function = self.constant()?;
+                if !matches!(function, ObjectType::CompileFunction(..)) {
+                    bail!(
+                        "a closure must wrap a function, got a {}",
+                        function.r#type()
+                    );
+                }
+                let len = self.len()?;
+                let free = (0..len)
+                    .map(|_| self.constant())
+                    .collect::<Result<_, _>>()?;
+                ObjectType::Closure(Box::new(function), free)
+            }
+            tag => bail!("unknown constant tag {tag}"),
+        };
+
+        Ok(constant)
+    }
+}
@@ -0,0 +1,19 @@
+use object::BuiltinFn;
+
+/// A call target resolved from a callee object, so a hot call site can skip
+/// inspecting the callee again.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum CallTarget {
+    // number of local variables, number of params
+    Closure(usize, usize),
+    Builtin(BuiltinFn),
+}
+
+/// What an `OpCallGlobal` site last called: the global it went through, the
+/// version of that global at the time, and the target its value resolved to.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CallSite {
+    pub(super) global: usize,
+    pub(super) version: u64,
+    pub(super) target: CallTarget,
+}
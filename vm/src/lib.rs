@@ -1,11 +1,21 @@
+mod bytecode;
+mod call_cache;
 mod frame;
+mod snapshot;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 
 use anyhow::{anyhow, bail};
+use ast::Program;
+use call_cache::{CallSite, CallTarget};
 use code::{self, Op};
-use compiler::Compiler;
+use compiler::{Compile, Compiler, symbol_table::SymbolTable};
 use frame::Frame;
-use object::{BUILTINS, BuiltinFn, HashPair, ObjectType};
-use std::collections::HashMap;
+use object::{BUILTINS, BuiltinFn, CallFunction, HashPair, Object, ObjectType, Rng};
+use std::{borrow::Cow, collections::HashMap};
+
+pub use bytecode::{read_bytecode, write_bytecode};
+pub use snapshot::VmState;
 
 pub const GLOBAL_SIZE: usize = 1024;
 
@@ -16,18 +26,48 @@ pub const FALSE: ObjectType = ObjectType::BoolObj(false);
 pub const NULL: ObjectType = ObjectType::NullObj;
 
 pub struct VM<'a> {
-    constants: &'a mut Vec<ObjectType>,
+    constants: Cow<'a, [ObjectType]>,
     stack: [ObjectType; STACK_SIZE],
     globals: &'a mut [ObjectType; GLOBAL_SIZE],
     // stack pointer
     sp: usize,
     frames: [Frame; FRAME_SIZE],
     frames_index: usize,
+    // bumped on every assignment to a global, so a cached call site can tell
+    // the global it called through has changed
+    global_versions: [u64; GLOBAL_SIZE],
+    // what each `OpCallGlobal` last called, indexed by its instruction offset
+    call_sites: Vec<Option<CallSite>>,
+    // receives everything builtins print, one line per call
+    output: Box<dyn FnMut(&str) + 'a>,
+    // what `shuffle` draws on, restarted by `seed`
+    rng: Rng,
 }
 
 impl<'a> VM<'a> {
     pub fn new(mut compiler: Compiler<'a>, globals: &'a mut [ObjectType; GLOBAL_SIZE]) -> Self {
-        let main_func = ObjectType::CompileFunction(compiler.bytecode().instructions, 0, 0);
+        let instructions = compiler.bytecode().instructions;
+        let constants: &'a [ObjectType] = compiler.constants;
+
+        Self::with_bytecode(instructions, Cow::Borrowed(constants), globals)
+    }
+
+    /// Builds a vm that owns its bytecode, so it doesn't hold on to the
+    /// compiler that produced it, or can run bytecode loaded from elsewhere.
+    pub fn from_owned(
+        instructions: code::Instructions,
+        constants: Vec<ObjectType>,
+        globals: &'a mut [ObjectType; GLOBAL_SIZE],
+    ) -> Self {
+        Self::with_bytecode(instructions, Cow::Owned(constants), globals)
+    }
+
+    fn with_bytecode(
+        instructions: code::Instructions,
+        constants: Cow<'a, [ObjectType]>,
+        globals: &'a mut [ObjectType; GLOBAL_SIZE],
+    ) -> Self {
+        let main_func = ObjectType::CompileFunction(instructions, 0, 0);
         let main_closure = ObjectType::Closure(Box::new(main_func), vec![]);
         let main_frame = Frame::new(main_closure, 0);
 
@@ -35,19 +75,44 @@ impl<'a> VM<'a> {
         frames[0] = main_frame;
 
         VM {
-            constants: compiler.constants,
+            constants,
             stack: [const { ObjectType::NullObj }; STACK_SIZE],
             globals,
             sp: 0,
             frames,
             frames_index: 1,
+            global_versions: [0; GLOBAL_SIZE],
+            call_sites: Vec::new(),
+            output: Box::new(|line| println!("{line}")),
+            rng: Rng::from_time(),
         }
     }
 
+    /// Sends builtin output such as `puts` to `sink` instead of stdout.
+    pub fn with_output(mut self, sink: impl FnMut(&str) + 'a) -> Self {
+        self.output = Box::new(sink);
+        self
+    }
+
+    /// Starts running the main instructions from `offset` instead of the
+    /// beginning, e.g. just the part a repl line added to the program.
+    pub fn starting_at(mut self, offset: usize) -> Self {
+        self.frames[0].ip = offset as isize - 1;
+        self
+    }
+
     pub fn run(&mut self) -> anyhow::Result<()> {
+        self.execute(0)
+    }
+
+    /// Runs instructions until the frame stack unwinds down to `stop_frames`
+    /// frames or the main frame runs out of instructions.
+    fn execute(&mut self, stop_frames: usize) -> anyhow::Result<()> {
         // ip = 'instruction pointer'
         let mut ip;
-        while self.current_frame().ip < self.current_frame().instructions().len() as isize - 1 {
+        while self.frames_index > stop_frames
+            && self.current_frame().ip < self.current_frame().instructions().len() as isize - 1
+        {
             self.current_frame().ip += 1;
 
             ip = self.current_frame().ip as usize;
@@ -61,7 +126,9 @@ impl<'a> VM<'a> {
 
                     self.push(self.constants[const_index as usize].clone())?;
                 }
-                Op::Add | Op::Sub | Op::Mul | Op::Div => self.execute_binary_operation(&op)?,
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod => {
+                    self.execute_binary_operation(&op)?
+                }
                 Op::Pop => {
                     self.pop();
                 }
@@ -89,6 +156,7 @@ impl<'a> VM<'a> {
                     let global_index = code::read_u16(&instructions[ip + 1..]);
                     self.current_frame().ip += 2;
                     self.globals[global_index as usize] = self.pop();
+                    self.global_versions[global_index as usize] += 1;
                 }
                 Op::GetGlobal => {
                     let global_index = code::read_u16(&instructions[ip + 1..]);
@@ -119,12 +187,27 @@ impl<'a> VM<'a> {
 
                     self.execute_index_expression(left, index)?;
                 }
+                Op::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let left = self.pop();
+
+                    let updated = self.execute_set_index(left, index, value)?;
+                    self.push(updated)?;
+                }
                 Op::Call => {
                     let num_args = code::read_u8(&instructions[ip + 1..]);
                     self.current_frame().ip += 1;
 
                     self.execute_call(num_args.into())?;
                 }
+                Op::CallGlobal => {
+                    let global_index = code::read_u16(&instructions[ip + 1..]);
+                    let num_args = code::read_u8(&instructions[ip + 3..]);
+                    self.current_frame().ip += 3;
+
+                    self.execute_global_call(ip, global_index.into(), num_args.into())?;
+                }
                 Op::ReturnValue => {
                     // Gets the functions return value from the stack
                     let return_value = self.pop();
@@ -188,15 +271,48 @@ impl<'a> VM<'a> {
                     let current_closure = self.current_frame().cl.clone();
                     self.push(current_closure)?;
                 }
+                Op::Destructure => {
+                    let num_names = code::read_u8(&instructions[ip + 1..]);
+                    self.current_frame().ip += 1;
+
+                    let value = self.pop();
+                    self.execute_destructure(value, num_names as usize)?;
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Calls `function` with `args` to completion and returns its result.
+    /// Used by builtins that take a function argument.
+    fn invoke(
+        &mut self,
+        function: ObjectType,
+        args: Vec<ObjectType>,
+    ) -> anyhow::Result<ObjectType> {
+        let frames_index = self.frames_index;
+        let num_args = args.len();
+
+        self.push(function)?;
+        for arg in args {
+            self.push(arg)?;
+        }
+
+        self.execute_call(num_args)?;
+        self.execute(frames_index)?;
+
+        Ok(self.pop())
+    }
+
     fn push_closure(&mut self, const_index: usize, num_free: usize) -> anyhow::Result<()> {
         let constant = &self.constants[const_index];
         if let ObjectType::CompileFunction(_, _, _) = constant {
+            // nothing captured, so there is no environment to gather
+            if num_free == 0 {
+                return self.push(ObjectType::Closure(Box::new(constant.clone()), Vec::new()));
+            }
+
             let mut free = vec![NULL; num_free];
             for (i, obj) in free.iter_mut().enumerate().take(num_free) {
                 *obj = self.stack[self.sp - num_free + i].clone();
@@ -210,46 +326,99 @@ impl<'a> VM<'a> {
     }
 
     fn execute_call(&mut self, num_args: usize) -> anyhow::Result<()> {
-        let callee = self.stack[self.sp - 1 - num_args].clone();
+        let target = Self::resolve_call_target(&self.stack[self.sp - 1 - num_args])?;
+        self.call_target(target, num_args)
+    }
 
+    /// A call through global `global` at instruction offset `site`. The
+    /// target is cached for the site and reused for as long as the global
+    /// hasn't been assigned since.
+    fn execute_global_call(
+        &mut self,
+        site: usize,
+        global: usize,
+        num_args: usize,
+    ) -> anyhow::Result<()> {
+        let version = self.global_versions[global];
+        let cached = self.call_sites.get(site).copied().flatten();
+
+        let target = match cached {
+            Some(cached) if cached.global == global && cached.version == version => cached.target,
+            _ => {
+                // the compiler only emits the call when nothing in the
+                // arguments can reassign the global, so the callee on the
+                // stack is still its current value
+                let target = Self::resolve_call_target(&self.stack[self.sp - 1 - num_args])?;
+                if self.call_sites.len() <= site {
+                    self.call_sites.resize(site + 1, None);
+                }
+                self.call_sites[site] = Some(CallSite {
+                    global,
+                    version,
+                    target,
+                });
+
+                target
+            }
+        };
+
+        self.call_target(target, num_args)
+    }
+
+    fn call_target(&mut self, target: CallTarget, num_args: usize) -> anyhow::Result<()> {
+        match target {
+            CallTarget::Closure(num_locals, num_params) => {
+                self.call_closure(num_locals, num_params, num_args)
+            }
+            CallTarget::Builtin(callee) => self.call_builtin(&callee, num_args),
+        }
+    }
+
+    fn resolve_call_target(callee: &ObjectType) -> anyhow::Result<CallTarget> {
         match callee {
-            ObjectType::Closure(_, _) => self.call_closure(callee, num_args),
-            ObjectType::BuiltinFunction(callee) => self.call_builtin(&callee, num_args),
+            ObjectType::Closure(func, _) => {
+                if let ObjectType::CompileFunction(_, num_locals, num_params) = func.as_ref() {
+                    Ok(CallTarget::Closure(*num_locals, *num_params))
+                } else {
+                    unreachable!("only compiled functions are in closures")
+                }
+            }
+            ObjectType::BuiltinFunction(callee) => Ok(CallTarget::Builtin(*callee)),
             _ => bail!("calling non-function and non-built-in, {:?}", callee),
         }
     }
 
     fn call_builtin(&mut self, callee: &BuiltinFn, num_args: usize) -> anyhow::Result<()> {
         let args = &self.stack[self.sp - num_args..self.sp];
-        let result = callee(args.to_vec());
+        let result = callee(args.to_vec(), self);
         self.sp = self.sp - num_args - 1;
 
         self.push(result)
     }
 
-    fn call_closure(&mut self, callee: ObjectType, num_args: usize) -> anyhow::Result<()> {
-        if let ObjectType::Closure(ref func, _) = callee {
-            if let ObjectType::CompileFunction(_, num_locals, num_params) = func.as_ref() {
-                if num_args != *num_params {
-                    bail!("wrong number of arguments: want={num_params}; got={num_args}");
-                }
+    fn call_closure(
+        &mut self,
+        num_locals: usize,
+        num_params: usize,
+        num_args: usize,
+    ) -> anyhow::Result<()> {
+        if num_args != num_params {
+            bail!("wrong number of arguments: want={num_params}; got={num_args}");
+        }
 
-                let frame = Frame::new(callee.clone(), self.sp - num_args);
+        // the callee's stack slot is dead until the return value lands in it,
+        // so the frame can take the closure instead of copying it per call
+        let callee = std::mem::replace(&mut self.stack[self.sp - 1 - num_args], NULL);
+        let frame = Frame::new(callee, self.sp - num_args);
 
-                let Frame {
-                    base_pointer: bp, ..
-                } = frame;
+        let Frame {
+            base_pointer: bp, ..
+        } = frame;
 
-                self.push_frame(frame);
-                self.sp = bp + num_locals;
+        self.push_frame(frame);
+        self.sp = bp + num_locals;
 
-                Ok(())
-            } else {
-                unreachable!("only compiled functions are in closures")
-            }
-        } else {
-            bail!("unexpected callee: {}", callee)
-        }
+        Ok(())
     }
 
     fn current_frame(&mut self) -> &mut Frame {
@@ -280,6 +449,13 @@ impl<'a> VM<'a> {
                     bail!("index operator not supported: {:?}", index)
                 }
             }
+            ObjectType::StringObj(string) => {
+                if let ObjectType::IntegerObj(int) = index {
+                    self.execute_string_index(&string, int)
+                } else {
+                    bail!("index operator not supported: {:?}", index)
+                }
+            }
             ObjectType::HashObj(hash) => self.execute_hash_index(hash, index),
             _ => bail!("index operator not supported: {:?}", index),
         }
@@ -298,17 +474,85 @@ impl<'a> VM<'a> {
         }
     }
 
-    fn execute_array_index(&mut self, array: Vec<ObjectType>, index: f64) -> anyhow::Result<()> {
-        if index < 0.0 || array.is_empty() {
-            return self.push(NULL);
+    /// Negative indices count back from the end, so `-1` is the last element.
+    fn execute_array_index(&mut self, array: Vec<ObjectType>, index: i64) -> anyhow::Result<()> {
+        let index = if index < 0 {
+            array.len().checked_sub(index.unsigned_abs() as usize)
+        } else {
+            usize::try_from(index).ok()
+        };
+
+        self.push(index.and_then(|i| array.get(i)).unwrap_or(&NULL).clone())
+    }
+
+    /// The character at `index` as a one character string, counting chars
+    /// rather than bytes.
+    fn execute_string_index(&mut self, string: &str, index: i64) -> anyhow::Result<()> {
+        let char = usize::try_from(index)
+            .ok()
+            .and_then(|index| string.chars().nth(index));
+
+        match char {
+            Some(char) => self.push(ObjectType::StringObj(char.to_string())),
+            None => self.push(NULL),
+        }
+    }
+
+    /// `left` with `index` set to `value`, an array can only have existing
+    /// elements replaced while a hash takes new keys too.
+    fn execute_set_index(
+        &mut self,
+        left: ObjectType,
+        index: ObjectType,
+        value: ObjectType,
+    ) -> anyhow::Result<ObjectType> {
+        match (left, index) {
+            (ObjectType::ArrayObj(mut array), ObjectType::IntegerObj(int)) => {
+                let index = if int < 0 {
+                    array.len().checked_sub(int.unsigned_abs() as usize)
+                } else {
+                    usize::try_from(int).ok()
+                };
+
+                match index.and_then(|i| array.get_mut(i)) {
+                    Some(slot) => *slot = value,
+                    None => bail!(
+                        "index {int} out of bounds for ARRAY of length {}",
+                        array.len()
+                    ),
+                }
+
+                Ok(ObjectType::ArrayObj(array))
+            }
+            (ObjectType::HashObj(mut hash), key) => {
+                let hashed = key.hash().map_err(|err| anyhow!(err))?;
+                hash.insert(hashed, HashPair { key, value });
+
+                Ok(ObjectType::HashObj(hash))
+            }
+            (left, index) => bail!(
+                "index assignment not supported: {}[{}]",
+                left.r#type(),
+                index.r#type()
+            ),
         }
+    }
 
-        let index = index as usize;
+    fn execute_destructure(&mut self, value: ObjectType, num_names: usize) -> anyhow::Result<()> {
+        match value {
+            ObjectType::ArrayObj(array) if array.len() == num_names => {
+                // reversed so the first element is popped by the first name
+                for element in array.into_iter().rev() {
+                    self.push(element)?;
+                }
 
-        if index > array.len() - 1 {
-            self.push(NULL)
-        } else {
-            self.push(array.get(index).unwrap_or(&NULL).clone())
+                Ok(())
+            }
+            ObjectType::ArrayObj(array) => bail!(
+                "cannot destructure ARRAY of length {} into {num_names} names",
+                array.len()
+            ),
+            _ => bail!("cannot destructure {value}, expected ARRAY"),
         }
     }
 
@@ -356,10 +600,12 @@ impl<'a> VM<'a> {
     fn execute_minus_operator(&mut self) -> anyhow::Result<()> {
         let operand = self.pop();
 
-        if let ObjectType::IntegerObj(value) = operand {
-            self.push(ObjectType::IntegerObj(-value))
-        } else {
-            bail!("Unsupported type for negation: {}", operand)
+        match operand {
+            ObjectType::IntegerObj(value) => {
+                self.push(ObjectType::IntegerObj(value.wrapping_neg()))
+            }
+            ObjectType::FloatObj(value) => self.push(ObjectType::FloatObj(-value)),
+            _ => bail!("Unsupported type for negation: {}", operand),
         }
     }
 
@@ -377,12 +623,17 @@ impl<'a> VM<'a> {
         let right = self.pop();
         let left = self.pop();
 
-        if let ObjectType::IntegerObj(right) = right {
-            if let ObjectType::IntegerObj(left) = left {
-                return self.execute_int_comparison(op, left, right);
-            }
+        if let ObjectType::IntegerObj(right) = right
+            && let ObjectType::IntegerObj(left) = left
+        {
+            return self.execute_number_comparison(op, left, right);
+        }
+        // an INTEGER against a FLOAT compares by value, so `2 == 2.0`
+        if let (Some(left), Some(right)) = (left.as_float(), right.as_float()) {
+            return self.execute_number_comparison(op, left, right);
         }
 
+        // values of different types are never equal, so `1 == true` is false
         match *op {
             Op::Equal => self.push(ObjectType::BoolObj(right == left)),
             Op::NotEqual => self.push(ObjectType::BoolObj(right != left)),
@@ -396,13 +647,19 @@ impl<'a> VM<'a> {
         }
     }
 
-    fn execute_int_comparison(&mut self, op: &Op, left: f64, right: f64) -> anyhow::Result<()> {
+    fn execute_number_comparison<T: PartialOrd + Default>(
+        &mut self,
+        op: &Op,
+        left: T,
+        right: T,
+    ) -> anyhow::Result<()> {
+        let zero = T::default();
         match *op {
             Op::GreaterThan => self.push(ObjectType::BoolObj(left > right)),
             Op::Equal => self.push(ObjectType::BoolObj(left == right)),
             Op::NotEqual => self.push(ObjectType::BoolObj(left != right)),
-            Op::Or => self.push(ObjectType::BoolObj((left != 0.0) || (right != 0.0))),
-            Op::And => self.push(ObjectType::BoolObj((left != 0.0) && (right != 0.0))),
+            Op::Or => self.push(ObjectType::BoolObj((left != zero) || (right != zero))),
+            Op::And => self.push(ObjectType::BoolObj((left != zero) && (right != zero))),
             _ => bail!("unexpected operator: {}", op),
         }
     }
@@ -411,23 +668,54 @@ impl<'a> VM<'a> {
         let right = self.pop();
         let left = self.pop();
 
-        match left {
-            ObjectType::IntegerObj(left) => match right {
-                ObjectType::IntegerObj(right) => {
-                    self.execute_binary_int_operation(op, left, right)?;
-                }
-                _ => bail!("unexpected operation: INTEGER {} {}", op, right),
-            },
-            ObjectType::StringObj(left) => match right {
-                ObjectType::StringObj(right) => {
-                    self.execute_string_operation(op, left, right)?;
-                }
-                _ => bail!("unexpected operation: STRING {} {}", op, right),
-            },
-            _ => bail!("unexpected operation: {} {} {}", left, op, right),
+        if let (ObjectType::FloatObj(_), _) | (_, ObjectType::FloatObj(_)) = (&left, &right)
+            && let (Some(left), Some(right)) = (left.as_float(), right.as_float())
+        {
+            return self.execute_binary_float_operation(op, left, right);
         }
 
-        Ok(())
+        match (left, right) {
+            (ObjectType::IntegerObj(left), ObjectType::IntegerObj(right)) => {
+                self.execute_binary_int_operation(op, left, right)
+            }
+            (ObjectType::StringObj(left), ObjectType::StringObj(right)) => {
+                self.execute_string_operation(op, left, right)
+            }
+            (ObjectType::StringObj(string), ObjectType::IntegerObj(count))
+            | (ObjectType::IntegerObj(count), ObjectType::StringObj(string))
+                if *op == Op::Mul =>
+            {
+                self.execute_string_repetition(string, count)
+            }
+            (ObjectType::StringObj(_), ObjectType::FloatObj(count))
+            | (ObjectType::FloatObj(count), ObjectType::StringObj(_))
+                if *op == Op::Mul =>
+            {
+                bail!("cannot repeat a string a non-whole number of times: {count}")
+            }
+            (ObjectType::ArrayObj(mut left), ObjectType::ArrayObj(right)) if *op == Op::Add => {
+                left.extend(right);
+                self.push(ObjectType::ArrayObj(left))
+            }
+            // keys on the right replace matching keys on the left. Every key is
+            // already hashed, so merging can't hit an unhashable one.
+            (ObjectType::HashObj(mut left), ObjectType::HashObj(right)) if *op == Op::Add => {
+                left.extend(right);
+                self.push(ObjectType::HashObj(left))
+            }
+            (left, right) if std::mem::discriminant(&left) != std::mem::discriminant(&right) => {
+                bail!("type mismatch: {} {} {}", left, op, right)
+            }
+            (left, right) => bail!("unexpected operation: {} {} {}", left, op, right),
+        }
+    }
+
+    fn execute_string_repetition(&mut self, string: String, count: i64) -> anyhow::Result<()> {
+        if count < 0 {
+            bail!("cannot repeat a string a negative number of times: {count}");
+        }
+
+        self.push(ObjectType::StringObj(string.repeat(count as usize)))
     }
 
     fn execute_string_operation(
@@ -446,18 +734,48 @@ impl<'a> VM<'a> {
     }
 
     fn execute_binary_int_operation(
+        &mut self,
+        op: &Op,
+        left: i64,
+        right: i64,
+    ) -> anyhow::Result<()> {
+        match *op {
+            Op::Add => self.push(ObjectType::IntegerObj(left.wrapping_add(right))),
+            Op::Sub => self.push(ObjectType::IntegerObj(left.wrapping_sub(right))),
+            Op::Mul => self.push(ObjectType::IntegerObj(left.wrapping_mul(right))),
+            Op::Div | Op::Mod if right == 0 => {
+                self.push(ObjectType::ErrorObj("division by zero".to_string()))
+            }
+            // stays an INTEGER only when it divides evenly, `5 / 2` is 2.5
+            Op::Div if left.wrapping_rem(right) != 0 => {
+                self.push(ObjectType::FloatObj(left as f64 / right as f64))
+            }
+            Op::Div => self.push(ObjectType::IntegerObj(left.wrapping_div(right))),
+            // truncated, so the result takes the sign of the left operand
+            Op::Mod => self.push(ObjectType::IntegerObj(left.wrapping_rem(right))),
+
+            _ => bail!("Unsupported integer operator: {}", op),
+        }
+    }
+
+    /// Arithmetic with at least one FLOAT, which always gives a FLOAT.
+    fn execute_binary_float_operation(
         &mut self,
         op: &Op,
         left: f64,
         right: f64,
     ) -> anyhow::Result<()> {
         match *op {
-            Op::Add => self.push(ObjectType::IntegerObj(left + right)),
-            Op::Sub => self.push(ObjectType::IntegerObj(left - right)),
-            Op::Mul => self.push(ObjectType::IntegerObj(left * right)),
-            Op::Div => self.push(ObjectType::IntegerObj(left / right)),
+            Op::Add => self.push(ObjectType::FloatObj(left + right)),
+            Op::Sub => self.push(ObjectType::FloatObj(left - right)),
+            Op::Mul => self.push(ObjectType::FloatObj(left * right)),
+            Op::Div | Op::Mod if right == 0.0 => {
+                self.push(ObjectType::ErrorObj("division by zero".to_string()))
+            }
+            Op::Div => self.push(ObjectType::FloatObj(left / right)),
+            Op::Mod => self.push(ObjectType::FloatObj(left % right)),
 
-            _ => bail!("Unsupported integer operator: {}", op),
+            _ => bail!("Unsupported float operator: {}", op),
         }
     }
 
@@ -486,7 +804,58 @@ impl<'a> VM<'a> {
         }
     }
 
+    /// The value most recently popped off the stack. The stack starts out
+    /// filled with `NULL`, so a program that never popped anything (an empty
+    /// one, say) gets `NULL` back.
     pub fn last_popped_stack_elem(&self) -> ObjectType {
         self.stack[self.sp].clone()
     }
 }
+
+/// Compiles and runs an already parsed `program`, so it can be parsed once
+/// and run many times. Globals can be seeded by defining names in
+/// `symbol_table` and setting the matching slots of `globals` beforehand.
+///
+/// Returns the last value popped off the stack.
+pub fn run_program(
+    program: &Program,
+    symbol_table: SymbolTable,
+    globals: &mut [ObjectType; GLOBAL_SIZE],
+) -> anyhow::Result<ObjectType> {
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, symbol_table)?;
+    for statement in &program.statements {
+        statement.compile(&mut comp)?;
+    }
+
+    let mut vm = VM::new(comp, globals);
+    vm.run()?;
+
+    Ok(vm.last_popped_stack_elem())
+}
+
+impl CallFunction for VM<'_> {
+    fn call_function(&mut self, function: ObjectType, args: Vec<ObjectType>) -> ObjectType {
+        let (sp, frames_index) = (self.sp, self.frames_index);
+
+        self.invoke(function, args).unwrap_or_else(|e| {
+            // unwind whatever the failed call left behind
+            self.sp = sp;
+            self.frames_index = frames_index;
+
+            ObjectType::ErrorObj(e.to_string())
+        })
+    }
+
+    fn output(&mut self, line: &str) {
+        (self.output)(line);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{Frame, GLOBAL_SIZE, NULL, VM};
+use object::ObjectType;
+
+/// Everything a [`VM`] needs to pick up from an earlier point, taken with
+/// [`VM::snapshot`]. Only the live part of the stack and frames is kept, and
+/// the globals up to the last one set.
+#[derive(Debug, Clone)]
+pub struct VmState {
+    globals: Vec<ObjectType>,
+    // the live stack plus the slot `last_popped_stack_elem` reads
+    stack: Vec<ObjectType>,
+    sp: usize,
+    frames: Vec<Frame>,
+}
+
+impl VM<'_> {
+    /// Captures the globals, stack and frames so [`VM::restore`] can roll
+    /// back to this point, e.g. to undo a call that failed half way.
+    pub fn snapshot(&self) -> VmState {
+        let globals_len = self
+            .globals
+            .iter()
+            .rposition(|global| *global != NULL)
+            .map_or(0, |last| last + 1);
+        let stack_len = (self.sp + 1).min(self.stack.len());
+
+        VmState {
+            globals: self.globals[..globals_len].to_vec(),
+            stack: self.stack[..stack_len].to_vec(),
+            sp: self.sp,
+            frames: self.frames[..self.frames_index].to_vec(),
+        }
+    }
+
+    /// Rolls the globals, stack and frames back to `state`.
+    pub fn restore(&mut self, state: VmState) {
+        let VmState {
+            globals,
+            stack,
+            sp,
+            frames,
+        } = state;
+
+        let globals_len = globals.len();
+        for (slot, global) in self.globals.iter_mut().zip(globals) {
+            *slot = global;
+        }
+        self.globals[globals_len..GLOBAL_SIZE].fill(NULL);
+
+        // slots above `sp` are dead either way, so they're left as they are
+
+        let stack_len = stack.len();
+        self.stack[..stack_len].clone_from_slice(&stack);
+        self.sp = sp;
+
+        self.frames_index = frames.len();
+        for (slot, frame) in self.frames.iter_mut().zip(frames) {
+            *slot = frame;
+        }
+
+        // the globals changed without their versions moving on, so cached
+        // call sites can't be trusted
+        self.call_sites.clear();
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers for driving source code through the lexer, parser, compiler and
+//! VM in tests, so callers don't have to wire each stage together by hand.
+//!
+//! Every helper panics if the source fails to parse, compile or run.
+use crate::{GLOBAL_SIZE, NULL, VM};
+use ast::Program;
+use code::Instructions;
+use compiler::{Compiler, symbol_table::SymbolTable};
+use lexer::Lexer;
+use object::ObjectType;
+use parser::Parser;
+
+/// Everything the compiler produced for a program.
+pub struct CompilerOutput {
+    pub instructions: Instructions,
+    pub constants: Vec<ObjectType>,
+    pub symbol_table: SymbolTable,
+}
+
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    if !parser.errors.is_empty() {
+        panic!("parser errors: {:?}", parser.errors);
+    }
+
+    program.unwrap_or_else(|e| panic!("failed to parse: {e}"))
+}
+
+/// Compiles `input` into bytecode.
+pub fn compile(input: &str) -> CompilerOutput {
+    let program = parse(input);
+    let mut constants = Vec::new();
+
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program)
+        .unwrap_or_else(|e| panic!("compilation failed: {e}"));
+
+    let instructions = comp.bytecode().instructions;
+    let symbol_table = comp.symbol_table;
+
+    CompilerOutput {
+        instructions,
+        constants,
+        symbol_table,
+    }
+}
+
+/// Compiles and runs `input`, returning the last value popped off the stack.
+pub fn run(input: &str) -> ObjectType {
+    let program = parse(input);
+    let mut constants = Vec::new();
+
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program)
+        .unwrap_or_else(|e| panic!("compilation failed: {e}"));
+
+    let mut globals = [const { NULL }; GLOBAL_SIZE];
+    let mut vm = VM::new(comp, &mut globals);
+    vm.run()
+        .unwrap_or_else(|e| panic!("executing the bytecode failed: {e}"));
+
+    vm.last_popped_stack_elem()
+}
+
+/// Compiles `input` and returns its disassembled main instructions.
+pub fn disasm(input: &str) -> String {
+    code::instruction_to_string(&compile(input).instructions)
+}
@@ -1,7 +1,7 @@
 use compiler::{Compiler, symbol_table::SymbolTable};
 use core::panic;
 use lexer::Lexer;
-use object::{self, ObjectType};
+use object::{self, CallFunction, ObjectType};
 use parser::{Parser, test_setup};
 use std::{any::Any, collections::HashMap};
 use vm::*;
@@ -16,7 +16,7 @@ fn run_vm_tests(tests: Vec<VmTestCase>) {
         let program = test_setup!(&test.input);
         let mut constants = Vec::new();
         let symbol_table = SymbolTable::new();
-        let mut comp = Compiler::new(&mut constants, symbol_table);
+        let mut comp = Compiler::new(&mut constants, symbol_table).unwrap();
         let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
 
         comp.compile(program).unwrap();
@@ -29,10 +29,26 @@ fn run_vm_tests(tests: Vec<VmTestCase>) {
     }
 }
 
+/// Compiles and runs `input`, returning the error the vm failed with.
+fn vm_error(input: &str) -> String {
+    let program = test_setup!(input);
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+
+    comp.compile(program).unwrap();
+
+    let mut vm = VM::new(comp, &mut globals);
+    vm.run().unwrap_err().to_string()
+}
+
 fn test_expected_object(expected: Box<dyn Any>, actual: &object::ObjectType) {
     if expected.is::<f64>() {
         return test_integer_object(*expected.downcast::<f64>().unwrap(), actual);
     }
+    if expected.is::<Approx>() {
+        return test_approx_object(*expected.downcast::<Approx>().unwrap(), actual);
+    }
     if expected.is::<bool>() {
         return test_bool_object(*expected.downcast::<bool>().unwrap(), actual);
     }
@@ -42,6 +58,12 @@ fn test_expected_object(expected: Box<dyn Any>, actual: &object::ObjectType) {
     if expected.is::<Vec<f64>>() {
         return test_array_object(*expected.downcast::<Vec<f64>>().unwrap(), actual);
     }
+    if expected.is::<Vec<&'static str>>() {
+        return test_string_array_object(
+            *expected.downcast::<Vec<&'static str>>().unwrap(),
+            actual,
+        );
+    }
     if expected.is::<HashMap<u64, f64>>() {
         return test_hash_object(*expected.downcast::<HashMap<u64, f64>>().unwrap(), actual);
     }
@@ -62,10 +84,14 @@ fn test_expected_object(expected: Box<dyn Any>, actual: &object::ObjectType) {
 
 fn test_object_type(expected: ObjectType, actual: &ObjectType) {
     match expected {
-        ObjectType::NullObj => assert_eq!(expected, *actual),
+        ObjectType::NullObj | ObjectType::IntegerObj(_) | ObjectType::FloatObj(_) => {
+            assert_eq!(expected, *actual)
+        }
         ObjectType::ErrorObj(s) => {
             if let ObjectType::ErrorObj(actual_s) = actual {
                 assert_eq!(s, *actual_s);
+            } else {
+                panic!("expected an error {s:?}, got: {:?}", actual);
             }
         }
         _ => panic!("object type not handled: {:?}", expected),
@@ -96,6 +122,18 @@ fn test_array_object(expected: Vec<f64>, actual: &ObjectType) {
     }
 }
 
+fn test_string_array_object(expected: Vec<&str>, actual: &ObjectType) {
+    match actual {
+        ObjectType::ArrayObj(objs) => {
+            assert_eq!(expected.len(), objs.len());
+            for (i, obj) in objs.iter().enumerate() {
+                test_string_object(expected[i], obj);
+            }
+        }
+        _ => panic!("expected an array object, got: {:?}", actual),
+    }
+}
+
 fn test_string_object(expected: &str, actual: &ObjectType) {
     match actual {
         ObjectType::StringObj(s) => assert_eq!(expected, *s),
@@ -112,11 +150,30 @@ fn test_bool_object(expected: bool, actual: &object::ObjectType) {
 
 fn test_integer_object(expected: f64, actual: &object::ObjectType) {
     match actual {
-        ObjectType::IntegerObj(x) => assert_eq!(expected, *x),
+        ObjectType::IntegerObj(x) => assert_eq!(expected, *x as f64),
         _ => panic!("expected only integer objects, got: {:?}", actual),
     }
 }
 
+/// An expected float that only has to be within 1e-9 of the result, for
+/// results rounding keeps from being exact.
+struct Approx(f64);
+
+fn test_approx_object(Approx(expected): Approx, actual: &object::ObjectType) {
+    match actual {
+        ObjectType::FloatObj(x) => assert!(
+            (x - expected).abs() < 1e-9,
+            "expected about {expected}, got {x}"
+        ),
+        _ => panic!("expected a float, got: {:?}", actual),
+    }
+}
+
+/// Runs `input` and checks it gives a float close to `expected`.
+fn test_approx(input: &str, expected: f64) {
+    test_approx_object(Approx(expected), &test_utils::run(input));
+}
+
 macro_rules! vm_test_case {
     ($input:expr, $expected:expr) => {{
         VmTestCase {
@@ -140,6 +197,56 @@ fn test_integer_arithmetic() {
         vm_test_case!("-10", -10.0f64),
         vm_test_case!("-50 + 100 + -50", 0.0f64),
         vm_test_case!("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50.0f64),
+        vm_test_case!("5 % 3", 2.0f64),
+        vm_test_case!("10 % 2", 0.0f64),
+        vm_test_case!("-7 % 3", -1.0f64),
+        vm_test_case!("7 % -3", 1.0f64),
+        vm_test_case!("1 + 10 % 4 * 2", 5.0f64),
+        vm_test_case!("5 % 3 == 2", true),
+        vm_test_case!("10 % 2 == 0", true),
+        vm_test_case!(
+            "5 % 0",
+            ObjectType::ErrorObj("division by zero".to_string())
+        ),
+        vm_test_case!(
+            "5 / 0",
+            ObjectType::ErrorObj("division by zero".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_integer_and_float_arithmetic() {
+    run_vm_tests(vec![
+        vm_test_case!("4 / 2", ObjectType::IntegerObj(2)),
+        vm_test_case!("5 / 2", ObjectType::FloatObj(2.5)),
+        vm_test_case!("-7 / 2", ObjectType::FloatObj(-3.5)),
+        vm_test_case!("5 / 2 * 2", ObjectType::FloatObj(5.0)),
+        vm_test_case!("5 / 2 + 1", ObjectType::FloatObj(3.5)),
+        vm_test_case!("-(5 / 2)", ObjectType::FloatObj(-2.5)),
+        vm_test_case!("len(\"four\")", ObjectType::IntegerObj(4)),
+        vm_test_case!("5 / 2 > 2", true),
+        vm_test_case!("4 / 2 == 2", true),
+        vm_test_case!("5 / 2 == 2", false),
+        vm_test_case!(
+            "5 / 2 % 0",
+            ObjectType::ErrorObj("division by zero".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_float_literals() {
+    run_vm_tests(vec![
+        vm_test_case!("2.5", ObjectType::FloatObj(2.5)),
+        vm_test_case!("-0.5", ObjectType::FloatObj(-0.5)),
+        vm_test_case!("1.5 + 1", ObjectType::FloatObj(2.5)),
+        vm_test_case!("2.0 * 3", ObjectType::FloatObj(6.0)),
+        vm_test_case!("let x = 1.25; x * 4", ObjectType::FloatObj(5.0)),
+        vm_test_case!("[1.5, 2][0]", ObjectType::FloatObj(1.5)),
+        vm_test_case!("2.5 == 5 / 2", true),
+        vm_test_case!("2.0 == 2", true),
+        vm_test_case!("0.1 < 0.2", true),
     ]);
 }
 
@@ -220,12 +327,12 @@ fn test_array_literals() {
 #[test]
 fn test_hash_literals() {
     let mut hash1 = HashMap::new();
-    hash1.insert(ObjectType::IntegerObj(1.0).hash().unwrap(), 2.0f64);
-    hash1.insert(ObjectType::IntegerObj(2.0).hash().unwrap(), 3.0f64);
+    hash1.insert(ObjectType::IntegerObj(1).hash().unwrap(), 2.0f64);
+    hash1.insert(ObjectType::IntegerObj(2).hash().unwrap(), 3.0f64);
 
     let mut hash2 = HashMap::new();
-    hash2.insert(ObjectType::IntegerObj(2.0).hash().unwrap(), 4.0f64);
-    hash2.insert(ObjectType::IntegerObj(6.0).hash().unwrap(), 16.0f64);
+    hash2.insert(ObjectType::IntegerObj(2).hash().unwrap(), 4.0f64);
+    hash2.insert(ObjectType::IntegerObj(6).hash().unwrap(), 16.0f64);
 
     run_vm_tests(vec![
         vm_test_case!("{1: 2, 2: 3}", hash1),
@@ -241,7 +348,13 @@ fn test_index_expressions() {
         vm_test_case!("[[1,1,1]][0][0]", 1f64),
         vm_test_case!("[][0]", NULL),
         vm_test_case!("[1,2,3][99]", NULL),
-        vm_test_case!("[1][-1]", NULL),
+        vm_test_case!("[1][-1]", 1f64),
+        vm_test_case!("[1, 2, 3][-1]", 3f64),
+        vm_test_case!("[1, 2, 3][-3]", 1f64),
+        vm_test_case!("[1, 2, 3][0 - 2]", 2f64),
+        vm_test_case!("[1, 2, 3][-4]", NULL),
+        vm_test_case!("[][-1]", NULL),
+        vm_test_case!("[1, 2, 3][-9223372036854775807 - 1]", NULL),
         vm_test_case!("{1: 1, 2: 2}[1]", 1f64),
         vm_test_case!("{1: 1, 2: 2}[2]", 2f64),
         vm_test_case!("{1: 1}[0]", NULL),
@@ -249,6 +362,25 @@ fn test_index_expressions() {
     ]);
 }
 
+#[test]
+fn test_string_index() {
+    run_vm_tests(vec![
+        vm_test_case!(r#""abc"[0]"#, "a"),
+        vm_test_case!(r#""abc"[1] == "b""#, true),
+        vm_test_case!(r#""abc"[1 + 1]"#, "c"),
+        vm_test_case!(r#"let s = "héllo"; s[1]"#, "é"),
+        vm_test_case!(r#""abc"[9]"#, NULL),
+        vm_test_case!(r#""abc"[3]"#, NULL),
+        vm_test_case!(r#""abc"[-1]"#, NULL),
+        vm_test_case!(r#"""[0]"#, NULL),
+    ]);
+
+    assert_eq!(
+        vm_error(r#""abc"["a"]"#),
+        r#"index operator not supported: StringObj("a")"#
+    );
+}
+
 #[test]
 fn test_function_calls() {
     run_vm_tests(vec![
@@ -499,7 +631,7 @@ fn call_functions_with_wrong_arguments() {
         let program = test_setup!(&test.input);
         let mut constants = Vec::new();
         let symbol_table = SymbolTable::new();
-        let mut comp = Compiler::new(&mut constants, symbol_table);
+        let mut comp = Compiler::new(&mut constants, symbol_table).unwrap();
         let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
 
         comp.compile(program).unwrap();
@@ -632,6 +764,43 @@ fn test_closures() {
     ]);
 }
 
+#[test]
+fn test_calls_leave_callee_intact() {
+    run_vm_tests(vec![
+        // a non-capturing function called repeatedly from the same global
+        vm_test_case!(
+            r#"
+                    let double = fn(x) { x * 2 };
+                    double(1) + double(2) + double(3);
+                "#,
+            12.0
+        ),
+        vm_test_case!(
+            r#"
+                    let fns = [fn() { 1 }, fn() { 2 }];
+                    fns[0]() + fns[0]() + fns[1]();
+                "#,
+            4.0
+        ),
+        // a capturing closure keeps its environment across calls
+        vm_test_case!(
+            r#"
+                    let newAdder = fn(a) { fn(b) { a + b } };
+                    let addTwo = newAdder(2);
+                    addTwo(1) + addTwo(10);
+                "#,
+            15.0
+        ),
+        vm_test_case!(
+            r#"
+                    let twice = fn(f, x) { f(f(x)) };
+                    twice(fn(x) { x * 3 }, 2);
+                "#,
+            18.0
+        ),
+    ]);
+}
+
 #[test]
 fn test_recursive_functions() {
     run_vm_tests(vec![
@@ -745,6 +914,68 @@ fn test_mutation() {
     ]);
 }
 
+#[test]
+fn test_compound_assignment() {
+    run_vm_tests(vec![
+        vm_test_case!("let a = 5; a += 5; a;", 10.0),
+        vm_test_case!("let a = 5; a -= 7; a;", -2.0),
+        vm_test_case!("let a = 5; a *= 3; a;", 15.0),
+        vm_test_case!("let a = 5; a /= 2; a;", ObjectType::FloatObj(2.5)),
+        vm_test_case!("let a = 5; let b = 6; a += b * 2; a;", 17.0),
+        vm_test_case!(r#"let s = "a"; s += "b"; s;"#, "ab"),
+        vm_test_case!("let a = [1]; a += [2]; a;", vec![1.0, 2.0]),
+        vm_test_case!("let f = fn() { let n = 1; n *= 4; n -= 1; n }; f()", 3.0),
+        vm_test_case!(
+            "let sum = 0; for (let i = 1; i < 5; i += 1) { sum += i; } sum",
+            10.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_while() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "let i = 0; let sum = 0; while (i < 5) { sum = sum + i; i = i + 1; } sum",
+            10.0
+        ),
+        vm_test_case!("let i = 0; while (false) { i++; } i", 0.0),
+        vm_test_case!(
+            "let i = 0; while (true) { if (i == 3) { break; }; i++; } i",
+            3.0
+        ),
+        vm_test_case!(
+            "let f = fn(n) { let i = 0; while (i < n) { i++; } i }; f(4) + f(2)",
+            6.0
+        ),
+        vm_test_case!(
+            r#"
+                let i = 0;
+                let n = 0;
+                while (i < 3) {
+                    let j = 0;
+                    while (j < 2) { n++; j++; }
+                    i++;
+                }
+                n
+            "#,
+            6.0
+        ),
+    ]);
+
+    // nothing should be left behind on the stack by the loop
+    let program = test_setup!("let i = 0; while (i < 100) { i++; } i");
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut vm = VM::new(comp, &mut globals);
+    vm.run().unwrap();
+    assert_eq!(vm.stack_top(), None);
+    assert_eq!(vm.last_popped_stack_elem(), ObjectType::IntegerObj(100));
+}
+
 #[test]
 fn test_postfix() {
     run_vm_tests(vec![
@@ -752,3 +983,2190 @@ fn test_postfix() {
         vm_test_case!("let a = 5; a--;", 4.0),
     ]);
 }
+
+#[test]
+fn test_global_call_cache_invalidation() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            r#"
+                let f = fn(x) { x + 1 };
+                let a = f(1);
+                f = fn(x) { x * 10 };
+                a + f(2);
+            "#,
+            22.0
+        ),
+        vm_test_case!(
+            r#"
+                let f = len;
+                let a = f("ab");
+                f = fn(x) { 5 };
+                a + f(1);
+            "#,
+            7.0
+        ),
+        vm_test_case!(
+            r#"
+                let f = fn(x) { x + 1 };
+                let total = 0;
+                let i = 0;
+                loop {
+                    if (i == 2) { break; };
+                    total = total + f(i);
+                    i++;
+                }
+                f = fn(x) { x * 10 };
+                loop {
+                    if (i == 4) { break; };
+                    total = total + f(i);
+                    i++;
+                }
+                total;
+            "#,
+            53.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_global_call_cache_per_call_site() {
+    run_vm_tests(vec![
+        // reassigned while its arguments are worked out, the call still goes
+        // to the function that was looked up
+        vm_test_case!(
+            r#"
+                let f = fn(x) { x + 1 };
+                let g = fn() { f = fn(x) { x * 10 }; 2 };
+                f(g()) + f(2);
+            "#,
+            23.0
+        ),
+        // the calls in `ca` and `cb` sit at the same offset in their functions
+        vm_test_case!(
+            r#"
+                let a = fn() { 1 };
+                let b = fn() { 2 };
+                let ca = fn() { a() };
+                let cb = fn() { b() };
+                ca() + cb() * 10 + ca() * 100;
+            "#,
+            121.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_global_call_cache_after_restore() {
+    let program = test_setup!(
+        r#"
+            let f = len;
+            let swap = fn() { f = fn(x) { 5 }; };
+            let call = fn() { f("ab") };
+            [swap, call]
+        "#
+    );
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut vm = VM::new(comp, &mut globals);
+    vm.run().unwrap();
+    let ObjectType::ArrayObj(fns) = vm.last_popped_stack_elem() else {
+        panic!("expected the functions in an array");
+    };
+    let (swap, call) = (fns[0].clone(), fns[1].clone());
+
+    let state = vm.snapshot();
+    vm.call_function(swap, vec![]);
+    assert_eq!(
+        vm.call_function(call.clone(), vec![]),
+        ObjectType::IntegerObj(5)
+    );
+
+    // `f` is `len` again, whatever the call site saw last
+    vm.restore(state);
+    assert_eq!(vm.call_function(call, vec![]), ObjectType::IntegerObj(2));
+}
+
+#[test]
+fn test_global_call_cache_checks_arity_after_reassignment() {
+    assert_eq!(
+        vm_error("let f = fn(x) { x }; f(1); f = fn(x, y) { x + y }; f(1);"),
+        "wrong number of arguments: want=2; got=1"
+    );
+}
+
+#[test]
+fn test_utils_match_manual_setup() {
+    let inputs = [
+        "let one = 1; let two = 2; one + two;",
+        r#"
+            let newAdder = fn(a) { fn(b) { a + b } };
+            let addTwo = newAdder(2);
+            addTwo(3);
+        "#,
+    ];
+
+    for input in inputs {
+        let program = test_setup!(input);
+        let mut constants = Vec::new();
+        let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        comp.compile(program).unwrap();
+        let instructions = comp.bytecode().instructions;
+
+        let output = test_utils::compile(input);
+        assert_eq!(output.instructions, instructions);
+        assert_eq!(output.constants.len(), constants.len());
+        assert_eq!(
+            test_utils::disasm(input),
+            code::instruction_to_string(&instructions)
+        );
+
+        let program = test_setup!(input);
+        let mut constants = Vec::new();
+        let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        comp.compile(program).unwrap();
+        let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+        let mut vm = VM::new(comp, &mut globals);
+        vm.run().unwrap();
+
+        assert_eq!(test_utils::run(input), vm.last_popped_stack_elem());
+    }
+}
+
+#[test]
+fn test_empty_programs() {
+    for input in ["", "   ", "// just a comment", "\n\t\n"] {
+        assert_eq!(test_utils::run(input), NULL);
+    }
+}
+
+#[test]
+fn test_group_by() {
+    match test_utils::run("group_by([1, 2, 3, 4], fn(x) { x % 2 })") {
+        ObjectType::HashObj(hash) => {
+            let even = ObjectType::IntegerObj(0).hash().unwrap();
+            let odd = ObjectType::IntegerObj(1).hash().unwrap();
+
+            assert_eq!(hash.len(), 2);
+            test_array_object(vec![2.0, 4.0], &hash[&even].value);
+            test_array_object(vec![1.0, 3.0], &hash[&odd].value);
+        }
+        obj => panic!("expected a hash object, got: {:?}", obj),
+    }
+
+    let errors = [
+        (
+            "group_by(1, fn(x) { x })",
+            "argument to `group_by` must be ARRAY, got INTEGER",
+        ),
+        (
+            "group_by([1], 1)",
+            "second argument to `group_by` must be FUNCTION, got INTEGER",
+        ),
+        (
+            "group_by([1], fn(x) { [x] })",
+            "unusable as a hash key: ARRAY",
+        ),
+        (
+            "group_by([1], fn(x, y) { x })",
+            "wrong number of arguments: want=2; got=1",
+        ),
+    ];
+
+    for (input, expected) in errors {
+        assert_eq!(
+            test_utils::run(input),
+            ObjectType::ErrorObj(expected.to_string())
+        );
+    }
+
+    // a failed callback shouldn't leave the stack in a bad state
+    test_integer_object(
+        7.0,
+        &test_utils::run("[group_by([1], fn(x, y) { x }), 7][1]"),
+    );
+}
+
+#[test]
+fn test_min_and_max() {
+    run_vm_tests(vec![
+        vm_test_case!("min(3, 7)", 3.0),
+        vm_test_case!("max(3, 7)", 7.0),
+        vm_test_case!("min(7, 3)", 3.0),
+        vm_test_case!("max(-1, -2)", -1.0),
+        vm_test_case!("min(1 / 2, 1)", ObjectType::FloatObj(0.5)),
+        vm_test_case!("max(1 / 2, 1)", 1.0),
+        vm_test_case!("min([1, 2])", 1.0),
+        vm_test_case!("max([1, 2])", 2.0),
+        vm_test_case!("min([4, -2, 9, 3 / 2])", -2.0),
+        vm_test_case!("max([4, -2, 9, 19 / 2])", ObjectType::FloatObj(9.5)),
+        vm_test_case!("min([5])", 5.0),
+        vm_test_case!(r#"max(["b", "c", "a"])"#, "c"),
+        vm_test_case!("max(min(10, 4), 2) + 1", 5.0),
+        vm_test_case!(
+            "min([])",
+            ObjectType::ErrorObj("argument to `min` must not be empty".to_string())
+        ),
+        vm_test_case!(
+            "max(1)",
+            ObjectType::ErrorObj("argument to `max` must be ARRAY, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "min([1, 2], 3)",
+            ObjectType::ErrorObj(
+                "arguments to `min` must be INTEGER or FLOAT, got ARRAY and INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"max([1, "a"])"#,
+            ObjectType::ErrorObj(
+                "elements for `max` must be comparable, got INTEGER and STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "min(1, 2, 3)",
+            ObjectType::ErrorObj("wrong number of arguments. got=3, want=1 or 2".to_string())
+        ),
+    ]);
+    // the array form gives back the element, not the array
+    assert_eq!(test_utils::run("min([1, 2])"), ObjectType::IntegerObj(1));
+    assert_eq!(test_utils::run("max(3, 7)"), ObjectType::IntegerObj(7));
+}
+
+#[test]
+fn test_clamp() {
+    run_vm_tests(vec![
+        vm_test_case!("clamp(5, 0, 10)", 5.0),
+        vm_test_case!("clamp(-3, 0, 10)", 0.0),
+        vm_test_case!("clamp(15, 0, 10)", 10.0),
+        vm_test_case!("clamp(10, 0, 10)", 10.0),
+        vm_test_case!("clamp(4, 4, 4)", 4.0),
+        vm_test_case!("clamp(5 / 2, 0, 2)", 2.0),
+        vm_test_case!("clamp(5 / 2, 0, 10)", ObjectType::FloatObj(2.5)),
+        vm_test_case!("clamp(0, 1 / 2, 1)", ObjectType::FloatObj(0.5)),
+        vm_test_case!(
+            "clamp(5, 10, 0)",
+            ObjectType::ErrorObj(
+                "lower bound for `clamp` can't be above the upper bound, got 10 and 0".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"clamp("5", 0, 10)"#,
+            ObjectType::ErrorObj(
+                "arguments to `clamp` must be INTEGER or FLOAT, got STRING, INTEGER and INTEGER"
+                    .to_string()
+            )
+        ),
+        vm_test_case!(
+            "clamp(5, 0)",
+            ObjectType::ErrorObj("wrong number of arguments. got=2, want=3".to_string())
+        ),
+    ]);
+    assert_eq!(
+        test_utils::run("clamp(-3, 0, 10)"),
+        ObjectType::IntegerObj(0)
+    );
+}
+
+#[test]
+fn test_gcd_and_lcm() {
+    run_vm_tests(vec![
+        vm_test_case!("gcd(12, 18)", ObjectType::IntegerObj(6)),
+        vm_test_case!("gcd(18, 12)", ObjectType::IntegerObj(6)),
+        vm_test_case!("gcd(0, 5)", ObjectType::IntegerObj(5)),
+        vm_test_case!("gcd(5, 0)", ObjectType::IntegerObj(5)),
+        vm_test_case!("gcd(0, 0)", ObjectType::IntegerObj(0)),
+        vm_test_case!("gcd(7, 13)", ObjectType::IntegerObj(1)),
+        vm_test_case!("gcd(-12, 18)", ObjectType::IntegerObj(6)),
+        vm_test_case!("lcm(4, 6)", ObjectType::IntegerObj(12)),
+        vm_test_case!("lcm(3, 5)", ObjectType::IntegerObj(15)),
+        vm_test_case!("lcm(0, 5)", ObjectType::IntegerObj(0)),
+        vm_test_case!("lcm(-4, 6)", ObjectType::IntegerObj(12)),
+        vm_test_case!(
+            "gcd(-9223372036854775807 - 1, 0)",
+            ObjectType::ErrorObj("result of `gcd` doesn't fit in an INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "gcd(-9223372036854775807 - 1, 6)",
+            ObjectType::IntegerObj(2)
+        ),
+        vm_test_case!(
+            "lcm(9223372036854775807, 2)",
+            ObjectType::ErrorObj("result of `lcm` doesn't fit in an INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "lcm(-9223372036854775807 - 1, 1)",
+            ObjectType::ErrorObj("result of `lcm` doesn't fit in an INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "gcd(5 / 2, 2)",
+            ObjectType::ErrorObj(
+                "arguments to `gcd` must be INTEGER, got FLOAT and INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"lcm(4, "6")"#,
+            ObjectType::ErrorObj(
+                "arguments to `lcm` must be INTEGER, got INTEGER and STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "gcd(4)",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=2".to_string())
+        ),
+    ]);
+    assert_eq!(test_utils::run("gcd(12, 18)"), ObjectType::IntegerObj(6));
+    assert_eq!(test_utils::run("lcm(-4, -6)"), ObjectType::IntegerObj(12));
+}
+
+#[test]
+fn test_pow_exp_and_log() {
+    run_vm_tests(vec![
+        vm_test_case!("pow(2, 10)", ObjectType::IntegerObj(1024)),
+        vm_test_case!("pow(-3, 3)", ObjectType::IntegerObj(-27)),
+        vm_test_case!("pow(5, 0)", ObjectType::IntegerObj(1)),
+        vm_test_case!("pow(2, -1)", ObjectType::FloatObj(0.5)),
+        vm_test_case!("pow(9, 1 / 2)", ObjectType::FloatObj(3.0)),
+        vm_test_case!("pow(5 / 2, 2)", ObjectType::FloatObj(6.25)),
+        vm_test_case!("exp(0)", ObjectType::FloatObj(1.0)),
+        vm_test_case!("log(1)", ObjectType::FloatObj(0.0)),
+        vm_test_case!("log(8, 2)", ObjectType::FloatObj(3.0)),
+        vm_test_case!(
+            "log(-1)",
+            ObjectType::ErrorObj("can't take the log of -1".to_string())
+        ),
+        vm_test_case!(
+            "log(0)",
+            ObjectType::ErrorObj("can't take the log of 0".to_string())
+        ),
+        vm_test_case!(
+            "log(8, 1)",
+            ObjectType::ErrorObj("base for `log` must be positive and not 1, got 1".to_string())
+        ),
+        vm_test_case!(
+            r#"log("e")"#,
+            ObjectType::ErrorObj(
+                "arguments to `log` must be INTEGER or FLOAT, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "pow(2, true)",
+            ObjectType::ErrorObj(
+                "arguments to `pow` must be INTEGER or FLOAT, got INTEGER and BOOLEAN".to_string()
+            )
+        ),
+        vm_test_case!(
+            "exp([])",
+            ObjectType::ErrorObj(
+                "argument to `exp` must be INTEGER or FLOAT, got ARRAY".to_string()
+            )
+        ),
+    ]);
+
+    assert_eq!(test_utils::run("pow(2, 10)"), ObjectType::IntegerObj(1024));
+    test_approx("log(exp(1))", 1.0);
+    test_approx("log(1000, 10)", 3.0);
+}
+
+#[test]
+fn test_trigonometry() {
+    run_vm_tests(vec![
+        vm_test_case!("sin(0)", ObjectType::FloatObj(0.0)),
+        vm_test_case!("cos(0)", ObjectType::FloatObj(1.0)),
+        vm_test_case!("tan(0)", ObjectType::FloatObj(0.0)),
+        vm_test_case!(
+            r#"sin("0")"#,
+            ObjectType::ErrorObj(
+                "argument to `sin` must be INTEGER or FLOAT, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "pi(1)",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=0".to_string())
+        ),
+    ]);
+
+    test_approx("pi()", std::f64::consts::PI);
+    test_approx("e()", std::f64::consts::E);
+    test_approx("sin(pi() / 2)", 1.0);
+    test_approx("cos(pi())", -1.0);
+    test_approx("tan(pi() / 4)", 1.0);
+    test_approx("sin(1)", 0.8414709848078965);
+    test_approx("log(e())", 1.0);
+}
+
+#[test]
+fn test_approx_eq() {
+    run_vm_tests(vec![
+        vm_test_case!("0.1 + 0.2", Approx(0.3)),
+        vm_test_case!("0.1 + 0.2 == 0.3", false),
+        vm_test_case!("approx_eq(0.1 + 0.2, 0.3, 0.0001)", true),
+        vm_test_case!("approx_eq(0.1, 0.2, 0.0001)", false),
+        vm_test_case!("approx_eq(1, 2, 1)", true),
+        vm_test_case!("approx_eq(2, 2, 0)", true),
+        vm_test_case!(
+            "approx_eq(1, 2, -1)",
+            ObjectType::ErrorObj("epsilon for `approx_eq` can't be negative, got -1".to_string())
+        ),
+        vm_test_case!(
+            r#"approx_eq(1, "1", 0)"#,
+            ObjectType::ErrorObj(
+                "arguments to `approx_eq` must be INTEGER or FLOAT, got INTEGER, STRING and INTEGER"
+                    .to_string()
+            )
+        ),
+        vm_test_case!(
+            "approx_eq(1, 1)",
+            ObjectType::ErrorObj("wrong number of arguments. got=2, want=3".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_trunc_and_fract() {
+    run_vm_tests(vec![
+        vm_test_case!("trunc(3.7)", ObjectType::FloatObj(3.0)),
+        vm_test_case!("trunc(-3.7)", ObjectType::FloatObj(-3.0)),
+        vm_test_case!("trunc(4)", ObjectType::FloatObj(4.0)),
+        vm_test_case!("fract(3.25)", Approx(0.25)),
+        vm_test_case!("fract(-3.25)", Approx(-0.25)),
+        vm_test_case!("fract(4)", ObjectType::FloatObj(0.0)),
+        vm_test_case!(
+            "trunc([1])",
+            ObjectType::ErrorObj(
+                "argument to `trunc` must be INTEGER or FLOAT, got ARRAY".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"fract("1")"#,
+            ObjectType::ErrorObj(
+                "argument to `fract` must be INTEGER or FLOAT, got STRING".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_delete() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"let h = {"a": 1, "b": 2}; len(h)"#, 2.0),
+        vm_test_case!(r#"let h = {"a": 1, "b": 2}; len(delete(h, "a"))"#, 1.0),
+        vm_test_case!(
+            r#"let h = {"a": 1, "b": 2}; keys(delete(h, "a"))"#,
+            vec!["b"]
+        ),
+        vm_test_case!(r#"let h = {"a": 1, "b": 2}; delete(h, "a")["a"]"#, NULL),
+        // the original is left alone
+        vm_test_case!(r#"let h = {"a": 1}; delete(h, "a"); h["a"]"#, 1.0),
+        vm_test_case!(r#"let h = {"a": 1}; delete(h, "b") == h"#, true),
+        vm_test_case!("len(delete({1: true}, 1))", 0.0),
+        vm_test_case!(
+            "delete([1], 0)",
+            ObjectType::ErrorObj("argument to `delete` must be HASH, got ARRAY".to_string())
+        ),
+        vm_test_case!(
+            "delete({}, [1])",
+            ObjectType::ErrorObj("unusable as a hash key: ARRAY".to_string())
+        ),
+        vm_test_case!(
+            "delete({})",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=2".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_set() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"let h = {"a": 1}; set(h, "b", 2)["b"]"#, 2.0),
+        vm_test_case!(r#"let h = {"a": 1}; len(set(h, "b", 2))"#, 2.0),
+        vm_test_case!(r#"let h = {"a": 1}; set(h, "a", 3)["a"]"#, 3.0),
+        vm_test_case!(r#"let h = {"a": 1}; len(set(h, "a", 3))"#, 1.0),
+        // the original is left alone
+        vm_test_case!(r#"let h = {"a": 1}; set(h, "a", 3); h["a"]"#, 1.0),
+        vm_test_case!(r#"let h = {"a": 1}; set(h, "b", 2); len(h)"#, 1.0),
+        vm_test_case!(r#"set({}, true, "yes") == {true: "yes"}"#, true),
+        vm_test_case!(
+            "set({}, [1], 1)",
+            ObjectType::ErrorObj("unusable as a hash key: ARRAY".to_string())
+        ),
+        vm_test_case!(
+            "set([], 0, 1)",
+            ObjectType::ErrorObj("argument to `set` must be HASH, got ARRAY".to_string())
+        ),
+        vm_test_case!(
+            "set({}, 1)",
+            ObjectType::ErrorObj("wrong number of arguments. got=2, want=3".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_max_by_and_min_by() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "max_by([[1], [1, 2], [1, 2, 3]], fn(a) { len(a) })",
+            vec![1.0, 2.0, 3.0]
+        ),
+        vm_test_case!(
+            "min_by([[1, 2], [1], [1, 2, 3]], fn(a) { len(a) })",
+            vec![1.0]
+        ),
+        vm_test_case!("max_by([3, -7, 5], fn(x) { -x })", -7.0),
+        vm_test_case!("min_by([\"b\", \"a\", \"c\"], fn(s) { s })", "a"),
+        // ties go to the first element
+        vm_test_case!("max_by([[1], [2]], fn(a) { len(a) })", vec![1.0]),
+    ]);
+
+    let errors = [
+        (
+            "max_by([], fn(x) { x })",
+            "argument to `max_by` must not be empty",
+        ),
+        (
+            "min_by([1], 2)",
+            "second argument to `min_by` must be FUNCTION, got INTEGER",
+        ),
+        (
+            "max_by([1, 2], fn(x) { if (x == 1) { 1 } else { \"two\" } })",
+            "keys for `max_by` must be comparable, got INTEGER and STRING",
+        ),
+    ];
+
+    for (input, expected) in errors {
+        assert_eq!(
+            test_utils::run(input),
+            ObjectType::ErrorObj(expected.to_string())
+        );
+    }
+}
+
+#[test]
+fn test_divmod_and_destructuring() {
+    run_vm_tests(vec![
+        vm_test_case!("divmod(17, 5)", vec![3.0, 2.0]),
+        vm_test_case!("divmod(-7, 2)", vec![-4.0, 1.0]),
+        vm_test_case!("let [q, r] = divmod(17, 5); q", 3.0),
+        vm_test_case!("let [q, r] = divmod(17, 5); r", 2.0),
+        vm_test_case!(
+            "let f = fn() { let [a, b, c] = [1, 2, 3]; a + b * c }; f()",
+            7.0
+        ),
+    ]);
+
+    assert_eq!(
+        test_utils::run("divmod(1, 0)"),
+        ObjectType::ErrorObj("division by zero in `divmod`".to_string())
+    );
+    assert_eq!(
+        test_utils::run(r#"divmod("a", 2)"#),
+        ObjectType::ErrorObj(
+            "arguments to `divmod` must be INTEGER or FLOAT, got STRING and INTEGER".to_string()
+        )
+    );
+
+    for (input, expected) in [
+        (
+            "let [a, b] = [1];",
+            "cannot destructure ARRAY of length 1 into 2 names",
+        ),
+        ("let [a] = 1;", "cannot destructure INTEGER, expected ARRAY"),
+    ] {
+        assert_eq!(vm_error(input), expected);
+    }
+}
+
+#[test]
+fn test_string_repetition() {
+    run_vm_tests(vec![
+        vm_test_case!("\"ab\" * 3", "ababab"),
+        vm_test_case!("3 * \"x\"", "xxx"),
+        vm_test_case!("\"a\" * 0", ""),
+        vm_test_case!("2 * 3", 6.0),
+    ]);
+
+    assert_eq!(
+        vm_error("\"a\" * -1"),
+        "cannot repeat a string a negative number of times: -1"
+    );
+    assert_eq!(
+        vm_error("\"a\" * (3 / 2)"),
+        "cannot repeat a string a non-whole number of times: 1.5"
+    );
+    assert_eq!(vm_error("\"a\" - 1"), "type mismatch: STRING OpSub INTEGER");
+}
+
+#[test]
+fn test_array_concatenation() {
+    run_vm_tests(vec![
+        vm_test_case!("[1, 2] + [3, 4]", vec![1.0, 2.0, 3.0, 4.0]),
+        vm_test_case!("[] + [1]", vec![1.0]),
+        vm_test_case!("let a = [1]; let b = a + [2]; a", vec![1.0]),
+    ]);
+
+    assert_eq!(vm_error("[1] + 2"), "type mismatch: ARRAY OpAdd INTEGER");
+    assert_eq!(
+        vm_error("[1] - [2]"),
+        "unexpected operation: ARRAY OpSub ARRAY"
+    );
+}
+
+#[test]
+fn test_hash_merge() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "{1: 1} + {2: 2}",
+            HashMap::from([
+                (ObjectType::IntegerObj(1).hash().unwrap(), 1.0),
+                (ObjectType::IntegerObj(2).hash().unwrap(), 2.0),
+            ])
+        ),
+        vm_test_case!(
+            "{1: 1} + {1: 9}",
+            HashMap::from([(ObjectType::IntegerObj(1).hash().unwrap(), 9.0)])
+        ),
+        vm_test_case!("({1: 1} + {1: 9})[1]", 9.0),
+        vm_test_case!("let a = {1: 1}; let b = a + {2: 2}; a[2]", NULL),
+    ]);
+
+    assert_eq!(vm_error("{1: 1} + [1]"), "type mismatch: HASH OpAdd ARRAY");
+}
+
+#[test]
+fn test_lines() {
+    run_vm_tests(vec![
+        vm_test_case!("lines(\"a\nb\nc\")", vec!["a", "b", "c"]),
+        vm_test_case!("lines(\"\")", Vec::<&str>::new()),
+        vm_test_case!("lines(\"a\r\nb\r\n\")", vec!["a", "b"]),
+        vm_test_case!("lines(\"a\n\nb\")", vec!["a", "", "b"]),
+        vm_test_case!(
+            "lines(1)",
+            ObjectType::ErrorObj("argument to `lines` must be STRING, got INTEGER".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_split() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"split("a,b,c", ",")"#, vec!["a", "b", "c"]),
+        vm_test_case!(r#"split("a, b", ", ")"#, vec!["a", "b"]),
+        vm_test_case!(r#"split("a,,b,", ",")"#, vec!["a", "", "b", ""]),
+        vm_test_case!(r#"split("abc", ";")"#, vec!["abc"]),
+        vm_test_case!(r#"split("héllo", "")"#, vec!["h", "é", "l", "l", "o"]),
+        vm_test_case!(r#"split("", ",")"#, Vec::<&str>::new()),
+        vm_test_case!(r#"split("", "")"#, Vec::<&str>::new()),
+        vm_test_case!(
+            r#"split(1, ",")"#,
+            ObjectType::ErrorObj(
+                "arguments to `split` must be STRING, got INTEGER and STRING".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_split_n() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"split_n("a,b,c", ",", 2)"#, vec!["a", "b,c"]),
+        vm_test_case!(r#"split_n("a,b,c", ",", 1)"#, vec!["a,b,c"]),
+        vm_test_case!(r#"split_n("a,b,c", ",", 3)"#, vec!["a", "b", "c"]),
+        vm_test_case!(r#"split_n("a,b,c", ",", 10)"#, vec!["a", "b", "c"]),
+        vm_test_case!(r#"split_n("a, b, c", ", ", 2)"#, vec!["a", "b, c"]),
+        vm_test_case!(r#"split_n("héllo", "", 3)"#, vec!["h", "é", "llo"]),
+        vm_test_case!(r#"split_n("ab", "", 5)"#, vec!["a", "b"]),
+        vm_test_case!(r#"split_n("", ",", 2)"#, Vec::<&str>::new()),
+        vm_test_case!(
+            r#"split_n("a,b", ",", 0)"#,
+            ObjectType::ErrorObj("third argument to `split_n` must be positive, got 0".to_string())
+        ),
+        vm_test_case!(
+            r#"split_n("a,b", ",", "2")"#,
+            ObjectType::ErrorObj(
+                "third argument to `split_n` must be INTEGER, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"split_n("a,b", 1, 2)"#,
+            ObjectType::ErrorObj(
+                "arguments to `split_n` must be STRING, got STRING and INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_count_matches() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"count_matches("aaaa", "aa")"#, 2.0),
+        vm_test_case!(r#"count_matches("aaa", "aa")"#, 1.0),
+        vm_test_case!(r#"count_matches("a,b,c", ",")"#, 2.0),
+        vm_test_case!(r#"count_matches("abc", "d")"#, 0.0),
+        vm_test_case!(r#"count_matches("", "a")"#, 0.0),
+        vm_test_case!(
+            r#"count_matches("abc", "")"#,
+            ObjectType::ErrorObj("second argument to `count_matches` can't be empty".to_string())
+        ),
+        vm_test_case!(
+            r#"count_matches(["a"], "a")"#,
+            ObjectType::ErrorObj(
+                "arguments to `count_matches` must be STRING, got ARRAY and STRING".to_string()
+            )
+        ),
+    ]);
+    assert_eq!(
+        test_utils::run(r#"count_matches("aaaa", "aa")"#),
+        ObjectType::IntegerObj(2)
+    );
+}
+
+#[test]
+fn test_join() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"join(["a", "b", "c"], "-")"#, "a-b-c"),
+        vm_test_case!(r#"join(["a", "b", "c"], ", ")"#, "a, b, c"),
+        vm_test_case!(r#"join(["a", "b"], "")"#, "ab"),
+        vm_test_case!(r#"join(["a"], "-")"#, "a"),
+        vm_test_case!(r#"join([], "-")"#, ""),
+        vm_test_case!(r#"join(split("a,b,c", ","), " and ")"#, "a and b and c"),
+        vm_test_case!(
+            r#"join(["a", 1], "-")"#,
+            ObjectType::ErrorObj("elements for `join` must be STRING, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            r#"join("a", "-")"#,
+            ObjectType::ErrorObj(
+                "arguments to `join` must be ARRAY and STRING, got STRING and STRING".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_string_matching() {
+    run_vm_tests(vec![
+        vm_test_case!("starts_with(\"monkey\", \"mon\")", true),
+        vm_test_case!("starts_with(\"monkey\", \"key\")", false),
+        vm_test_case!("starts_with(\"monkey\", \"\")", true),
+        vm_test_case!("ends_with(\"monkey\", \"key\")", true),
+        vm_test_case!("ends_with(\"monkey\", \"mon\")", false),
+        vm_test_case!("ends_with(\"monkey\", \"\")", true),
+        vm_test_case!("index_of(\"monkey\", \"key\")", 3.0),
+        vm_test_case!("index_of(\"monkey\", \"\")", 0.0),
+        vm_test_case!("index_of(\"monkey\", \"ape\")", -1.0),
+        vm_test_case!("index_of(\"héllo\", \"l\")", 2.0),
+        vm_test_case!(
+            "starts_with(\"monkey\", 1)",
+            ObjectType::ErrorObj(
+                "arguments to `starts_with` must be STRING, got STRING and INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_padding() {
+    run_vm_tests(vec![
+        vm_test_case!("pad_left(\"5\", 3, \"0\")", "005"),
+        vm_test_case!("pad_right(\"hi\", 4, \".\")", "hi.."),
+        vm_test_case!("pad_left(\"monkey\", 3, \" \")", "monkey"),
+        vm_test_case!("pad_right(\"monkey\", 6, \" \")", "monkey"),
+        vm_test_case!(
+            "pad_left(\"5\", 3, \"00\")",
+            ObjectType::ErrorObj(
+                "fill for `pad_left` must be a single character, got \"00\"".to_string()
+            )
+        ),
+        vm_test_case!(
+            "pad_right(\"5\", \"3\", \"0\")",
+            ObjectType::ErrorObj(
+                "arguments to `pad_right` must be STRING, INTEGER, STRING, got STRING, STRING, STRING"
+                    .to_string()
+            )
+        ),
+        vm_test_case!(
+            "pad_left(\"a\", 9223372036854775807, \" \")",
+            ObjectType::ErrorObj(
+                "width for `pad_left` must be at most 1000000, got 9223372036854775807".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_substr() {
+    run_vm_tests(vec![
+        vm_test_case!("substr(\"hello\", 1, 3)", "ell"),
+        vm_test_case!("substr(\"hello\", 3)", "lo"),
+        vm_test_case!("substr(\"hello\", 3, 10)", "lo"),
+        vm_test_case!("substr(\"hello\", 10)", ""),
+        vm_test_case!("substr(\"hello\", -1, 2)", ""),
+        vm_test_case!("substr(\"héllo\", 1, 2)", "él"),
+        vm_test_case!(
+            "substr(\"hello\", 1, \"3\")",
+            ObjectType::ErrorObj("length for `substr` must be INTEGER, got STRING".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_output_sink() {
+    let program = test_setup!("puts(\"hi\", 1); puts(true);");
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut captured = Vec::new();
+
+    let mut vm = VM::new(comp, &mut globals).with_output(|line| captured.push(line.to_string()));
+    vm.run().unwrap();
+    drop(vm);
+
+    assert_eq!(captured, vec!["hi", "1", "true"]);
+}
+
+#[test]
+fn test_snapshot_and_restore() {
+    let program = test_setup!(
+        "let a = 1; let b = [a]; let bump = fn() { a = a + 1; b = push(b, a); a }; bump"
+    );
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut vm = VM::new(comp, &mut globals);
+    vm.run().unwrap();
+    let bump = vm.last_popped_stack_elem();
+
+    let state = vm.snapshot();
+    assert_eq!(
+        vm.call_function(bump.clone(), vec![]),
+        ObjectType::IntegerObj(2)
+    );
+    assert_eq!(
+        vm.call_function(bump.clone(), vec![]),
+        ObjectType::IntegerObj(3)
+    );
+
+    vm.restore(state.clone());
+    assert_eq!(vm.last_popped_stack_elem(), bump);
+    assert_eq!(
+        vm.call_function(bump.clone(), vec![]),
+        ObjectType::IntegerObj(2)
+    );
+
+    // a state can be restored more than once
+    vm.restore(state);
+    drop(vm);
+
+    assert_eq!(globals[0], ObjectType::IntegerObj(1));
+    assert_eq!(
+        globals[1],
+        ObjectType::ArrayObj(vec![ObjectType::IntegerObj(1)])
+    );
+}
+
+#[test]
+fn test_raw_strings() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"len(r"\n")"#, 2.0),
+        vm_test_case!(r###"r#"say "hi""#"###, "say \"hi\""),
+        vm_test_case!(r#"let r = 1; r + 1"#, 2.0),
+    ]);
+}
+
+#[test]
+fn test_fmt_num() {
+    run_vm_tests(vec![
+        vm_test_case!("fmt_num(3.14159, 2)", "3.14"),
+        vm_test_case!("fmt_num(2 / 3, 3)", "0.667"),
+        vm_test_case!("fmt_num(3.7, 0)", "4"),
+        vm_test_case!("fmt_num(5, 2)", "5.00"),
+        vm_test_case!("fmt_num(-1.75, 1)", "-1.8"),
+        vm_test_case!(
+            "fmt_num(1, -1)",
+            ObjectType::ErrorObj(
+                "decimals for `fmt_num` must be a whole number >= 0, got -1".to_string()
+            )
+        ),
+        vm_test_case!(
+            "fmt_num(1, 70000)",
+            ObjectType::ErrorObj(
+                "decimals for `fmt_num` must be at most 100, got 70000".to_string()
+            )
+        ),
+        vm_test_case!(
+            "fmt_num(\"1\", 2)",
+            ObjectType::ErrorObj(
+                "arguments to `fmt_num` must be INTEGER or FLOAT, got STRING and INTEGER"
+                    .to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_nested_returns() {
+    run_vm_tests(vec![
+        vm_test_case!("let f = fn(x) { if (x) { return 1; } 2 }; f(true)", 1.0),
+        vm_test_case!("let f = fn(x) { if (x) { return 1; } 2 }; f(false)", 2.0),
+        vm_test_case!(
+            "let f = fn(x) { if (x > 0) { if (x > 5) { return 10; } return 5; } 0 }; [f(9), f(3), f(0)]",
+            vec![10.0, 5.0, 0.0]
+        ),
+        vm_test_case!(
+            "let f = fn() { let i = 0; loop { i++; if (i == 3) { return i * 10; }; } 99 }; f()",
+            30.0
+        ),
+        vm_test_case!(
+            "let f = fn() { let i = 0; loop { i++; if (i > 1) { if (i == 4) { return i; } }; } 99 }; f()",
+            4.0
+        ),
+        vm_test_case!(
+            "let f = fn() { loop { loop { return 7; } } 99 }; f() + 1",
+            8.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_loops_with_several_breaks() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "let i = 0; loop { if (i == 3) { break; }; if (i > 10) { break; }; i++; } i",
+            3.0
+        ),
+        vm_test_case!(
+            r#"
+                let i = 0;
+                let n = 0;
+                loop {
+                    if (i == 3) { break; };
+                    let j = 0;
+                    loop {
+                        if (j == 2) { break; };
+                        n++;
+                        j++;
+                    }
+                    i++;
+                }
+                n
+            "#,
+            6.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_run_parsed_program() {
+    let program = test_setup!("let doubled = input * 2; doubled + 1");
+
+    for (input, expected) in [(3, 7), (3, 7), (10, 21)] {
+        let mut symbol_table = SymbolTable::new();
+        let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+        let slot = symbol_table.define("input".to_string()).index;
+        globals[slot] = ObjectType::IntegerObj(input);
+
+        let result = run_program(&program, symbol_table, &mut globals).unwrap();
+        assert_eq!(result, ObjectType::IntegerObj(expected));
+    }
+}
+
+#[test]
+fn test_cross_type_equality() {
+    run_vm_tests(vec![
+        vm_test_case!("1 == true", false),
+        vm_test_case!("0 == false", false),
+        vm_test_case!("\"1\" == 1", false),
+        vm_test_case!("1 != true", true),
+        vm_test_case!("[1] == 1", false),
+    ]);
+}
+
+#[test]
+fn test_vm_from_owned_bytecode() {
+    let test_utils::CompilerOutput {
+        instructions,
+        constants,
+        ..
+    } = test_utils::compile("let add = fn(a, b) { a + b }; add(2, [3, 4][1])");
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut vm = VM::from_owned(instructions, constants, &mut globals);
+    vm.run().unwrap();
+
+    assert_eq!(vm.last_popped_stack_elem(), ObjectType::IntegerObj(6));
+}
+
+#[test]
+fn test_literal_folding_gives_the_same_results() {
+    let inputs = [
+        "[1, 2, 3]",
+        "[[1, \"a\"], [true]][0][1]",
+        "{1: 2, \"b\": [3]}[\"b\"]",
+        "let x = 4; [1, x, [5]]",
+        "len([]) + len([[], {}])",
+    ];
+
+    for input in inputs {
+        let results = [true, false].map(|fold| {
+            let program = test_setup!(input);
+            let mut constants = Vec::new();
+            let mut comp = Compiler::new(&mut constants, SymbolTable::new())
+                .unwrap()
+                .with_literal_folding(fold);
+            comp.compile(program).unwrap();
+
+            let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+            let mut vm = VM::new(comp, &mut globals);
+            vm.run().unwrap();
+            vm.last_popped_stack_elem()
+        });
+
+        assert_eq!(results[0], results[1], "{input}");
+    }
+}
+
+#[test]
+fn test_tap() {
+    run_vm_tests(vec![
+        vm_test_case!("tap(5)", 5.0),
+        vm_test_case!("tap(2) * 3", 6.0),
+        vm_test_case!("tap([1, 2])", vec![1.0, 2.0]),
+    ]);
+
+    let program = test_setup!("let f = fn(n) { tap(n) * 2 }; f(5) + f(1)");
+    let mut constants = Vec::new();
+    let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+    comp.compile(program).unwrap();
+
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut captured = Vec::new();
+
+    let mut vm = VM::new(comp, &mut globals).with_output(|line| captured.push(line.to_string()));
+    vm.run().unwrap();
+    assert_eq!(vm.last_popped_stack_elem(), ObjectType::IntegerObj(12));
+    drop(vm);
+
+    assert_eq!(captured, vec!["5", "1"]);
+}
+
+#[test]
+fn test_repeated_names_resolve() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            r#"
+                let total = 1;
+                let add = fn(total, step) {
+                    let next = fn(step) { total + step };
+                    next(step) + total
+                };
+                add(total + 1, total) + total
+            "#,
+            6.0
+        ),
+        vm_test_case!("let a = 1; let b = fn() { let a = 2; a }; b() + a", 3.0),
+    ]);
+}
+
+#[test]
+fn test_while_let() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            r#"
+                let i = 0;
+                let next = fn() { if (i < 3) { i = i + 1; i } };
+                let total = 0;
+                let seen = 0;
+                while (let x = next()) { total = total + x; seen++; }
+                [total, seen, i]
+            "#,
+            vec![6.0, 3.0, 3.0]
+        ),
+        vm_test_case!(
+            r#"
+                let sum = fn(items) {
+                    let i = 0;
+                    let total = 0;
+                    while (let item = items[i]) { total = total + item; i++; }
+                    total
+                };
+                sum([4, 5, 6])
+            "#,
+            15.0
+        ),
+        vm_test_case!(
+            "let n = 0; while (let x = n < 10) { if (n == 4) { break; }; n++; } n",
+            4.0
+        ),
+        vm_test_case!(
+            "let ran = false; while (let x = false) { ran = true; } ran",
+            false
+        ),
+    ]);
+}
+
+#[test]
+fn test_incremental_compilation() {
+    let lines = [
+        "let add = fn(a, b) { a + b };",
+        "let n = add(2, 3);",
+        "add(n, 1)",
+    ];
+
+    let mut constants = Vec::new();
+    let mut instructions = Vec::new();
+    let mut symbol_table = SymbolTable::new();
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+
+    for line in lines {
+        let line_start = instructions.len();
+        let mut comp = Compiler::new(&mut constants, symbol_table)
+            .unwrap()
+            .with_instructions(instructions);
+        comp.compile(test_setup!(line)).unwrap();
+
+        instructions = comp.bytecode().instructions;
+        symbol_table = comp.symbol_table.clone();
+
+        // run just this line against what the earlier lines left in globals
+        let mut vm = VM::new(comp, &mut globals).starting_at(line_start);
+        vm.run().unwrap();
+    }
+    assert_eq!(globals[1], ObjectType::IntegerObj(5));
+
+    // the combined bytecode is a program in its own right
+    let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+    let mut vm = VM::from_owned(instructions, constants, &mut globals);
+    vm.run().unwrap();
+    assert_eq!(vm.last_popped_stack_elem(), ObjectType::IntegerObj(6));
+}
+
+#[test]
+fn test_map() {
+    run_vm_tests(vec![
+        vm_test_case!("map([1, 2, 3], fn(x) { x * 2 })", vec![2.0, 4.0, 6.0]),
+        vm_test_case!("map([], fn(x) { x * 2 })", Vec::<f64>::new()),
+        vm_test_case!(r#"map(["a", "bcd"], len)"#, vec![1.0, 3.0]),
+        vm_test_case!("let n = 10; map([1, 2], fn(x) { x + n })", vec![11.0, 12.0]),
+        vm_test_case!(
+            "map([1, 2], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `map` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "map(1, fn(x) { x })",
+            ObjectType::ErrorObj("first argument to `map` must be ARRAY, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "map([1], fn(a, b) { a })",
+            ObjectType::ErrorObj("wrong number of arguments: want=2; got=1".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_flat_map() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "flat_map([1, 2], fn(x) { [x, x] })",
+            vec![1.0, 1.0, 2.0, 2.0]
+        ),
+        vm_test_case!("flat_map([1, 2, 3], fn(x) { [] })", Vec::<f64>::new()),
+        vm_test_case!("flat_map([], fn(x) { [x] })", Vec::<f64>::new()),
+        vm_test_case!(
+            "flat_map([1, 2], fn(x) { x })",
+            ObjectType::ErrorObj(
+                "function passed to `flat_map` must return ARRAY, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "flat_map([1], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `flat_map` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "flat_map(1, fn(x) { [x] })",
+            ObjectType::ErrorObj(
+                "first argument to `flat_map` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+    ]);
+
+    // only one level is flattened
+    assert_eq!(
+        test_utils::run("flat_map([1], fn(x) { [[x]] })"),
+        ObjectType::ArrayObj(vec![ObjectType::ArrayObj(vec![ObjectType::IntegerObj(1)])])
+    );
+}
+
+#[test]
+fn test_partition() {
+    let int_arrays = |arrays: [&[i64]; 2]| {
+        ObjectType::ArrayObj(
+            arrays
+                .iter()
+                .map(|array| {
+                    ObjectType::ArrayObj(array.iter().map(|&i| ObjectType::IntegerObj(i)).collect())
+                })
+                .collect(),
+        )
+    };
+
+    let tests: [(&str, [&[i64]; 2]); 4] = [
+        (
+            "partition([1, 2, 3, 4], fn(x) { x < 3 })",
+            [&[1, 2], &[3, 4]],
+        ),
+        (
+            "partition([1, 2, 3, 4], fn(x) { x % 2 == 1 })",
+            [&[1, 3], &[2, 4]],
+        ),
+        ("partition([1, 2], fn(x) { true })", [&[1, 2], &[]]),
+        (
+            "let pred = fn(x) { x > 0 }; partition([], pred)",
+            [&[], &[]],
+        ),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(test_utils::run(input), int_arrays(expected), "{input}");
+    }
+
+    run_vm_tests(vec![
+        vm_test_case!(
+            "partition([1], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `partition` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "partition(1, fn(x) { true })",
+            ObjectType::ErrorObj(
+                "first argument to `partition` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "partition([1])",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=2".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_filter() {
+    run_vm_tests(vec![
+        vm_test_case!("filter([1, 2, 3, 4], fn(x) { x > 2 })", vec![3.0, 4.0]),
+        vm_test_case!("filter([1, 2, 3, 4], fn(x) { x > 4 })", Vec::<f64>::new()),
+        vm_test_case!("filter([], fn(x) { true })", Vec::<f64>::new()),
+        vm_test_case!(
+            r#"filter(["a", "", "bc"], fn(s) { len(s) > 0 })"#,
+            vec!["a", "bc"]
+        ),
+        // anything but false and null passes, as in an if
+        vm_test_case!("filter([0, 1, 2], fn(x) { x })", vec![0.0, 1.0, 2.0]),
+        vm_test_case!(
+            "filter([1, 2, 3], fn(x) { if (x != 2) { x } })",
+            vec![1.0, 3.0]
+        ),
+        vm_test_case!(
+            "filter([1, 2], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `filter` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "filter(1, fn(x) { true })",
+            ObjectType::ErrorObj(
+                "first argument to `filter` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "filter([1])",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=2".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_take_while_and_drop_while() {
+    run_vm_tests(vec![
+        vm_test_case!("take_while([1, 2, 3, 1], fn(x) { x < 3 })", vec![1.0, 2.0]),
+        vm_test_case!("drop_while([1, 2, 3, 1], fn(x) { x < 3 })", vec![3.0, 1.0]),
+        vm_test_case!("take_while([1, 2], fn(x) { true })", vec![1.0, 2.0]),
+        vm_test_case!("drop_while([1, 2], fn(x) { true })", Vec::<f64>::new()),
+        vm_test_case!("take_while([1, 2], fn(x) { false })", Vec::<f64>::new()),
+        vm_test_case!("drop_while([1, 2], fn(x) { false })", vec![1.0, 2.0]),
+        vm_test_case!("take_while([], fn(x) { true })", Vec::<f64>::new()),
+        // the predicate isn't called past the end of the run
+        vm_test_case!(
+            "let calls = 0; drop_while([1, 2, 3, 1], fn(x) { calls = calls + 1; x < 2 }); calls",
+            2.0
+        ),
+        vm_test_case!(
+            "take_while([1], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `take_while` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "drop_while(1, fn(x) { true })",
+            ObjectType::ErrorObj(
+                "first argument to `drop_while` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_chunk_and_window() {
+    let int_arrays = |arrays: &[&[i64]]| {
+        ObjectType::ArrayObj(
+            arrays
+                .iter()
+                .map(|array| {
+                    ObjectType::ArrayObj(array.iter().map(|&i| ObjectType::IntegerObj(i)).collect())
+                })
+                .collect(),
+        )
+    };
+
+    let tests: [(&str, &[&[i64]]); 7] = [
+        ("chunk([1, 2, 3, 4, 5], 2)", &[&[1, 2], &[3, 4], &[5]]),
+        ("chunk([1, 2, 3, 4], 2)", &[&[1, 2], &[3, 4]]),
+        ("chunk([1, 2], 5)", &[&[1, 2]]),
+        ("chunk([], 2)", &[]),
+        ("window([1, 2, 3], 2)", &[&[1, 2], &[2, 3]]),
+        ("window([1, 2, 3], 3)", &[&[1, 2, 3]]),
+        ("window([1], 2)", &[]),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(test_utils::run(input), int_arrays(expected), "{input}");
+    }
+
+    run_vm_tests(vec![
+        vm_test_case!(
+            "chunk([1], 0)",
+            ObjectType::ErrorObj("second argument to `chunk` must be positive, got 0".to_string())
+        ),
+        vm_test_case!(
+            "window([1], -1)",
+            ObjectType::ErrorObj(
+                "second argument to `window` must be positive, got -1".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"window([1], "2")"#,
+            ObjectType::ErrorObj(
+                "second argument to `window` must be INTEGER, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "chunk(1, 2)",
+            ObjectType::ErrorObj(
+                "first argument to `chunk` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "chunk([1])",
+            ObjectType::ErrorObj("wrong number of arguments. got=1, want=2".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_any_and_all() {
+    run_vm_tests(vec![
+        vm_test_case!("any([1, 2, 3], fn(x) { x > 2 })", true),
+        vm_test_case!("any([1, 2, 3], fn(x) { x > 3 })", false),
+        vm_test_case!("any([], fn(x) { true })", false),
+        vm_test_case!("any([false, 0], fn(x) { x })", true),
+        vm_test_case!("all([1, 2, 3], fn(x) { x > 0 })", true),
+        vm_test_case!("all([1, 2, 3], fn(x) { x > 1 })", false),
+        vm_test_case!("let pred = fn(x) { x > 0 }; all([], pred)", true),
+        // both stop calling the predicate once the answer is known
+        vm_test_case!(
+            "let calls = 0; any([1, 2, 3], fn(x) { calls = calls + 1; x == 2 }); calls",
+            2.0
+        ),
+        vm_test_case!(
+            "let calls = 0; all([1, 2, 3], fn(x) { calls = calls + 1; x < 1 }); calls",
+            1.0
+        ),
+        vm_test_case!(
+            "all([1, 0], fn(x) { 10 / x })",
+            ObjectType::ErrorObj("division by zero".to_string())
+        ),
+        vm_test_case!(
+            "any([1], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `any` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "all(1, fn(x) { true })",
+            ObjectType::ErrorObj("first argument to `all` must be ARRAY, got INTEGER".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_type() {
+    run_vm_tests(vec![
+        vm_test_case!("type(5)", "INTEGER"),
+        vm_test_case!("type(5 / 2)", "FLOAT"),
+        vm_test_case!(r#"type("x")"#, "STRING"),
+        vm_test_case!("type([1])", "ARRAY"),
+        vm_test_case!("type({})", "HASH"),
+        vm_test_case!("type(true)", "BOOLEAN"),
+        vm_test_case!("type(first([]))", "NULL"),
+        vm_test_case!("type(fn() { 1 })", "FUNCTION"),
+        vm_test_case!("let n = 1; type(fn() { n })", "FUNCTION"),
+        vm_test_case!("type(len)", "BUILTIN"),
+        vm_test_case!(r#"assert_type([], type([1]))"#, Vec::<f64>::new()),
+    ]);
+}
+
+#[test]
+fn test_int_and_str() {
+    run_vm_tests(vec![
+        vm_test_case!("str(5)", "5"),
+        vm_test_case!("str(true)", "true"),
+        vm_test_case!("str(5 / 2)", "2.5"),
+        vm_test_case!(r#"str("x")"#, "x"),
+        vm_test_case!(r#"str(12) + "px""#, "12px"),
+        vm_test_case!(r#"int("42")"#, ObjectType::IntegerObj(42)),
+        vm_test_case!(r#"int(" -7 ")"#, ObjectType::IntegerObj(-7)),
+        vm_test_case!("int(7 / 2)", ObjectType::IntegerObj(3)),
+        vm_test_case!("int(-7 / 2)", ObjectType::IntegerObj(-3)),
+        vm_test_case!("int(4)", ObjectType::IntegerObj(4)),
+        vm_test_case!("int(str(99)) + 1", ObjectType::IntegerObj(100)),
+        vm_test_case!(
+            r#"int("abc")"#,
+            ObjectType::ErrorObj(r#"could not parse "abc" as INTEGER"#.to_string())
+        ),
+        vm_test_case!(
+            r#"int("4.5")"#,
+            ObjectType::ErrorObj(r#"could not parse "4.5" as INTEGER"#.to_string())
+        ),
+        vm_test_case!(
+            "int(true)",
+            ObjectType::ErrorObj(
+                "argument to `int` must be STRING, INTEGER or FLOAT, got BOOLEAN".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_contains() {
+    run_vm_tests(vec![
+        vm_test_case!("contains([1, 2, 3], 2)", true),
+        vm_test_case!("contains([1, 2, 3], 4)", false),
+        vm_test_case!("contains([], 1)", false),
+        vm_test_case!(r#"contains([1, "a", [2]], [2])"#, true),
+        vm_test_case!(r#"contains([1, 2], "1")"#, false),
+        vm_test_case!("contains([1, 2], 4 / 2)", true),
+        vm_test_case!("contains([0.5], 2 / 4)", true),
+        vm_test_case!("contains({1: 2}, 1)", true),
+        vm_test_case!("contains({1: 2}, 2)", false),
+        vm_test_case!(r#"contains({"a": 1, true: 2}, "a")"#, true),
+        vm_test_case!(r#"contains({"a": 1, true: 2}, true)"#, true),
+        vm_test_case!(r#"contains({"a": 1}, "b")"#, false),
+        vm_test_case!("contains({}, 1)", false),
+        vm_test_case!(r#"contains("hello", "ell")"#, true),
+        vm_test_case!(r#"contains("hello", "")"#, true),
+        vm_test_case!(r#"contains("hello", "elk")"#, false),
+        vm_test_case!(r#"contains("", "a")"#, false),
+        vm_test_case!(
+            "contains({1: 2}, [1])",
+            ObjectType::ErrorObj("unusable as a hash key: ARRAY".to_string())
+        ),
+        vm_test_case!(
+            r#"contains("hello", 1)"#,
+            ObjectType::ErrorObj(
+                "second argument to `contains` must be STRING when searching a STRING, got INTEGER"
+                    .to_string()
+            )
+        ),
+        vm_test_case!(
+            "contains(1, 1)",
+            ObjectType::ErrorObj(
+                "first argument to `contains` must be ARRAY, HASH or STRING, got INTEGER"
+                    .to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_equals_ignore_case() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"equals_ignore_case("ABC", "abc")"#, true),
+        vm_test_case!(r#"equals_ignore_case("Straße", "STRASSE")"#, false),
+        vm_test_case!(r#"equals_ignore_case("ÄÖÜ", "äöü")"#, true),
+        vm_test_case!(r#"equals_ignore_case("", "")"#, true),
+        vm_test_case!(r#"equals_ignore_case("a", "b")"#, false),
+        vm_test_case!(r#"equals_ignore_case("a", "ab")"#, false),
+        vm_test_case!(
+            r#"equals_ignore_case("a", 1)"#,
+            ObjectType::ErrorObj(
+                "arguments to `equals_ignore_case` must be STRING, got STRING and INTEGER"
+                    .to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_abs_and_signum() {
+    run_vm_tests(vec![
+        vm_test_case!("signum(-5)", ObjectType::IntegerObj(-1)),
+        vm_test_case!("signum(0)", ObjectType::IntegerObj(0)),
+        vm_test_case!("signum(3)", ObjectType::IntegerObj(1)),
+        vm_test_case!("signum(-1 / 2)", ObjectType::IntegerObj(-1)),
+        vm_test_case!("signum(1 / 2)", ObjectType::IntegerObj(1)),
+        vm_test_case!("signum(0 / 2)", ObjectType::IntegerObj(0)),
+        vm_test_case!("abs(-5)", ObjectType::IntegerObj(5)),
+        vm_test_case!("abs(5)", ObjectType::IntegerObj(5)),
+        vm_test_case!("abs(0)", ObjectType::IntegerObj(0)),
+        vm_test_case!("abs(-5 / 2)", ObjectType::FloatObj(2.5)),
+        vm_test_case!("abs(-7) * signum(-7)", ObjectType::IntegerObj(-7)),
+        vm_test_case!(
+            r#"signum("1")"#,
+            ObjectType::ErrorObj(
+                "argument to `signum` must be INTEGER or FLOAT, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "abs([1])",
+            ObjectType::ErrorObj(
+                "argument to `abs` must be INTEGER or FLOAT, got ARRAY".to_string()
+            )
+        ),
+    ]);
+    assert_eq!(test_utils::run("signum(-5)").to_sexpr(), "(int -1)");
+    assert_eq!(test_utils::run("abs(-5 / 2)").to_sexpr(), "(float 2.5)");
+}
+
+#[test]
+fn test_assert_type() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"assert_type([1], "ARRAY")"#, vec![1.0]),
+        vm_test_case!(
+            r#"assert_type(5 / 2, "FLOAT") * 2"#,
+            ObjectType::FloatObj(5.0)
+        ),
+        vm_test_case!(
+            r#"assert_type(1, "ARRAY")"#,
+            ObjectType::ErrorObj("type assertion failed: expected ARRAY, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            r#"assert_type(1, 2)"#,
+            ObjectType::ErrorObj(
+                "second argument to `assert_type` must be STRING, got INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_transform() {
+    run_vm_tests(vec![
+        vm_test_case!("transform(3, fn(x) { x * 2 })", 6.0),
+        vm_test_case!(
+            "transform([1, 2], 1)",
+            ObjectType::ErrorObj(
+                "second argument to `transform` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+    ]);
+    // the first error stops the walk
+    assert_eq!(
+        test_utils::run(r#"transform([1, ["a"]], fn(x) { x - 1 })"#),
+        ObjectType::ErrorObj("type mismatch: STRING OpSub INTEGER".to_string())
+    );
+
+    let run = |input| test_utils::run(input).to_sexpr();
+    assert_eq!(
+        run("transform([1, [2, 3]], fn(x) { x * 2 })"),
+        "(array (int 2) (array (int 4) (int 6)))"
+    );
+    assert_eq!(
+        run(r#"transform({"a": 1, "b": {"c": [2], 3: 4}}, fn(x) { x + 10 })"#),
+        r#"(hash ((string "a") (int 11)) ((string "b") (hash ((int 3) (int 14)) ((string "c") (array (int 12))))))"#
+    );
+    assert_eq!(run("transform([], fn(x) { x })"), "(array)");
+}
+
+#[test]
+fn test_reduce() {
+    run_vm_tests(vec![
+        vm_test_case!("reduce([1, 2, 3, 4], 0, fn(a, b) { a + b })", 10.0),
+        vm_test_case!("reduce([], 7, fn(a, b) { a + b })", 7.0),
+        vm_test_case!(
+            "reduce([1, 2, 3], [], fn(acc, x) { push(acc, x * x) })",
+            vec![1.0, 4.0, 9.0]
+        ),
+        vm_test_case!(
+            "reduce([1, 2], 0, fn(a) { a })",
+            ObjectType::ErrorObj(
+                "function passed to `reduce` must take 2 arguments, takes 1".to_string()
+            )
+        ),
+        vm_test_case!(
+            "reduce([], 0, fn(a, b, c) { a })",
+            ObjectType::ErrorObj(
+                "function passed to `reduce` must take 2 arguments, takes 3".to_string()
+            )
+        ),
+        vm_test_case!(
+            "reduce([1], 0, 1)",
+            ObjectType::ErrorObj(
+                "third argument to `reduce` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_scan() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "scan([1, 2, 3], 0, fn(a, x) { a + x })",
+            vec![0.0, 1.0, 3.0, 6.0]
+        ),
+        vm_test_case!("let f = fn(a, x) { a + x }; scan([], 5, f)", vec![5.0]),
+        vm_test_case!(
+            r#"scan(["b", "c"], "a", fn(acc, s) { acc + s })"#,
+            vec!["a", "ab", "abc"]
+        ),
+        vm_test_case!(
+            "scan([1, 2], 0, fn(a) { a })",
+            ObjectType::ErrorObj(
+                "function passed to `scan` must take 2 arguments, takes 1".to_string()
+            )
+        ),
+        vm_test_case!(
+            "scan(1, 0, fn(a, x) { a })",
+            ObjectType::ErrorObj("first argument to `scan` must be ARRAY, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            "scan([1], 0, 1)",
+            ObjectType::ErrorObj(
+                "third argument to `scan` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_compare() {
+    run_vm_tests(vec![
+        vm_test_case!("compare(1, 2)", ObjectType::IntegerObj(-1)),
+        vm_test_case!(r#"compare("b", "a")"#, ObjectType::IntegerObj(1)),
+        vm_test_case!("compare(3, 3)", ObjectType::IntegerObj(0)),
+        vm_test_case!("compare(5 / 2, 2)", ObjectType::IntegerObj(1)),
+        vm_test_case!(
+            r#"compare(1, "a")"#,
+            ObjectType::ErrorObj("cannot compare INTEGER and STRING".to_string())
+        ),
+        vm_test_case!(
+            "compare(true, false)",
+            ObjectType::ErrorObj("cannot compare BOOLEAN and BOOLEAN".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_sort() {
+    run_vm_tests(vec![
+        vm_test_case!("sort([3, 1, 2])", vec![1.0, 2.0, 3.0]),
+        vm_test_case!("sort([])", Vec::<f64>::new()),
+        vm_test_case!(r#"sort(["b", "c", "a"])"#, vec!["a", "b", "c"]),
+        vm_test_case!(
+            "sort([3, 1, 2], fn(a, b) { compare(b, a) })",
+            vec![3.0, 2.0, 1.0]
+        ),
+        vm_test_case!("sort([3, 1, 2], fn(a, b) { a - b })", vec![1.0, 2.0, 3.0]),
+        // ties keep their order
+        vm_test_case!(
+            r#"sort(["bb", "a", "cc", "d"], fn(a, b) { len(a) - len(b) })"#,
+            vec!["a", "d", "bb", "cc"]
+        ),
+        vm_test_case!(
+            r#"
+            let people = [
+                {"name": "ann", "age": 30},
+                {"name": "bob", "age": 25},
+                {"name": "cat", "age": 30},
+                {"name": "dan", "age": 25}
+            ];
+            let by_age = sort(people, fn(a, b) { a["age"] - b["age"] });
+            map(by_age, fn(p) { p["name"] })
+            "#,
+            vec!["bob", "dan", "ann", "cat"]
+        ),
+        vm_test_case!(
+            r#"sort([1, "a"])"#,
+            ObjectType::ErrorObj("cannot sort INTEGER and STRING together".to_string())
+        ),
+        vm_test_case!(
+            r#"sort([1, 2], fn(a, b) { "no" })"#,
+            ObjectType::ErrorObj(
+                "comparator for `sort` must return a number, got STRING".to_string()
+            )
+        ),
+        vm_test_case!(
+            "sort([1, 2], 3)",
+            ObjectType::ErrorObj(
+                "second argument to `sort` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+    ]);
+
+    // integers and floats sort together, each keeping its type
+    assert_eq!(
+        test_utils::run("sort([2, 5 / 2, 1])"),
+        ObjectType::ArrayObj(vec![
+            ObjectType::IntegerObj(1),
+            ObjectType::IntegerObj(2),
+            ObjectType::FloatObj(2.5),
+        ])
+    );
+}
+
+#[test]
+fn test_is_sorted() {
+    run_vm_tests(vec![
+        vm_test_case!("is_sorted([1, 2, 2, 3])", true),
+        vm_test_case!("is_sorted([3, 1])", false),
+        vm_test_case!("is_sorted([])", true),
+        vm_test_case!("is_sorted([1])", true),
+        vm_test_case!("is_sorted([1, 3 / 2, 2])", true),
+        vm_test_case!(r#"is_sorted(["a", "b", "b"])"#, true),
+        vm_test_case!(r#"is_sorted(["b", "a"])"#, false),
+        vm_test_case!("is_sorted(sort([5, 3, 4]))", true),
+        vm_test_case!(
+            r#"is_sorted([1, "a"])"#,
+            ObjectType::ErrorObj("cannot compare INTEGER and STRING".to_string())
+        ),
+        vm_test_case!(
+            "is_sorted(1)",
+            ObjectType::ErrorObj("argument to `is_sorted` must be ARRAY, got INTEGER".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_shuffle() {
+    let input = "seed(1); shuffle([1, 2, 3, 4, 5, 6, 7, 8])";
+    let shuffled = test_utils::run(input);
+    assert_eq!(test_utils::run(input), shuffled);
+
+    let ObjectType::ArrayObj(items) = &shuffled else {
+        panic!("expected an array, got {shuffled:?}");
+    };
+    let mut sorted = items
+        .iter()
+        .map(|item| match item {
+            ObjectType::IntegerObj(i) => *i,
+            other => panic!("expected an integer, got {other:?}"),
+        })
+        .collect::<Vec<_>>();
+    sorted.sort();
+    assert_eq!(sorted, (1..=8).collect::<Vec<_>>());
+
+    // another seed gives another order, and the generator moves on between calls
+    assert_ne!(
+        test_utils::run("seed(2); shuffle([1, 2, 3, 4, 5, 6, 7, 8])"),
+        shuffled
+    );
+    assert_ne!(
+        test_utils::run(
+            "seed(1); shuffle([1, 2, 3, 4, 5, 6, 7, 8]); shuffle([1, 2, 3, 4, 5, 6, 7, 8])"
+        ),
+        shuffled
+    );
+
+    run_vm_tests(vec![
+        vm_test_case!("shuffle([])", Vec::<f64>::new()),
+        vm_test_case!("shuffle([7])", vec![7.0]),
+        vm_test_case!("let a = [1, 2, 3]; shuffle(a); a", vec![1.0, 2.0, 3.0]),
+        vm_test_case!("seed(5)", NULL),
+        vm_test_case!(
+            "shuffle(1)",
+            ObjectType::ErrorObj("argument to `shuffle` must be ARRAY, got INTEGER".to_string())
+        ),
+        vm_test_case!(
+            r#"seed("1")"#,
+            ObjectType::ErrorObj("argument to `seed` must be INTEGER, got STRING".to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_keys_and_values() {
+    run_vm_tests(vec![
+        vm_test_case!("keys({1: 2, 3: 4})", vec![1.0, 3.0]),
+        vm_test_case!("values({1: 2, 3: 4})", vec![2.0, 4.0]),
+        // sorted by key, not in the order they were written
+        vm_test_case!("keys({30: 1, 10: 2, 20: 3})", vec![10.0, 20.0, 30.0]),
+        vm_test_case!("values({30: 1, 10: 2, 20: 3})", vec![2.0, 3.0, 1.0]),
+        vm_test_case!(r#"keys({"b": 1, "a": 2})"#, vec!["a", "b"]),
+        vm_test_case!("keys({})", Vec::<f64>::new()),
+        vm_test_case!(
+            "let h = {1: 1, 2: 2, 3: 3}; len(keys(h)) == len(values(h))",
+            true
+        ),
+        vm_test_case!(
+            "keys([1])",
+            ObjectType::ErrorObj("argument to `keys` must be HASH, got ARRAY".to_string())
+        ),
+        vm_test_case!(
+            "values(1)",
+            ObjectType::ErrorObj("argument to `values` must be HASH, got INTEGER".to_string())
+        ),
+    ]);
+
+    assert_eq!(
+        test_utils::run(r#"keys({"x": 1, true: 2, 5: 3})"#).to_sexpr(),
+        r#"(array (bool true) (int 5) (string "x"))"#
+    );
+}
+
+#[test]
+fn test_bytes() {
+    run_vm_tests(vec![
+        vm_test_case!(r#"bytes("A")"#, vec![65.0]),
+        vm_test_case!(r#"bytes("é")"#, vec![195.0, 169.0]),
+        vm_test_case!(r#"bytes("")"#, Vec::<f64>::new()),
+        vm_test_case!("from_bytes([72, 105])", "Hi"),
+        vm_test_case!(r#"from_bytes(bytes("héllo"))"#, "héllo"),
+        vm_test_case!(
+            "from_bytes([72, 256])",
+            ObjectType::ErrorObj("byte out of range 0-255: 256".to_string())
+        ),
+        vm_test_case!(
+            "from_bytes([-1])",
+            ObjectType::ErrorObj("byte out of range 0-255: -1".to_string())
+        ),
+        vm_test_case!(
+            r#"from_bytes(["a"])"#,
+            ObjectType::ErrorObj("bytes for `from_bytes` must be INTEGER, got STRING".to_string())
+        ),
+    ]);
+
+    assert!(matches!(
+        test_utils::run("from_bytes([195])"),
+        ObjectType::ErrorObj(msg) if msg.starts_with("invalid UTF-8 in `from_bytes`")
+    ));
+}
+
+#[test]
+fn test_hex() {
+    run_vm_tests(vec![
+        vm_test_case!("hex([255, 0])", "ff00"),
+        vm_test_case!("hex([1, 171, 16])", "01ab10"),
+        vm_test_case!("hex([])", ""),
+        vm_test_case!(r#"from_hex("ff00")"#, vec![255.0, 0.0]),
+        vm_test_case!(r#"from_hex("01AB10")"#, vec![1.0, 171.0, 16.0]),
+        vm_test_case!(r#"from_hex(hex(bytes("Hi")))"#, vec![72.0, 105.0]),
+        vm_test_case!(
+            "hex([256])",
+            ObjectType::ErrorObj("byte out of range 0-255: 256".to_string())
+        ),
+        vm_test_case!(
+            r#"from_hex("fff")"#,
+            ObjectType::ErrorObj(
+                "hex for `from_hex` must have an even number of digits, got 3".to_string()
+            )
+        ),
+        vm_test_case!(
+            r#"from_hex("zz")"#,
+            ObjectType::ErrorObj(r#"invalid hex in `from_hex`: "zz""#.to_string())
+        ),
+        vm_test_case!(
+            r#"from_hex("+f")"#,
+            ObjectType::ErrorObj(r#"invalid hex in `from_hex`: "+f""#.to_string())
+        ),
+    ]);
+}
+
+#[test]
+fn test_apply() {
+    run_vm_tests(vec![
+        vm_test_case!("apply(fn(a, b) { a + b }, [1, 2])", 3.0),
+        vm_test_case!(
+            "let add = fn(a, b, c) { a + b * c }; apply(add, [1, 2, 3])",
+            7.0
+        ),
+        vm_test_case!("apply(fn() { 5 }, [])", 5.0),
+        vm_test_case!(r#"apply(len, ["four"])"#, 4.0),
+        vm_test_case!(
+            "let n = 10; let f = fn(x) { x + n }; apply(f, [1]) + apply(f, [2])",
+            23.0
+        ),
+        vm_test_case!(
+            "apply(fn(a, b) { a + b }, [1])",
+            ObjectType::ErrorObj("wrong number of arguments: want=2; got=1".to_string())
+        ),
+        vm_test_case!(
+            "apply(fn() { 5 }, [1])",
+            ObjectType::ErrorObj("wrong number of arguments: want=0; got=1".to_string())
+        ),
+        vm_test_case!(
+            "apply(1, [])",
+            ObjectType::ErrorObj(
+                "first argument to `apply` must be FUNCTION, got INTEGER".to_string()
+            )
+        ),
+        vm_test_case!(
+            "apply(fn(a) { a }, 1)",
+            ObjectType::ErrorObj(
+                "second argument to `apply` must be ARRAY, got INTEGER".to_string()
+            )
+        ),
+    ]);
+}
+
+#[test]
+fn test_index_assignment() {
+    run_vm_tests(vec![
+        vm_test_case!("let a = [1, 2, 3]; a[0] = 9; a[0]", 9.0),
+        vm_test_case!(
+            "let a = [1, 2, 3]; a[2] = a[0] + a[1]; a",
+            vec![1.0, 2.0, 3.0]
+        ),
+        vm_test_case!("let a = [1, 2]; let b = a; b[0] = 5; a[0]", 1.0),
+        vm_test_case!(
+            "let a = [[1, 2], [3, 4]]; a[1][0] = 7; a[1]",
+            vec![7.0, 4.0]
+        ),
+        vm_test_case!(r#"let h = {"a": 1}; h["a"] = 2; h["a"]"#, 2.0),
+        vm_test_case!(r#"let h = {}; h["new"] = 3; h["new"]"#, 3.0),
+        vm_test_case!(
+            r#"let h = {"xs": [1, 2]}; h["xs"][1] = 5; h["xs"]"#,
+            vec![1.0, 5.0]
+        ),
+        vm_test_case!(
+            "let f = fn() { let a = [0, 0]; a[1] = 4; a }; f()",
+            vec![0.0, 4.0]
+        ),
+        vm_test_case!(
+            "let a = [0, 0, 0]; let i = 0; while (i < 3) { a[i] = i * 2; i++; } a",
+            vec![0.0, 2.0, 4.0]
+        ),
+        vm_test_case!("let a = [1, 2, 3]; a[-1] = 9; a", vec![1.0, 2.0, 9.0]),
+        vm_test_case!("let a = [1, 2, 3]; a[-3] = 7; a[0]", 7.0),
+    ]);
+
+    for (input, expected) in [
+        (
+            "let a = [1]; a[1] = 2;",
+            "index 1 out of bounds for ARRAY of length 1",
+        ),
+        (
+            "let a = [1]; a[-2] = 2;",
+            "index -2 out of bounds for ARRAY of length 1",
+        ),
+        (
+            "let a = 1; a[0] = 2;",
+            "index assignment not supported: INTEGER[INTEGER]",
+        ),
+    ] {
+        assert_eq!(vm_error(input), expected);
+    }
+}
+
+#[test]
+fn test_for() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "let sum = 0; for (let i = 0; i < 5; i = i + 1) { sum = sum + i } sum",
+            10.0
+        ),
+        vm_test_case!(
+            "let n = 0; for (let i = 0; i < 3; i++) { n = n + 2; } n",
+            6.0
+        ),
+        vm_test_case!("let n = 0; for (let i = 0; false; i++) { n++; } n", 0.0),
+        vm_test_case!(
+            "let n = 0; for (let i = 0; i < 10; i++) { if (i == 4) { break; }; n++; } n",
+            4.0
+        ),
+        // the inner i doesn't disturb the outer one
+        vm_test_case!(
+            r#"
+                let n = 0;
+                for (let i = 0; i < 3; i++) {
+                    for (let i = 0; i < 2; i++) { n++; }
+                }
+                n
+            "#,
+            6.0
+        ),
+        // and neither touches a variable of the same name outside
+        vm_test_case!("let i = 7; for (let i = 0; i < 3; i++) { } i", 7.0),
+        vm_test_case!(
+            "let f = fn(n) { let total = 0; for (let i = 1; i < n + 1; i++) { total = total + i; } total }; f(4)",
+            10.0
+        ),
+        vm_test_case!(
+            "let f = fn() { for (let i = 0; true; i++) { if (i == 3) { return i; }; } }; f()",
+            3.0
+        ),
+    ]);
+}
+
+#[test]
+fn test_modules() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "mod math { let pi = 3; let area = fn(r) { pi * r * r }; } math.area(2)",
+            12.0
+        ),
+        vm_test_case!("mod math { let pi = 3; } math.pi + 1", 4.0),
+        // a member's plain name is only in scope inside the mod
+        vm_test_case!("let pi = 1; mod math { let pi = 3; } pi + math.pi", 4.0),
+        vm_test_case!(
+            r#"
+                mod counter {
+                    let start = 10;
+                    let step = 2;
+                    let next = fn(n) { n + step };
+                    let first = next(start);
+                }
+                counter.first + counter.next(0)
+            "#,
+            14.0
+        ),
+        vm_test_case!(
+            "let f = fn() { mod m { let x = 5; let get = fn() { x }; } m.get() }; f()",
+            5.0
+        ),
+    ]);
+
+    for (input, expected) in [
+        (
+            "mod math { let pi = 3; } math.tau",
+            "undefined member: math.tau",
+        ),
+        ("let x = 1; x.y", "undefined member: x.y"),
+        ("mod math { let tau = 6; } tau", "undefined variable"),
+    ] {
+        let program = test_setup!(input);
+        let mut constants = Vec::new();
+        let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        assert_eq!(comp.compile(program).unwrap_err().to_string(), expected);
+    }
+}
+
+#[test]
+fn test_break_and_continue() {
+    run_vm_tests(vec![
+        vm_test_case!(
+            "let n = 0; for (let i = 0; i < 100; i++) { if (i == 5) { break; }; n = n + i; } n",
+            10.0
+        ),
+        // skips the odd numbers, and the update still runs after a continue
+        vm_test_case!(
+            "let sum = 0; for (let i = 0; i < 6; i++) { if (i % 2 == 1) { continue; }; sum = sum + i; } sum",
+            6.0
+        ),
+        vm_test_case!(
+            "let i = 0; let sum = 0; while (i < 6) { i++; if (i % 2 == 0) { continue; }; sum = sum + i; } sum",
+            9.0
+        ),
+        vm_test_case!(
+            "let i = 0; let n = 0; loop { i++; if (i > 4) { break; }; if (i == 2) { continue; }; n++; } n",
+            3.0
+        ),
+        // continue only applies to the innermost loop
+        vm_test_case!(
+            r#"
+                let n = 0;
+                for (let i = 0; i < 3; i++) {
+                    for (let j = 0; j < 3; j++) {
+                        if (j == 1) { continue; };
+                        n++;
+                    }
+                }
+                n
+            "#,
+            6.0
+        ),
+    ]);
+
+    for (input, expected) in [
+        ("continue;", "continue outside of a loop"),
+        ("break;", "break outside of a loop"),
+        // a function body isn't part of the loop around it
+        (
+            "loop { let f = fn() { break; }; break; }",
+            "break outside of a loop",
+        ),
+    ] {
+        let program = test_setup!(input);
+        let mut constants = Vec::new();
+        let mut comp = Compiler::new(&mut constants, SymbolTable::new()).unwrap();
+        assert_eq!(comp.compile(program).unwrap_err().to_string(), expected);
+    }
+}
+
+#[test]
+fn test_bytecode_round_trip() {
+    let path = std::env::temp_dir().join(format!("round_trip_{}.monkeyc", std::process::id()));
+    let tests = [
+        "let add = fn(a) { fn(b) { a + b } }; add(2)(3)",
+        r#"let h = {"one": 1, 2: [true, "two"]}; [h["one"], h[2][1], len(h)]"#,
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10) / 4",
+        r#"puts("hi"); if (false) { 1 }"#,
+    ];
+
+    for input in tests {
+        let output = test_utils::compile(input);
+        write_bytecode(&path, &output.instructions, &output.constants).unwrap();
+        let (instructions, constants) = read_bytecode(&path).unwrap();
+        assert_eq!(instructions, output.instructions);
+        assert_eq!(constants, output.constants);
+
+        let mut globals = [const { ObjectType::NullObj }; GLOBAL_SIZE];
+        let mut vm = VM::from_owned(instructions, constants, &mut globals);
+        vm.run().unwrap();
+        assert_eq!(
+            vm.last_popped_stack_elem(),
+            test_utils::run(input),
+            "{input}"
+        );
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_bad_bytecode() {
+    let path = std::env::temp_dir().join(format!("bad_{}.monkeyc", std::process::id()));
+    let output = test_utils::compile(r#"let s = "a string"; s"#);
+    write_bytecode(&path, &output.instructions, &output.constants).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+
+    for bad in [
+        b"not bytecode".to_vec(),
+        [&bytes[..7], &[99]].concat(),
+        bytes[..bytes.len() - 3].to_vec(),
+        [&bytes[..], &[0]].concat(),
+    ] {
+        std::fs::write(&path, bad).unwrap();
+        assert!(read_bytecode(&path).is_err());
+    }
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(read_bytecode(&path).is_err());
+    assert!(write_bytecode(&path, &[], &[ObjectType::Break]).is_err());
+}
@@ -37,7 +37,10 @@ pub fn execute(buffer: &str) -> String {
     }
 
     if let Ok(program) = program {
-        let mut comp = Compiler::new(&mut constants, symbol_table);
+        let mut comp = match Compiler::new(&mut constants, symbol_table) {
+            Ok(comp) => comp,
+            Err(e) => return format!("whoops! compilation failed - {e:?}"),
+        };
         if let Err(e) = comp.compile(program) {
             return format!("whoops! compilation failed - {e:?}");
         }